@@ -0,0 +1,327 @@
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+use quote::quote;
+use trino::params::Placeholders;
+
+use crate::cache::{self, CacheEntry, CacheError};
+use crate::config::{self, Config, ConfigError};
+use crate::nullability;
+use crate::output::{self, Collision, Layout, Output};
+use crate::prepare::prepare;
+use crate::utils::{column_names, generate_query_fn, generate_struct, query_params, relative_path};
+
+// File written into `out_dir` with `Layout::File`
+pub const DEFAULT_OUT_FILE: &str = "queries.rs";
+
+// Generates query types from a build script:
+//
+// trino_codegen::Builder::new()
+//     .queries("src/queries/*.sql")
+//     .out_dir(std::env::var("OUT_DIR").unwrap())
+//     .compile()
+//     .unwrap();
+//
+// and then `include!(concat!(env!("OUT_DIR"), "/queries.rs"));`. Plans come from the cache
+// unless a client is given, and even then the cache is used when the server can't be reached.
+pub struct Builder {
+    queries: Vec<String>,
+    output: Option<(PathBuf, bool)>,
+    layout: Layout,
+    cache_dir: PathBuf,
+    config: PathBuf,
+    client: Option<trino::Client>,
+    update_cache: bool,
+    cargo_metadata: bool,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Glob(glob::PatternError),
+    Io(PathBuf, io::Error),
+    Config(ConfigError),
+    Cache(CacheError),
+    // the query itself is broken, or the server rejected it
+    Query(PathBuf, trino::Error),
+    // the generated code doesn't parse, which is a trino-codegen bug
+    Syntax(syn::Error),
+    Collision(Collision),
+    NoOutput,
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Glob(e) => write!(f, "invalid query pattern: {}", e),
+            Error::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Config(e) => write!(f, "{}", e),
+            Error::Cache(e) => write!(f, "{}", e),
+            Error::Query(path, e) => write!(f, "{}: {}", path.display(), e),
+            Error::Syntax(e) => write!(f, "generated code doesn't parse: {}", e),
+            Error::Collision(e) => write!(f, "{}", e),
+            Error::NoOutput => write!(f, "no output directory set, call `out_dir`"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl Default for Builder {
+    fn default() -> Self {
+        Builder::new()
+    }
+}
+
+impl Builder {
+    pub fn new() -> Self {
+        Builder {
+            queries: Vec::new(),
+            output: None,
+            layout: Layout::File,
+            cache_dir: PathBuf::from(cache::DEFAULT_CACHE_DIR),
+            config: PathBuf::from(config::DEFAULT_CONFIG_PATH),
+            client: None,
+            update_cache: true,
+            cargo_metadata: true,
+        }
+    }
+
+    // A glob of `.sql` files, can be given more than once
+    pub fn queries(mut self, pattern: &str) -> Self {
+        self.queries.push(pattern.to_owned());
+        self
+    }
+
+    // Writes `queries.rs`, or the module directory's files for the other layouts, into `dir`
+    pub fn out_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.output = Some((dir.as_ref().to_path_buf(), true));
+        self
+    }
+
+    // Like `out_dir`, but the exact output file (or module directory)
+    pub fn out_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.output = Some((path.as_ref().to_path_buf(), false));
+        self
+    }
+
+    pub fn layout(mut self, layout: Layout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    pub fn cache_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.cache_dir = dir.as_ref().to_path_buf();
+        self
+    }
+
+    // Type mapping overrides, only read if the file exists
+    pub fn config(mut self, path: impl AsRef<Path>) -> Self {
+        self.config = path.as_ref().to_path_buf();
+        self
+    }
+
+    // Prepare queries against this server instead of only reading the cache
+    pub fn client(mut self, client: trino::Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    // Whether plans fetched from the server are written back to the cache
+    pub fn update_cache(mut self, update: bool) -> Self {
+        self.update_cache = update;
+        self
+    }
+
+    // Whether to print `cargo:rerun-if-changed` and `cargo:warning` lines, on by default
+    pub fn cargo_metadata(mut self, emit: bool) -> Self {
+        self.cargo_metadata = emit;
+        self
+    }
+
+    // Generates and writes everything, blocking on its own runtime, so it can't be called
+    // from async code; use `render` and `output::write` there
+    pub fn compile(self) -> Result<Vec<PathBuf>, Error> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| Error::Io(PathBuf::from("tokio runtime"), e))?;
+        let files = runtime.block_on(self.render())?;
+        let out = self.output_path()?;
+        output::write(&files).map_err(|e| Error::Io(out, e))?;
+        Ok(files.into_iter().map(|(path, _)| path).collect())
+    }
+
+    // Every generated file with its contents, without writing anything but the cache
+    pub async fn render(&self) -> Result<Vec<(PathBuf, String)>, Error> {
+        let config = if self.config.exists() {
+            self.rerun_if_changed(&self.config);
+            Config::load(&self.config).map_err(Error::Config)?
+        } else {
+            Config::default()
+        };
+
+        let mut output =
+            Output::new(self.layout, &absolute(&self.output_path()?)).serialize(config.serialize);
+
+        for pattern in &self.queries {
+            for file in glob::glob(pattern).map_err(Error::Glob)?.flatten() {
+                self.rerun_if_changed(&file);
+                tracing::info!("Reading file: {:#?}", file);
+                let query =
+                    std::fs::read_to_string(&file).map_err(|e| Error::Io(file.clone(), e))?;
+                let placeholders =
+                    Placeholders::parse(&query).map_err(|e| Error::Query(file.clone(), e))?;
+                let entry = self.entry(&file, &query, &placeholders).await?;
+
+                let non_null = if entry.degenerate {
+                    Vec::new()
+                } else {
+                    nullability::infer(
+                        &entry.plan,
+                        &column_names(&entry.plan),
+                        &entry.not_null_columns,
+                        &query,
+                    )
+                };
+                let generated_struct = generate_struct(&entry.plan, &file, &non_null, &config);
+
+                let generated_file = output.file_for(&file);
+                let source = file
+                    .canonicalize()
+                    .map_err(|e| Error::Io(file.clone(), e))?;
+                let sql_path = relative_path(
+                    generated_file
+                        .parent()
+                        .expect("output files are in a directory"),
+                    &source,
+                );
+                let params = query_params(&placeholders, &entry.parameters);
+                let query_fn = generate_query_fn(&file, &sql_path.to_string_lossy(), &params);
+
+                output
+                    .add(
+                        &file,
+                        &query,
+                        quote! {
+                            #generated_struct
+                            #query_fn
+                        },
+                    )
+                    .map_err(Error::Collision)?;
+            }
+        }
+
+        output.render().map_err(Error::Syntax)
+    }
+
+    async fn entry(
+        &self,
+        file: &Path,
+        query: &str,
+        placeholders: &Placeholders,
+    ) -> Result<CacheEntry, Error> {
+        if let Some(client) = &self.client {
+            match prepare(client, file, query, placeholders).await {
+                Ok(entry) => {
+                    if self.update_cache {
+                        cache::store(&self.cache_dir, &entry).map_err(Error::Cache)?;
+                    }
+                    return Ok(entry);
+                }
+                Err(trino::Error::Http(e)) if e.is_connect() || e.is_timeout() => {
+                    self.warn(&format!(
+                        "couldn't reach trino ({}), using the cached plan for {}",
+                        e,
+                        file.display()
+                    ));
+                }
+                Err(e) => return Err(Error::Query(file.to_path_buf(), e)),
+            }
+        }
+        let entry = cache::load(&self.cache_dir, file, query).map_err(Error::Cache)?;
+        self.rerun_if_changed(&cache::entry_path(&self.cache_dir, query));
+        Ok(entry)
+    }
+
+    fn output_path(&self) -> Result<PathBuf, Error> {
+        match &self.output {
+            Some((dir, true)) if self.layout == Layout::File => Ok(dir.join(DEFAULT_OUT_FILE)),
+            Some((path, _)) => Ok(path.clone()),
+            None => Err(Error::NoOutput),
+        }
+    }
+
+    fn rerun_if_changed(&self, path: &Path) {
+        if self.cargo_metadata {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+
+    fn warn(&self, message: &str) {
+        if self.cargo_metadata {
+            println!("cargo:warning={}", message);
+        }
+        tracing::warn!("{}", message);
+    }
+}
+
+// `relative_path` needs both sides absolute, and the output may not exist yet
+fn absolute(path: &Path) -> PathBuf {
+    if path.is_absolute() {
+        return path.to_path_buf();
+    }
+    let cwd = std::env::current_dir().unwrap_or_default();
+    let cwd = cwd.canonicalize().unwrap_or(cwd);
+    path.components()
+        .filter(|c| *c != Component::CurDir)
+        .fold(cwd, |acc, c| match c {
+            Component::ParentDir => acc.parent().map(Path::to_path_buf).unwrap_or(acc),
+            c => acc.join(c),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_from_cache() {
+        let dir =
+            std::env::temp_dir().join(format!("trino-codegen-builder-{}", std::process::id()));
+        let queries = dir.join("queries");
+        std::fs::create_dir_all(&queries).unwrap();
+        let source = queries.join("catalogs.sql");
+        std::fs::write(&source, "SELECT * FROM system.metadata.catalogs").unwrap();
+
+        let plan = serde_json::from_str(include_str!("../data/explain.json")).unwrap();
+        let cache_dir = dir.join("cache");
+        let entry = CacheEntry::new(&source, "SELECT * FROM system.metadata.catalogs", plan);
+        cache::store(&cache_dir, &entry).unwrap();
+
+        let out = dir.join("out");
+        let written = Builder::new()
+            .queries(&queries.join("*.sql").to_string_lossy())
+            .out_dir(&out)
+            .cache_dir(&cache_dir)
+            .cargo_metadata(false)
+            .compile()
+            .unwrap();
+
+        assert_eq!(written, vec![out.join("queries.rs")]);
+        let generated = std::fs::read_to_string(out.join("queries.rs")).unwrap();
+        assert!(generated.contains("pub struct CatalogsQueryResult"));
+        assert!(generated.contains("include_str!(\"../queries/catalogs.sql\")"));
+
+        std::fs::write(&source, "SELECT 1").unwrap();
+        let err = Builder::new()
+            .queries(&queries.join("*.sql").to_string_lossy())
+            .out_dir(&out)
+            .cache_dir(&cache_dir)
+            .cargo_metadata(false)
+            .compile()
+            .unwrap_err();
+        assert!(matches!(err, Error::Cache(CacheError::Stale { .. })));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,153 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::explain::ExplainRoot;
+use crate::nullability::NotNullColumns;
+
+pub const DEFAULT_CACHE_DIR: &str = "./.trino-codegen";
+
+// One file per query, named after the hash of its SQL, so an edited query never
+// picks up a stale plan
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub query_hash: String,
+    pub source: String,
+    pub plan: ExplainRoot,
+    // `DESCRIBE INPUT` types, one per placeholder
+    #[serde(default)]
+    pub parameters: Vec<String>,
+    #[serde(default)]
+    pub not_null_columns: NotNullColumns,
+    // see `prepare::DEGENERATE`, nullability isn't inferred from such a plan
+    #[serde(default)]
+    pub degenerate: bool,
+}
+
+impl CacheEntry {
+    pub fn new(source: &Path, sql: &str, plan: ExplainRoot) -> Self {
+        CacheEntry {
+            query_hash: hash_query(sql),
+            source: source.to_string_lossy().into_owned(),
+            plan,
+            parameters: Vec::new(),
+            not_null_columns: NotNullColumns::new(),
+            degenerate: false,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    // the query was prepared before, but its SQL has changed since
+    Stale { source: String, path: PathBuf },
+    Missing { source: String, path: PathBuf },
+}
+
+impl std::fmt::Display for CacheError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CacheError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            CacheError::Json(path, e) => write!(f, "{}: {}", path.display(), e),
+            CacheError::Stale { source, path } => write!(
+                f,
+                "{} changed since it was last prepared (no cache entry at {}), rerun trino-codegen connected to Trino",
+                source,
+                path.display()
+            ),
+            CacheError::Missing { source, path } => write!(
+                f,
+                "{} has never been prepared (no cache entry at {}), rerun trino-codegen connected to Trino",
+                source,
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CacheError {}
+
+pub fn hash_query(sql: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(sql.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+pub fn entry_path(dir: &Path, sql: &str) -> PathBuf {
+    dir.join(format!("{}.json", hash_query(sql)))
+}
+
+pub fn store(dir: &Path, entry: &CacheEntry) -> Result<(), CacheError> {
+    std::fs::create_dir_all(dir).map_err(|e| CacheError::Io(dir.to_path_buf(), e))?;
+    let path = dir.join(format!("{}.json", entry.query_hash));
+    let json =
+        serde_json::to_string_pretty(entry).map_err(|e| CacheError::Json(path.clone(), e))?;
+    std::fs::write(&path, json).map_err(|e| CacheError::Io(path, e))
+}
+
+pub fn load(dir: &Path, source: &Path, sql: &str) -> Result<CacheEntry, CacheError> {
+    let path = entry_path(dir, sql);
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let source = source.to_string_lossy().into_owned();
+            return Err(if has_entry_for(dir, &source) {
+                CacheError::Stale { source, path }
+            } else {
+                CacheError::Missing { source, path }
+            });
+        }
+        Err(e) => return Err(CacheError::Io(path, e)),
+    };
+    serde_json::from_str(&text).map_err(|e| CacheError::Json(path, e))
+}
+
+// Only used to tell an edited query apart from a brand new one in the error
+fn has_entry_for(dir: &Path, source: &str) -> bool {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return false,
+    };
+    entries.flatten().any(|entry| {
+        std::fs::read_to_string(entry.path())
+            .ok()
+            .and_then(|text| serde_json::from_str::<CacheEntry>(&text).ok())
+            .is_some_and(|cached| cached.source == source)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan() -> ExplainRoot {
+        serde_json::from_str(include_str!("../data/explain.json")).unwrap()
+    }
+
+    #[test]
+    fn test_roundtrip_and_staleness() {
+        let dir = std::env::temp_dir().join(format!("trino-codegen-cache-{}", std::process::id()));
+        let source = Path::new("src/queries/daily_sales.sql");
+
+        assert!(matches!(
+            load(&dir, source, "SELECT 1"),
+            Err(CacheError::Missing { .. })
+        ));
+
+        store(&dir, &CacheEntry::new(source, "SELECT 1", plan())).unwrap();
+        let entry = load(&dir, source, "SELECT 1").unwrap();
+        assert_eq!(entry.query_hash, hash_query("SELECT 1"));
+        assert_eq!(entry.plan.outputs.len(), plan().outputs.len());
+
+        assert!(matches!(
+            load(&dir, source, "SELECT 2"),
+            Err(CacheError::Stale { .. })
+        ));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
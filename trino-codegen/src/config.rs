@@ -0,0 +1,196 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use serde::Deserialize;
+use trino::types::TrinoType;
+
+pub const DEFAULT_CONFIG_PATH: &str = "./trino-codegen.toml";
+
+// `trino-codegen.toml`, e.g.
+//
+// serialize = true
+// insert = true
+//
+// [types]
+// decimal = { rust_type = "rust_decimal::Decimal", deserialize_with = "rust_decimal::serde::str::deserialize" }
+// "timestamp(6)" = { rust_type = "my_crate::Micros", deserialize_with = "my_crate::micros" }
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    // derive `Serialize` too, writing values the way Trino returns them
+    #[serde(default)]
+    pub serialize: bool,
+    // implement `utils::insert::InsertRow` and an `insert_batch` fn for table structs
+    #[serde(default)]
+    pub insert: bool,
+    #[serde(default)]
+    pub types: BTreeMap<String, TypeOverride>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TypeOverride {
+    pub rust_type: String,
+    // a serde `deserialize_with` function for the non-null value, the type's own
+    // `Deserialize` impl is used without one
+    pub deserialize_with: Option<String>,
+}
+
+impl TypeOverride {
+    pub fn rust_type(&self) -> TokenStream {
+        syn::parse_str::<syn::Type>(&self.rust_type)
+            .expect("validated on load")
+            .into_token_stream()
+    }
+
+    pub fn deserialize_with(&self) -> Option<TokenStream> {
+        self.deserialize_with.as_ref().map(|path| {
+            syn::parse_str::<syn::Path>(path)
+                .expect("validated on load")
+                .into_token_stream()
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+    Invalid(PathBuf, String),
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            ConfigError::Toml(path, e) => write!(f, "{}: {}", path.display(), e),
+            ConfigError::Invalid(path, msg) => write!(f, "{}: {}", path.display(), msg),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Io(path.to_path_buf(), e))?;
+        Config::from_toml(&text, path)
+    }
+
+    // Keys are normalized so `DECIMAL`, `decimal` and `Decimal` all mean the same type,
+    // `path` is only used in errors
+    pub fn from_toml(text: &str, path: &Path) -> Result<Self, ConfigError> {
+        let path = path.to_path_buf();
+        let raw: Config = toml::from_str(text).map_err(|e| ConfigError::Toml(path.clone(), e))?;
+
+        let mut types = BTreeMap::new();
+        for (key, mapping) in raw.types {
+            let ty: TrinoType = key.parse().map_err(|e| {
+                ConfigError::Invalid(path.clone(), format!("[types] `{}`: {}", key, e))
+            })?;
+            if syn::parse_str::<syn::Type>(&mapping.rust_type).is_err() {
+                return Err(ConfigError::Invalid(
+                    path,
+                    format!(
+                        "[types] `{}`: `{}` is not a rust type",
+                        key, mapping.rust_type
+                    ),
+                ));
+            }
+            if let Some(with) = &mapping.deserialize_with {
+                if syn::parse_str::<syn::Path>(with).is_err() {
+                    return Err(ConfigError::Invalid(
+                        path,
+                        format!("[types] `{}`: `{}` is not a function path", key, with),
+                    ));
+                }
+            }
+            types.insert(type_key(&ty), mapping);
+        }
+        Ok(Config {
+            serialize: raw.serialize,
+            insert: raw.insert,
+            types,
+        })
+    }
+
+    // An exact match such as `timestamp(6)` wins over the bare `timestamp`
+    pub fn lookup(&self, ty: &TrinoType) -> Option<&TypeOverride> {
+        self.types
+            .get(&type_key(ty))
+            .or_else(|| self.types.get(&base_key(ty)))
+    }
+}
+
+fn type_key(ty: &TrinoType) -> String {
+    ty.to_string().to_ascii_lowercase()
+}
+
+// The type without its parameters
+fn base_key(ty: &TrinoType) -> String {
+    let tz = |with_time_zone: &bool| {
+        if *with_time_zone {
+            " with time zone"
+        } else {
+            ""
+        }
+    };
+    match ty {
+        TrinoType::Decimal { .. } => "decimal".to_string(),
+        TrinoType::Varchar(_) => "varchar".to_string(),
+        TrinoType::Char(_) => "char".to_string(),
+        TrinoType::Time { with_time_zone, .. } => format!("time{}", tz(with_time_zone)),
+        TrinoType::Timestamp { with_time_zone, .. } => format!("timestamp{}", tz(with_time_zone)),
+        TrinoType::Other(raw) => raw
+            .split('(')
+            .next()
+            .unwrap_or(raw)
+            .trim()
+            .to_ascii_lowercase(),
+        _ => type_key(ty),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let parse = |text: &str| Config::from_toml(text, Path::new("trino-codegen.toml"));
+        let config = parse(
+            r#"
+            [types]
+            DECIMAL = { rust_type = "rust_decimal::Decimal" }
+            "timestamp(6)" = { rust_type = "time::PrimitiveDateTime", deserialize_with = "crate::de::micros" }
+            timestamp = { rust_type = "time::PrimitiveDateTime", deserialize_with = "crate::de::millis" }
+            qdigest = { rust_type = "Vec<u8>" }
+            "#,
+        )
+        .unwrap();
+
+        let with = |ty: &str| {
+            config
+                .lookup(&ty.parse().unwrap())
+                .and_then(|o| o.deserialize_with.clone())
+        };
+        assert_eq!(with("timestamp(6)").as_deref(), Some("crate::de::micros"));
+        assert_eq!(with("timestamp(3)").as_deref(), Some("crate::de::millis"));
+        assert!(config
+            .lookup(&"timestamp(3) with time zone".parse().unwrap())
+            .is_none());
+
+        let decimal = config.lookup(&"decimal(38,2)".parse().unwrap()).unwrap();
+        assert_eq!(decimal.rust_type, "rust_decimal::Decimal");
+        assert!(config.lookup(&"qdigest(bigint)".parse().unwrap()).is_some());
+
+        assert!(parse("[types]\ndecimal = { rust_type = \"not a type!\" }").is_err());
+        assert!(parse("[types]\ndecimal = { rust_type = \"u8\", with = \"x\" }").is_err());
+
+        let config = parse("serialize = true\ninsert = true").unwrap();
+        assert!(config.serialize && config.insert && config.types.is_empty());
+    }
+}
@@ -0,0 +1,166 @@
+// Renders a plan tree as a Graphviz DOT or Mermaid graph, one box per operator with its
+// table, outputs and estimates
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::explain::ExplainRoot;
+
+// Nodes list at most this many outputs, wide scans would otherwise dwarf the graph
+const MAX_OUTPUTS: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphFormat {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for GraphFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dot" => Ok(GraphFormat::Dot),
+            "mermaid" => Ok(GraphFormat::Mermaid),
+            _ => Err(format!(
+                "unknown graph format `{}`, expected dot or mermaid",
+                s
+            )),
+        }
+    }
+}
+
+pub fn render(plan: &ExplainRoot, format: GraphFormat) -> String {
+    match format {
+        GraphFormat::Dot => dot(plan),
+        GraphFormat::Mermaid => mermaid(plan),
+    }
+}
+
+// A line the renderer skips, to put notes about the plan in the output
+pub fn comment(format: GraphFormat, text: &str) -> String {
+    match format {
+        GraphFormat::Dot => format!("// {}\n", text),
+        GraphFormat::Mermaid => format!("%% {}\n", text),
+    }
+}
+
+pub fn dot(plan: &ExplainRoot) -> String {
+    let mut out = String::from("digraph plan {\n    node [shape=box, fontname=\"monospace\"];\n");
+    for (id, node, parent) in numbered(plan) {
+        let label = label(node)
+            .iter()
+            .map(|line| line.replace('\\', "\\\\").replace('"', "\\\""))
+            .collect::<Vec<_>>()
+            .join("\\l");
+        writeln!(out, "    n{} [label=\"{}\\l\"];", id, label).unwrap();
+        if let Some(parent) = parent {
+            writeln!(out, "    n{} -> n{};", parent, id).unwrap();
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+pub fn mermaid(plan: &ExplainRoot) -> String {
+    let mut out = String::from("flowchart TD\n");
+    for (id, node, parent) in numbered(plan) {
+        let label = label(node)
+            .iter()
+            .map(|line| {
+                line.replace('"', "#quot;")
+                    .replace('<', "#lt;")
+                    .replace('>', "#gt;")
+            })
+            .collect::<Vec<_>>()
+            .join("<br/>");
+        writeln!(out, "    n{}[\"{}\"]", id, label).unwrap();
+        if let Some(parent) = parent {
+            writeln!(out, "    n{} --> n{}", parent, id).unwrap();
+        }
+    }
+    out
+}
+
+// Every node with a sequential id and its parent's; plan ids aren't valid identifiers in
+// either format
+fn numbered(plan: &ExplainRoot) -> Vec<(usize, &ExplainRoot, Option<usize>)> {
+    let mut nodes = Vec::new();
+    let mut stack = vec![(plan, None)];
+    while let Some((node, parent)) = stack.pop() {
+        let id = nodes.len();
+        nodes.push((id, node, parent));
+        stack.extend(node.children.iter().rev().map(|child| (child, Some(id))));
+    }
+    nodes
+}
+
+fn label(node: &ExplainRoot) -> Vec<String> {
+    let mut lines = vec![node.name.clone()];
+    if let Some(table) = node.descriptor.table() {
+        lines.push(table.to_string());
+    }
+    for output in node.outputs.iter().take(MAX_OUTPUTS) {
+        lines.push(format!("{}: {}", output.symbol, output.r#type));
+    }
+    if node.outputs.len() > MAX_OUTPUTS {
+        lines.push(format!("... {} more", node.outputs.len() - MAX_OUTPUTS));
+    }
+    if let Some(estimate) = node.estimates.last() {
+        let value = |value: Option<f64>| match value {
+            Some(value) => format!("{:.0}", value),
+            None => "?".to_string(),
+        };
+        lines.push(format!(
+            "rows: {}, bytes: {}, cpu: {}",
+            value(estimate.output_row_count),
+            value(estimate.output_size_in_bytes),
+            value(estimate.cpu_cost)
+        ));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let plan: ExplainRoot = serde_json::from_value(serde_json::json!({
+            "id": "3",
+            "name": "Output",
+            "outputs": [{ "symbol": "name", "type": "varchar(25)" }],
+            "children": [{
+                "id": "0",
+                "name": "TableScan",
+                "descriptor": { "table": "tpch:tiny:nation" },
+                "outputs": [{ "symbol": "name", "type": "varchar(25)" }],
+                "estimates": [{ "outputRowCount": 25.0, "outputSizeInBytes": 1.5e3, "cpuCost": "NaN" }]
+            }]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            dot(&plan),
+            r#"digraph plan {
+    node [shape=box, fontname="monospace"];
+    n0 [label="Output\lname: varchar(25)\l"];
+    n1 [label="TableScan\ltpch:tiny:nation\lname: varchar(25)\lrows: 25, bytes: 1500, cpu: ?\l"];
+    n0 -> n1;
+}
+"#
+        );
+        assert_eq!(
+            mermaid(&plan),
+            r#"flowchart TD
+    n0["Output<br/>name: varchar(25)"]
+    n1["TableScan<br/>tpch:tiny:nation<br/>name: varchar(25)<br/>rows: 25, bytes: 1500, cpu: ?"]
+    n0 --> n1
+"#
+        );
+
+        let full: ExplainRoot = serde_json::from_str(include_str!("../data/explain.json")).unwrap();
+        let rendered = mermaid(&full);
+        assert_eq!(rendered.matches("-->").count(), full.nodes().count() - 1);
+    }
+}
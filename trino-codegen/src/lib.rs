@@ -1,2 +1,13 @@
+pub mod builder;
+pub mod cache;
+pub mod config;
 pub mod explain;
+pub mod graph;
+pub mod nullability;
+pub mod output;
+pub mod plan_diff;
+pub mod prepare;
+pub mod tables;
 pub mod utils;
+
+pub use builder::Builder;
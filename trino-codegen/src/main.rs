@@ -1,26 +1,94 @@
 use std::path::{Path, PathBuf};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
-use proc_macro2::{Ident, Span, TokenStream};
-use quote::quote;
-
-use tokio::io::AsyncWriteExt;
 use tokio::time::Duration;
 
-mod explain;
-mod utils;
-
-use utils::generate_struct;
-
-use crate::explain::ExplainRoot;
+use trino::params::Placeholders;
+use trino_codegen::cache;
+use trino_codegen::config::{self, Config};
+use trino_codegen::graph::{self, GraphFormat};
+use trino_codegen::output::{self, Layout};
+use trino_codegen::plan_diff::{self, Snapshot};
+use trino_codegen::prepare;
+use trino_codegen::tables::{self, Filter, Target};
+use trino_codegen::Builder;
 
 #[derive(Parser)]
 struct Cli {
+    #[clap(subcommand)]
+    command: Option<Command>,
     #[clap(short, long, default_value = "./src/queries/*.sql")]
     input_path: String,
+    /// The output file, or the module directory for the `query` and `dir` layouts
     #[clap(short, long, default_value = "./src/generated_structs.rs")]
     output_path: String,
+    /// `file`: everything in one file, `query`: a module per query, `dir`: a module per
+    /// directory of queries
+    #[clap(long, default_value = "file")]
+    layout: Layout,
+    /// Exit with an error if the generated code is out of date, without writing anything.
+    /// Queries are generated from the plan cache, as with `--offline`
+    #[clap(long, global = true)]
+    check: bool,
+    /// Generate purely from the plan cache, without contacting Trino
+    #[clap(long)]
+    offline: bool,
+    #[clap(long, default_value = cache::DEFAULT_CACHE_DIR)]
+    cache_dir: String,
+    /// Type mapping overrides, only read if the file exists
+    #[clap(long, global = true, default_value = config::DEFAULT_CONFIG_PATH)]
+    config: String,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Generate structs mirroring a table, or every table and view of a schema
+    Table {
+        /// `catalog.schema.table`, or `catalog.schema` for the whole schema
+        target: Target,
+        #[clap(short, long, default_value = "./src/tables.rs")]
+        output_path: String,
+        /// Only tables matching one of these glob patterns
+        #[clap(long)]
+        include: Vec<String>,
+        /// Skip tables matching one of these glob patterns
+        #[clap(long)]
+        exclude: Vec<String>,
+    },
+    /// Render the plan of a query as a graph
+    Explain {
+        /// A `.sql` file, parameters are explained as NULLs of their type
+        query: PathBuf,
+        /// `dot` for Graphviz or `mermaid`
+        #[clap(long, default_value = "dot")]
+        format: GraphFormat,
+        /// Written to stdout without one
+        #[clap(short, long)]
+        output_path: Option<PathBuf>,
+    },
+    /// Snapshot the plans of the queries and compare snapshots, e.g. across a Trino upgrade
+    PlanDiff {
+        #[clap(subcommand)]
+        action: PlanDiffAction,
+    },
+}
+
+#[derive(Subcommand)]
+enum PlanDiffAction {
+    /// Explain every query matching `--input-path` and save the plans
+    Capture {
+        #[clap(short, long, default_value = "./plans.json")]
+        output_path: PathBuf,
+    },
+    /// Print the queries whose plans differ between two snapshots
+    Compare {
+        old: PathBuf,
+        new: PathBuf,
+        /// Report estimates that moved by more than this fraction of the old value
+        #[clap(long, default_value = "0.5")]
+        estimate_threshold: f64,
+    },
 }
 
 #[tokio::main]
@@ -29,75 +97,161 @@ async fn main() {
 
     let args = Cli::parse();
 
-    let password = rpassword::prompt_password("Your Trino password: ").unwrap();
-
-    let client = trino::ClientBuilder::default()
-        .password(&password)
-        .timeout(Duration::from_secs(20))
-        .build();
-
-    let mut outfile = tokio::fs::File::create(&args.output_path)
-        .await
-        .expect("Failed to create output file");
-
-    outfile
-        .write_all(b"// this file was autogenerated by trino-codegen, don't touch!\n")
-        .await
-        .unwrap();
-
-    let imports = vec![
-        "use serde::Deserialize;\n",
-        "use trino_codegen::utils::dates;\n",
-        "use trino_codegen::utils::binary;\n",
-        "use trino_codegen::utils::array;\n",
-    ];
-
-    for import in &imports {
-        outfile.write_all(import.as_bytes()).await.unwrap();
+    if let Some(Command::Table {
+        target,
+        output_path,
+        include,
+        exclude,
+    }) = &args.command
+    {
+        let filter = Filter::new(include, exclude).unwrap_or_else(|e| fail(e));
+        let config_path = Path::new(&args.config);
+        let config = if config_path.exists() {
+            Config::load(config_path).unwrap_or_else(|e| fail(e))
+        } else {
+            Config::default()
+        };
+        let tables = tables::describe(&client(args.check), target, &filter)
+            .await
+            .unwrap_or_else(|e| fail(e));
+        if tables.is_empty() {
+            fail(format!("no tables found in {}", target));
+        }
+        let rendered = tables::render(&tables, &config).unwrap_or_else(|e| fail(e));
+        finish(args.check, &[(PathBuf::from(output_path), rendered)]);
+        return;
     }
 
-    for file in glob::glob(&args.input_path)
-        .expect("Failed to read glob pattern")
-        .flatten()
+    if let Some(Command::Explain {
+        query,
+        format,
+        output_path,
+    }) = &args.command
     {
-        tracing::info!("Reading file: {:#?}", file);
-        let query = tokio::fs::read_to_string(&file)
+        let sql = std::fs::read_to_string(query)
+            .unwrap_or_else(|e| fail(format!("{}: {}", query.display(), e)));
+        let placeholders = Placeholders::parse(&sql).unwrap_or_else(|e| fail(e));
+        let explained = prepare::explain(&client(args.check), &sql, &placeholders)
             .await
-            .expect("Failed to read file");
-
-        if file.file_name().unwrap().to_string_lossy().contains("test") {
-            let res: Vec<serde_json::Value> = client
-                .query(&query)
-                .await
-                .expect("Query failed to return results");
-            tracing::debug!("res: {:#?}", res);
+            .unwrap_or_else(|e| fail(e));
+        let mut rendered = graph::render(&explained.plan, *format);
+        if explained.degenerate {
+            eprintln!("warning: {}", prepare::DEGENERATE);
+            rendered =
+                graph::comment(*format, &format!("warning: {}", prepare::DEGENERATE)) + &rendered;
         }
+        match output_path {
+            Some(path) => std::fs::write(path, rendered)
+                .unwrap_or_else(|e| fail(format!("{}: {}", path.display(), e))),
+            None => print!("{}", rendered),
+        }
+        return;
+    }
 
-        let res: ExplainRoot = client
-            .query_once(format!("EXPLAIN (TYPE LOGICAL, FORMAT JSON) {}", query).as_str())
-            .await
-            .expect("Query failed to return results");
+    if let Some(Command::PlanDiff { action }) = &args.command {
+        plan_diff(&args, action).await;
+        return;
+    }
+
+    let mut builder = Builder::new()
+        .queries(&args.input_path)
+        .out_path(&args.output_path)
+        .layout(args.layout)
+        .cache_dir(&args.cache_dir)
+        .config(&args.config)
+        .update_cache(!args.check)
+        .cargo_metadata(false);
 
-        let generated_struct = generate_struct(&res, &file);
+    // `--check` compares against the committed plan cache, so it never needs Trino
+    if !args.offline && !args.check {
+        builder = builder.client(client(false));
+    }
 
-        tracing::info!("Generated struct: {:#?}", generated_struct);
-        tracing::info!("Writing to file: {:#?}", args.output_path);
+    let files = builder.render().await.unwrap_or_else(|e| fail(e));
+    finish(args.check, &files);
+}
 
-        let amt = outfile
-            .write(generated_struct.to_string().as_bytes())
-            .await
-            .expect("Failed to write to output file");
+async fn plan_diff(args: &Cli, action: &PlanDiffAction) {
+    match action {
+        PlanDiffAction::Capture { output_path } => {
+            let snapshot = Snapshot::capture(&client(args.check), &args.input_path)
+                .await
+                .unwrap_or_else(|e| fail(e));
+            tracing::info!(
+                "Captured {} plans to {:#?}",
+                snapshot.plans.len(),
+                output_path
+            );
+            snapshot.save(output_path).unwrap_or_else(|e| fail(e));
+        }
+        PlanDiffAction::Compare {
+            old,
+            new,
+            estimate_threshold,
+        } => {
+            let old = Snapshot::load(old).unwrap_or_else(|e| fail(e));
+            let new = Snapshot::load(new).unwrap_or_else(|e| fail(e));
+            if old.trino_version != new.trino_version {
+                println!(
+                    "Trino {} -> {}\n",
+                    old.trino_version.as_deref().unwrap_or("?"),
+                    new.trino_version.as_deref().unwrap_or("?")
+                );
+            }
+            let diffs = plan_diff::diff(&old, &new, *estimate_threshold);
+            for diff in &diffs {
+                println!("{}", diff);
+            }
+            // like the generated code, `--check` fails when something changed
+            if args.check && !diffs.is_empty() {
+                std::process::exit(1);
+            }
+        }
+    }
+}
 
-        tracing::info!("Wrote {:#?} bytes to file", amt);
-        // Write a newline to the output file
-        let _ = outfile
-            .write(b"\n")
-            .await
-            .expect("Failed to write to output file");
+// With `check` the password has to come from TRINO_PASSWORD, an unattended check shouldn't
+// wait on a prompt
+fn client(check: bool) -> trino::Client {
+    match std::env::var("TRINO_PORT").map(|port| port.parse::<u32>()) {
+        Ok(Ok(_)) => {}
+        Ok(Err(_)) => fail("TRINO_PORT must be an integer"),
+        Err(_) => fail("TRINO_PORT must be set to connect to Trino"),
+    }
+    let password = match std::env::var("TRINO_PASSWORD") {
+        Ok(password) if check => password,
+        Err(_) if check => fail("--check never prompts for a password, set TRINO_PASSWORD"),
+        _ => rpassword::prompt_password("Your Trino password: ").unwrap_or_else(|e| fail(e)),
+    };
+    trino::ClientBuilder::default()
+        .password(&password)
+        .timeout(Duration::from_secs(20))
+        .build()
+}
 
-        outfile
-            .flush()
-            .await
-            .expect("Failed to flush output file to disk");
+fn fail(e: impl std::fmt::Display) -> ! {
+    eprintln!("error: {}", e);
+    std::process::exit(1);
+}
+
+// With `check`, only reports files that are out of date
+fn finish(check: bool, files: &[(PathBuf, String)]) {
+    if check {
+        let stale = output::check(files);
+        for path in &stale {
+            eprintln!(
+                "error: {} is out of date, rerun trino-codegen",
+                path.display()
+            );
+        }
+        if !stale.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    for (path, _) in files {
+        tracing::info!("Writing to file: {:#?}", path);
     }
+    output::write(files).expect("Failed to write generated code");
 }
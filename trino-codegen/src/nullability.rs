@@ -0,0 +1,601 @@
+// Works out which output columns can never be NULL by following each output symbol down the
+// plan through its `symbol := expression` assignments. A column is only non-null when that can
+// be shown: a literal, `COALESCE` with a non-null argument, a counting aggregate, or a table
+// column declared NOT NULL. Anything else, anything on the outer side of a join, and anything
+// coming through a `GroupId` or `EnforceSingleRow` stays nullable. `-- @nonnull col` and `-- @nullable col` comments in the SQL override the result.
+use std::collections::BTreeMap;
+
+use trino::params::{segments, Segment};
+
+use crate::explain::{ExplainRoot, PlanNode};
+
+// NOT NULL columns of every table the plan scans, keyed by the table as the plan names it
+pub type NotNullColumns = BTreeMap<String, Vec<String>>;
+
+struct Node<'a> {
+    name: &'a str,
+    table: Option<&'a str>,
+    outputs: Vec<&'a str>,
+    details: Vec<&'a str>,
+    children: Vec<Node<'a>>,
+}
+
+impl<'a> Node<'a> {
+    fn new(node: &'a PlanNode) -> Self {
+        Node {
+            name: &node.name,
+            table: node.descriptor.table(),
+            outputs: node.outputs.iter().map(|o| o.symbol.as_str()).collect(),
+            details: node.details.iter().map(String::as_str).collect(),
+            children: node.children.iter().map(Node::new).collect(),
+        }
+    }
+
+    fn is_scan(&self) -> bool {
+        self.table.is_some()
+    }
+
+    // ROLLUP, CUBE and GROUPING SETS null out the grouping columns of subtotal rows, and a
+    // scalar subquery is NULL when it returns no rows, whatever the columns below them hold
+    fn adds_nulls(&self) -> bool {
+        matches!(self.name, "GroupId" | "EnforceSingleRow")
+    }
+
+    fn assignment(&self, symbol: &str) -> Option<&'a str> {
+        self.details.iter().find_map(|detail| {
+            let (target, expr) = detail.split_once(" := ")?;
+            if unquote(target.trim()) == symbol {
+                Some(expr.trim())
+            } else {
+                None
+            }
+        })
+    }
+}
+
+// Every table the plan reads from, as `catalog:schema.table` or however the connector names it
+pub fn scanned_tables(root: &ExplainRoot) -> Vec<String> {
+    fn collect(node: &Node<'_>, tables: &mut Vec<String>) {
+        if let Some(table) = node.table {
+            if !tables.iter().any(|t| t == table) {
+                tables.push(table.to_string());
+            }
+        }
+        for child in &node.children {
+            collect(child, tables);
+        }
+    }
+    let mut tables = Vec::new();
+    collect(&Node::new(root), &mut tables);
+    tables
+}
+
+// Splits a plan table name into catalog, schema and table, e.g. `hive:sales.orders`
+pub fn split_table(table: &str) -> Option<(&str, &str, &str)> {
+    let table = table.split_whitespace().next()?;
+    let (catalog, rest) = table.split_once(':')?;
+    let (schema, name) = rest.split_once(['.', ':'])?;
+    Some((catalog, schema, name))
+}
+
+// One entry per output column, `true` where the column can't be NULL
+pub fn infer(
+    root: &ExplainRoot,
+    column_names: &[String],
+    not_null: &NotNullColumns,
+    sql: &str,
+) -> Vec<bool> {
+    let node = Node::new(root);
+    let overrides = Overrides::parse(sql);
+    column_names
+        .iter()
+        .zip(&node.outputs)
+        .map(|(column, symbol)| {
+            if overrides.nonnull.iter().any(|c| c == column) {
+                true
+            } else if overrides.nullable.iter().any(|c| c == column) {
+                false
+            } else {
+                Inference { not_null }.symbol_below(&node, symbol)
+            }
+        })
+        .collect()
+}
+
+struct Inference<'a> {
+    not_null: &'a NotNullColumns,
+}
+
+impl Inference<'_> {
+    // Where `symbol` comes from one of `node`'s children
+    fn symbol_below(&self, node: &Node<'_>, symbol: &str) -> bool {
+        if node.adds_nulls() {
+            return false;
+        }
+        let producers: Vec<(usize, &Node<'_>)> = node
+            .children
+            .iter()
+            .enumerate()
+            .filter(|(_, child)| child.outputs.contains(&symbol))
+            .collect();
+        if producers.is_empty() {
+            return false;
+        }
+        producers.into_iter().all(|(i, child)| {
+            let outer_side = match node.name {
+                "LeftJoin" => i == 1,
+                "RightJoin" => i == 0,
+                "FullJoin" => true,
+                _ => false,
+            };
+            !outer_side && self.symbol_at(child, symbol)
+        })
+    }
+
+    // Where `symbol` is produced by `node`, either assigned there or passed up from below
+    fn symbol_at(&self, node: &Node<'_>, symbol: &str) -> bool {
+        if node.adds_nulls() {
+            return false;
+        }
+        if let Some(expr) = node.assignment(symbol) {
+            if node.is_scan() && self.column_not_null(node, expr) {
+                return true;
+            }
+            return self.expr(node, expr);
+        }
+        if node.name == "Values" {
+            return self.values_column(node, symbol);
+        }
+        self.symbol_below(node, symbol)
+    }
+
+    // Scan assignments point at a column handle such as `orderkey`, `orderkey:bigint:REGULAR`
+    // or `tpch:orderkey`, depending on the connector
+    fn column_not_null(&self, node: &Node<'_>, handle: &str) -> bool {
+        let columns = match node.table.and_then(|t| self.not_null.get(t)) {
+            Some(columns) => columns,
+            None => return false,
+        };
+        handle.split(':').any(|part| {
+            columns
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(unquote(part)))
+        })
+    }
+
+    fn values_column(&self, node: &Node<'_>, symbol: &str) -> bool {
+        let index = match node.outputs.iter().position(|s| *s == symbol) {
+            Some(index) => index,
+            None => return false,
+        };
+        !node.details.is_empty()
+            && node.details.iter().all(|row| {
+                let row = strip_parens(row.trim());
+                split_args(row)
+                    .get(index)
+                    .is_some_and(|value| self.expr(node, value))
+            })
+    }
+
+    fn expr(&self, node: &Node<'_>, expr: &str) -> bool {
+        let expr = strip_parens(expr.trim());
+        if is_literal(expr) {
+            return true;
+        }
+        if let Some((function, args)) = call(expr) {
+            return match function.to_ascii_lowercase().as_str() {
+                "count" | "count_if" | "approx_distinct" => true,
+                "coalesce" => split_args(args).iter().any(|arg| self.expr(node, arg)),
+                "cast" => args
+                    .rsplit_once(" AS ")
+                    .or_else(|| args.rsplit_once(" as "))
+                    .is_some_and(|(inner, _)| self.expr(node, inner)),
+                // the `ROW(...)` constructor, `map(...)` and friends are NULL on NULL arguments
+                "row" => true,
+                _ => false,
+            };
+        }
+        if expr.starts_with("ARRAY[") || expr.starts_with("array[") {
+            return true;
+        }
+        let symbol = unquote(expr);
+        if is_symbol(symbol) {
+            return self.symbol_below(node, symbol);
+        }
+        false
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Overrides {
+    nonnull: Vec<String>,
+    nullable: Vec<String>,
+}
+
+impl Overrides {
+    fn parse(sql: &str) -> Self {
+        let mut overrides = Overrides::default();
+        for (segment, range) in segments(sql) {
+            if segment != Segment::LineComment {
+                continue;
+            }
+            let comment = sql[range].trim_start_matches('-').trim();
+            let (target, columns) = if let Some(columns) = comment.strip_prefix("@nonnull") {
+                (&mut overrides.nonnull, columns)
+            } else if let Some(columns) = comment.strip_prefix("@nullable") {
+                (&mut overrides.nullable, columns)
+            } else {
+                continue;
+            };
+            target.extend(
+                columns
+                    .split(|c: char| c == ',' || c.is_whitespace())
+                    .filter(|c| !c.is_empty())
+                    .map(String::from),
+            );
+        }
+        overrides
+    }
+}
+
+fn unquote(s: &str) -> &str {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .unwrap_or(s)
+}
+
+fn is_symbol(s: &str) -> bool {
+    let mut chars = s.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$')
+}
+
+// Numbers, strings, booleans, typed literals like `DATE '2023-01-01'` and constants
+// the planner folded into `$literal$(...)`
+fn is_literal(expr: &str) -> bool {
+    let lower = expr.to_ascii_lowercase();
+    if lower == "true" || lower == "false" || lower.starts_with("$literal$") {
+        return true;
+    }
+    // `f64` would parse `nan` and `inf` too, which are column names here
+    let digits = expr.strip_prefix(['-', '+']).unwrap_or(expr);
+    if digits.starts_with(|c: char| c.is_ascii_digit() || c == '.')
+        && digits
+            .chars()
+            .all(|c| c.is_ascii_digit() || ".eE+-".contains(c))
+        && expr.parse::<f64>().is_ok()
+    {
+        return true;
+    }
+    if expr.starts_with('\'') && expr.ends_with('\'') && expr.len() >= 2 {
+        return true;
+    }
+    match expr.find('\'') {
+        Some(quote) if quote > 0 && expr.ends_with('\'') => expr[..quote]
+            .trim()
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || " (),".contains(c)),
+        _ => false,
+    }
+}
+
+// `name(args)` where the closing paren ends the expression
+fn call(expr: &str) -> Option<(&str, &str)> {
+    let open = expr.find('(')?;
+    let function = expr[..open].trim();
+    if function.is_empty() || !is_symbol(function) || !expr.ends_with(')') {
+        return None;
+    }
+    let args = &expr[open + 1..expr.len() - 1];
+    if matching_paren(expr, open) != Some(expr.len() - 1) {
+        return None;
+    }
+    Some((function, args))
+}
+
+fn strip_parens(mut expr: &str) -> &str {
+    while expr.starts_with('(') && matching_paren(expr, 0) == Some(expr.len() - 1) {
+        expr = expr[1..expr.len() - 1].trim();
+    }
+    expr
+}
+
+fn matching_paren(expr: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut in_string = false;
+    for (i, c) in expr.char_indices().skip_while(|(i, _)| *i < open) {
+        match c {
+            '\'' => in_string = !in_string,
+            '(' if !in_string => depth += 1,
+            ')' if !in_string => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+// Splits on top-level commas, leaving nested calls, arrays and strings intact
+fn split_args(args: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '\'' => in_string = !in_string,
+            '(' | '[' if !in_string => depth += 1,
+            ')' | ']' if !in_string => depth -= 1,
+            ',' if !in_string && depth == 0 => {
+                parts.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    let last = args[start..].trim();
+    if !last.is_empty() {
+        parts.push(last);
+    }
+    parts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plan(value: serde_json::Value) -> ExplainRoot {
+        serde_json::from_value(value).unwrap()
+    }
+
+    fn node(
+        name: &str,
+        outputs: &[&str],
+        details: &[&str],
+        children: serde_json::Value,
+    ) -> serde_json::Value {
+        let outputs: Vec<serde_json::Value> = outputs
+            .iter()
+            .map(|s| serde_json::json!({ "symbol": s, "type": "bigint" }))
+            .collect();
+        serde_json::json!({
+            "id": "1",
+            "name": name,
+            "descriptor": {},
+            "outputs": outputs,
+            "details": details,
+            "estimates": [],
+            "children": children
+        })
+    }
+
+    #[test]
+    fn infer_from_plan() {
+        let mut scan = node(
+            "TableScan",
+            &["orderkey", "comment"],
+            &[
+                "orderkey := orderkey:bigint:REGULAR",
+                "comment := comment:varchar:REGULAR",
+            ],
+            serde_json::json!([]),
+        );
+        scan["descriptor"]["table"] = "iceberg:sales.orders".into();
+        let other = {
+            let mut other = node(
+                "TableScan",
+                &["custkey"],
+                &["custkey := custkey"],
+                serde_json::json!([]),
+            );
+            other["descriptor"]["table"] = "iceberg:sales.customers".into();
+            other
+        };
+        let join = node(
+            "LeftJoin",
+            &["orderkey", "comment", "custkey"],
+            &[],
+            serde_json::json!([scan, other]),
+        );
+        let project = node(
+            "Project",
+            &["orderkey", "comment", "custkey", "expr", "expr_0", "expr_1"],
+            &[
+                "expr := COALESCE(\"comment\", '')",
+                "expr_0 := CAST(\"orderkey\" AS varchar)",
+                "expr_1 := (\"orderkey\" + \"custkey\")",
+            ],
+            serde_json::json!([join]),
+        );
+        let mut output = node(
+            "Output",
+            &["orderkey", "comment", "custkey", "expr", "expr_0", "expr_1"],
+            &[],
+            serde_json::json!([project]),
+        );
+        output["descriptor"]["columnNames"] = "[orderkey, comment, custkey, c1, c2, c3]".into();
+        let root = plan(output);
+
+        assert_eq!(
+            scanned_tables(&root),
+            vec!["iceberg:sales.orders", "iceberg:sales.customers"]
+        );
+        assert_eq!(
+            split_table("iceberg:sales.orders"),
+            Some(("iceberg", "sales", "orders"))
+        );
+
+        let mut not_null = NotNullColumns::new();
+        not_null.insert(
+            "iceberg:sales.orders".to_string(),
+            vec!["orderkey".to_string()],
+        );
+        not_null.insert(
+            "iceberg:sales.customers".to_string(),
+            vec!["custkey".to_string()],
+        );
+
+        let names: Vec<String> = ["orderkey", "comment", "custkey", "c1", "c2", "c3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(
+            infer(&root, &names, &not_null, "SELECT ..."),
+            // custkey is NOT NULL in its table but on the outer side of the join
+            vec![true, false, false, true, true, false]
+        );
+        assert_eq!(
+            infer(
+                &root,
+                &names,
+                &not_null,
+                "-- @nonnull c3, comment\n-- @nullable orderkey\nSELECT ..."
+            ),
+            vec![false, true, false, true, true, true]
+        );
+    }
+
+    #[test]
+    fn infer_literals_and_aggregates() {
+        let root: ExplainRoot =
+            serde_json::from_str(include_str!("../data/explain2.json")).unwrap();
+        let names = crate::utils::column_names(&root);
+        let inferred = infer(&root, &names, &NotNullColumns::new(), "");
+        // the plan only prints the first few values of the row, the rest can't be proven
+        let non_null: Vec<&str> = names
+            .iter()
+            .zip(&inferred)
+            .filter(|(_, n)| **n)
+            .map(|(name, _)| name.as_str())
+            .collect();
+        assert_eq!(
+            non_null,
+            vec!["decimal_type", "real_type", "double_type", "boolean_type"]
+        );
+
+        let node = Node::new(&root);
+        let inference = Inference {
+            not_null: &NotNullColumns::new(),
+        };
+        assert!(inference.expr(&node, "count(*)"));
+        assert!(inference.expr(&node, "COALESCE(NULL, DATE '2023-01-01')"));
+        assert!(!inference.expr(&node, "COALESCE(NULL, \"missing\")"));
+        assert!(!inference.expr(&node, "sum(\"x\")"));
+        assert!(!inference.expr(&node, "NULL"));
+        assert!(inference.expr(&node, "ROW(\"missing\", 1)"));
+        assert!(inference.expr(&node, "ARRAY[\"missing\"]"));
+        assert!(!inference.expr(&node, "map(\"missing\", \"missing\")"));
+        assert!(is_literal("-1.5e3"));
+        assert!(!is_literal("nan"));
+        assert!(!is_literal("infinity"));
+    }
+
+    #[test]
+    fn parse_overrides() {
+        let overrides = Overrides::parse(
+            "SELECT 'a -- @nonnull b' AS a, -- @nonnull c, d\n\
+             /* -- @nullable e */ \"--\" AS f -- @nullable f",
+        );
+        assert_eq!(
+            overrides,
+            Overrides {
+                nonnull: vec!["c".to_string(), "d".to_string()],
+                nullable: vec!["f".to_string()],
+            }
+        );
+    }
+
+    fn orders_scan() -> serde_json::Value {
+        let mut scan = node(
+            "TableScan",
+            &["orderkey"],
+            &["orderkey := orderkey:bigint:REGULAR"],
+            serde_json::json!([]),
+        );
+        scan["descriptor"]["table"] = "iceberg:sales.orders".into();
+        scan
+    }
+
+    fn orders_not_null() -> NotNullColumns {
+        let mut not_null = NotNullColumns::new();
+        not_null.insert(
+            "iceberg:sales.orders".to_string(),
+            vec!["orderkey".to_string()],
+        );
+        not_null
+    }
+
+    #[test]
+    fn infer_rollup() {
+        // SELECT orderkey, count(*) FROM orders GROUP BY ROLLUP (orderkey)
+        let group_id = node(
+            "GroupId",
+            &["orderkey$gid", "groupid"],
+            &["orderkey$gid := orderkey"],
+            serde_json::json!([orders_scan()]),
+        );
+        let aggregate = node(
+            "Aggregate",
+            &["orderkey$gid", "groupid", "count"],
+            &["count := count(*)"],
+            serde_json::json!([group_id]),
+        );
+        let mut output = node(
+            "Output",
+            &["orderkey$gid", "count"],
+            &[],
+            serde_json::json!([aggregate]),
+        );
+        output["descriptor"]["columnNames"] = "[orderkey, _col1]".into();
+        let names = vec!["orderkey".to_string(), "_col1".to_string()];
+
+        assert_eq!(
+            infer(&plan(output), &names, &orders_not_null(), ""),
+            // the subtotal row has a NULL orderkey
+            vec![false, true]
+        );
+    }
+
+    #[test]
+    fn infer_scalar_subquery() {
+        // SELECT (SELECT orderkey FROM orders WHERE orderkey = 1)
+        let filter = node(
+            "Filter",
+            &["orderkey"],
+            &[],
+            serde_json::json!([orders_scan()]),
+        );
+        let single = node(
+            "EnforceSingleRow",
+            &["orderkey"],
+            &[],
+            serde_json::json!([filter]),
+        );
+        let values = node("Values", &[], &["()"], serde_json::json!([]));
+        let join = node(
+            "CrossJoin",
+            &["orderkey"],
+            &[],
+            serde_json::json!([values, single]),
+        );
+        let mut output = node("Output", &["orderkey"], &[], serde_json::json!([join]));
+        output["descriptor"]["columnNames"] = "[_col0]".into();
+
+        // NULL when the subquery finds no order
+        assert_eq!(
+            infer(
+                &plan(output),
+                &["_col0".to_string()],
+                &orders_not_null(),
+                ""
+            ),
+            vec![false]
+        );
+    }
+}
@@ -0,0 +1,312 @@
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::quote;
+
+use crate::cache::hash_query;
+use crate::utils::{fn_name, snake_case, unreserved};
+
+// How generated code is split into files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Layout {
+    // everything in the one output file
+    File,
+    // the output path is a directory with a `mod.rs` and one module per query
+    Query,
+    // like `Query`, but one module per directory of queries
+    Dir,
+}
+
+impl FromStr for Layout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "file" => Ok(Layout::File),
+            "query" => Ok(Layout::Query),
+            "dir" => Ok(Layout::Dir),
+            _ => Err(format!(
+                "unknown layout `{}`, expected file, query or dir",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct Source {
+    pub(crate) path: String,
+    pub(crate) hash: String,
+}
+
+// Two queries whose code would end up in the same module although the layout gives them
+// separate ones, e.g. `a/daily.sql` and `b/daily.sql` with `Layout::Query`
+#[derive(Debug)]
+pub struct Collision {
+    pub file: PathBuf,
+    pub first: PathBuf,
+    pub second: PathBuf,
+}
+
+impl std::fmt::Display for Collision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} and {} would both be generated into {}, rename one of them",
+            self.first.display(),
+            self.second.display(),
+            self.file.display()
+        )
+    }
+}
+
+impl std::error::Error for Collision {}
+
+#[derive(Default)]
+struct Module {
+    sources: Vec<Source>,
+    items: Vec<TokenStream>,
+}
+
+// Collects the code generated for each query, then renders every file of the layout
+pub struct Output {
+    layout: Layout,
+    path: PathBuf,
+    serialize: bool,
+    modules: BTreeMap<PathBuf, Module>,
+}
+
+impl Output {
+    // `path` is the output file for `Layout::File`, the module directory otherwise
+    pub fn new(layout: Layout, path: &Path) -> Self {
+        Output {
+            layout,
+            path: path.to_path_buf(),
+            serialize: false,
+            modules: BTreeMap::new(),
+        }
+    }
+
+    // Whether the generated structs derive `Serialize`, see `Config::serialize`
+    pub fn serialize(mut self, serialize: bool) -> Self {
+        self.serialize = serialize;
+        self
+    }
+
+    // The file the code for the query at `source` ends up in, which is what
+    // `include_str!` paths in it are relative to
+    pub fn file_for(&self, source: &Path) -> PathBuf {
+        match self.layout {
+            Layout::File => self.path.clone(),
+            Layout::Query => self.path.join(format!("{}.rs", fn_name(source))),
+            Layout::Dir => {
+                let dir = source
+                    .parent()
+                    .and_then(Path::file_name)
+                    .map(|name| unreserved(snake_case(&name.to_string_lossy())))
+                    .unwrap_or_else(|| "queries".to_string());
+                self.path.join(format!("{}.rs", dir))
+            }
+        }
+    }
+
+    // What a module is generated from: a query, or a directory of them
+    fn origin<'a>(&self, source: &'a Path) -> Option<&'a Path> {
+        match self.layout {
+            Layout::File => None,
+            Layout::Query => Some(source),
+            Layout::Dir => source.parent(),
+        }
+    }
+
+    pub fn add(&mut self, source: &Path, sql: &str, items: TokenStream) -> Result<(), Collision> {
+        let file = self.file_for(source);
+        if let Some(first) = self.modules.get(&file).and_then(|m| m.sources.first()) {
+            let first = Path::new(&first.path);
+            if self.origin(first) != self.origin(source) {
+                return Err(Collision {
+                    file,
+                    first: first.to_path_buf(),
+                    second: source.to_path_buf(),
+                });
+            }
+        }
+        let module = self.modules.entry(file).or_default();
+        module.sources.push(Source {
+            path: source.to_string_lossy().into_owned(),
+            hash: hash_query(sql),
+        });
+        module.items.push(items);
+        Ok(())
+    }
+
+    // Every file of the output with its formatted contents
+    pub fn render(&self) -> syn::Result<Vec<(PathBuf, String)>> {
+        let mut files = Vec::new();
+        for (path, module) in &self.modules {
+            let items = &module.items;
+            let import = serde_import(self.serialize);
+            let code = quote! {
+                #import
+                #(#items)*
+            };
+            files.push((path.clone(), render_file(&module.sources, code)?));
+        }
+
+        if self.layout != Layout::File {
+            let mut sources = Vec::new();
+            let mut mods = Vec::new();
+            for (path, module) in &self.modules {
+                sources.extend(module.sources.iter().cloned());
+                let stem = path.file_stem().unwrap().to_string_lossy();
+                mods.push(Ident::new(&stem, Span::call_site()));
+            }
+            let code = quote! { #(pub mod #mods;)* };
+            files.push((self.path.join("mod.rs"), render_file(&sources, code)?));
+        }
+        Ok(files)
+    }
+}
+
+pub(crate) fn serde_import(serialize: bool) -> TokenStream {
+    if serialize {
+        quote! { use serde::{Deserialize, Serialize}; }
+    } else {
+        quote! { use serde::Deserialize; }
+    }
+}
+
+pub(crate) fn render_file(sources: &[Source], code: TokenStream) -> syn::Result<String> {
+    let file: syn::File = syn::parse2(code)?;
+    let mut out = String::from("// @generated by trino-codegen, don't edit by hand\n//\n");
+    out.push_str("// sources:\n");
+    for source in sources {
+        out.push_str(&format!("//   {} sha256:{}\n", source.path, source.hash));
+    }
+    out.push('\n');
+    out.push_str(&prettyplease::unparse(&file));
+    Ok(out)
+}
+
+pub fn write(files: &[(PathBuf, String)]) -> io::Result<()> {
+    for (path, contents) in files {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, contents)?;
+    }
+    Ok(())
+}
+
+// Files that are missing or differ from what would be written
+pub fn check(files: &[(PathBuf, String)]) -> Vec<PathBuf> {
+    files
+        .iter()
+        .filter(|(path, contents)| std::fs::read_to_string(path).ok().as_ref() != Some(contents))
+        .map(|(path, _)| path.clone())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_layouts() {
+        let item = |name: &str| {
+            let ident = Ident::new(name, Span::call_site());
+            quote! { pub struct #ident { pub a: i64 } }
+        };
+
+        let mut output = Output::new(Layout::Dir, Path::new("src/gen"));
+        output
+            .add(Path::new("sql/sales/daily.sql"), "SELECT 1", item("Daily"))
+            .unwrap();
+        output
+            .add(
+                Path::new("sql/sales/weekly.sql"),
+                "SELECT 2",
+                item("Weekly"),
+            )
+            .unwrap();
+        output
+            .add(Path::new("sql/mod/users.sql"), "SELECT 3", item("Users"))
+            .unwrap();
+        let files = output.render().unwrap();
+
+        let paths: Vec<&Path> = files.iter().map(|(p, _)| p.as_path()).collect();
+        assert_eq!(
+            paths,
+            vec![
+                Path::new("src/gen/mod_.rs"),
+                Path::new("src/gen/sales.rs"),
+                Path::new("src/gen/mod.rs")
+            ]
+        );
+        assert_eq!(
+            files[1].1,
+            format!(
+                "// @generated by trino-codegen, don't edit by hand\n//\n// sources:\n\
+                 //   sql/sales/daily.sql sha256:{}\n//   sql/sales/weekly.sql sha256:{}\n\n\
+                 use serde::Deserialize;\npub struct Daily {{\n    pub a: i64,\n}}\n\
+                 pub struct Weekly {{\n    pub a: i64,\n}}\n",
+                hash_query("SELECT 1"),
+                hash_query("SELECT 2")
+            )
+        );
+        assert!(files[2].1.ends_with("\npub mod mod_;\npub mod sales;\n"));
+
+        let output = Output::new(Layout::Query, Path::new("src/gen"));
+        assert_eq!(
+            output.file_for(Path::new("sql/2024-report.sql")),
+            Path::new("src/gen/query_2024_report.rs")
+        );
+        assert_eq!(
+            output.file_for(Path::new("sql/match.sql")),
+            Path::new("src/gen/match_.rs")
+        );
+        let output = Output::new(Layout::Dir, Path::new("src/gen"));
+        assert_eq!(
+            output.file_for(Path::new("sql/type/a.sql")),
+            Path::new("src/gen/type_.rs")
+        );
+    }
+
+    #[test]
+    fn test_module_collisions() {
+        let item = || quote! { pub struct Daily { pub a: i64 } };
+
+        let mut output = Output::new(Layout::Query, Path::new("src/gen"));
+        output
+            .add(Path::new("sql/a/daily.sql"), "SELECT 1", item())
+            .unwrap();
+        let collision = output
+            .add(Path::new("sql/b/daily.sql"), "SELECT 2", item())
+            .unwrap_err();
+        assert_eq!(collision.file, Path::new("src/gen/daily.rs"));
+        assert_eq!(collision.first, Path::new("sql/a/daily.sql"));
+        assert_eq!(collision.second, Path::new("sql/b/daily.sql"));
+
+        let mut output = Output::new(Layout::Dir, Path::new("src/gen"));
+        output
+            .add(Path::new("a/sales/daily.sql"), "SELECT 1", item())
+            .unwrap();
+        output
+            .add(Path::new("a/sales/weekly.sql"), "SELECT 2", item())
+            .unwrap();
+        assert!(output
+            .add(Path::new("b/Sales/daily.sql"), "SELECT 3", item())
+            .is_err());
+
+        let mut output = Output::new(Layout::File, Path::new("src/gen.rs"));
+        output
+            .add(Path::new("sql/a/daily.sql"), "SELECT 1", item())
+            .unwrap();
+        output
+            .add(Path::new("sql/b/weekly.sql"), "SELECT 2", item())
+            .unwrap();
+    }
+}
@@ -0,0 +1,463 @@
+// Snapshots of the plans of a set of queries, and what changed between two of them: the
+// operator tree, the join order and estimates that moved by more than a threshold. Meant
+// for Trino upgrades and view changes, where the SQL stays the same but plans don't.
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use trino::params::Placeholders;
+
+use crate::cache::hash_query;
+use crate::explain::{table_name, ExplainRoot};
+use crate::prepare;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub trino_version: Option<String>,
+    // keyed by the query file
+    pub plans: BTreeMap<String, SnapshotEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    pub query_hash: String,
+    pub plan: ExplainRoot,
+    // see `prepare::DEGENERATE`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub degenerate: bool,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Glob(glob::PatternError),
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+    Query(PathBuf, trino::Error),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Glob(e) => write!(f, "invalid query pattern: {}", e),
+            SnapshotError::Io(path, e) => write!(f, "{}: {}", path.display(), e),
+            SnapshotError::Json(path, e) => write!(f, "{}: {}", path.display(), e),
+            SnapshotError::Query(path, e) => write!(f, "{}: {}", path.display(), e),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+impl Snapshot {
+    // Explains every query matching `pattern`
+    pub async fn capture(client: &trino::Client, pattern: &str) -> Result<Self, SnapshotError> {
+        let mut snapshot = Snapshot {
+            trino_version: client
+                .query::<(String,)>(
+                    "SELECT node_version FROM system.runtime.nodes WHERE coordinator",
+                )
+                .await
+                .ok()
+                .and_then(|rows| rows.into_iter().next())
+                .map(|(version,)| version),
+            plans: BTreeMap::new(),
+        };
+        for file in glob::glob(pattern).map_err(SnapshotError::Glob)?.flatten() {
+            let sql =
+                std::fs::read_to_string(&file).map_err(|e| SnapshotError::Io(file.clone(), e))?;
+            let query = |e| SnapshotError::Query(file.clone(), e);
+            let placeholders = Placeholders::parse(&sql).map_err(query)?;
+            let explained = prepare::explain(client, &sql, &placeholders)
+                .await
+                .map_err(query)?;
+            snapshot.plans.insert(
+                file.to_string_lossy().into_owned(),
+                SnapshotEntry {
+                    query_hash: hash_query(&sql),
+                    plan: explained.plan,
+                    degenerate: explained.degenerate,
+                },
+            );
+        }
+        Ok(snapshot)
+    }
+
+    pub fn load(path: &Path) -> Result<Self, SnapshotError> {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| SnapshotError::Io(path.to_path_buf(), e))?;
+        serde_json::from_str(&text).map_err(|e| SnapshotError::Json(path.to_path_buf(), e))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), SnapshotError> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| SnapshotError::Io(parent.to_path_buf(), e))?;
+        }
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| SnapshotError::Json(path.to_path_buf(), e))?;
+        std::fs::write(path, json).map_err(|e| SnapshotError::Io(path.to_path_buf(), e))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added,
+    Removed,
+    // the plans are still compared, but may differ because of it
+    SqlChanged,
+    // either plan is degenerate, see `prepare::DEGENERATE`, so the changes after this may
+    // say little about the real plan
+    Degenerate,
+    // lines of the operator tree, prefixed with `-`, `+` or ` `
+    Operators(Vec<String>),
+    JoinOrder {
+        old: String,
+        new: String,
+    },
+    Estimate {
+        operator: String,
+        field: &'static str,
+        old: Option<f64>,
+        new: Option<f64>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryDiff {
+    pub source: String,
+    pub changes: Vec<Change>,
+}
+
+// Every query whose plan differs between `old` and `new`. Estimates are compared only when
+// the operator tree is unchanged, and reported when they moved by more than
+// `estimate_threshold` relative to the old value, 0.5 for 50%.
+pub fn diff(old: &Snapshot, new: &Snapshot, estimate_threshold: f64) -> Vec<QueryDiff> {
+    let mut sources: Vec<&String> = old.plans.keys().chain(new.plans.keys()).collect();
+    sources.sort();
+    sources.dedup();
+
+    let mut diffs = Vec::new();
+    for source in sources {
+        let changes = match (old.plans.get(source), new.plans.get(source)) {
+            (Some(_), None) => vec![Change::Removed],
+            (None, Some(_)) => vec![Change::Added],
+            (Some(old), Some(new)) => {
+                let mut changes = Vec::new();
+                if old.query_hash != new.query_hash {
+                    changes.push(Change::SqlChanged);
+                }
+                let plan_changes = diff_plans(&old.plan, &new.plan, estimate_threshold);
+                if !plan_changes.is_empty() && (old.degenerate || new.degenerate) {
+                    changes.push(Change::Degenerate);
+                }
+                changes.extend(plan_changes);
+                changes
+            }
+            (None, None) => unreachable!("sources come from the snapshots"),
+        };
+        if !changes.is_empty() {
+            diffs.push(QueryDiff {
+                source: source.clone(),
+                changes,
+            });
+        }
+    }
+    diffs
+}
+
+pub fn diff_plans(old: &ExplainRoot, new: &ExplainRoot, estimate_threshold: f64) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    let (old_shape, new_shape) = (shape(old), shape(new));
+    if old_shape != new_shape {
+        changes.push(Change::Operators(diff_lines(&old_shape, &new_shape)));
+    }
+
+    let (old_joins, new_joins) = (join_order(old), join_order(new));
+    if old_joins != new_joins {
+        changes.push(Change::JoinOrder {
+            old: old_joins.unwrap_or_default(),
+            new: new_joins.unwrap_or_default(),
+        });
+    }
+
+    if old_shape == new_shape {
+        for ((line, old_node), new_node) in old_shape.iter().zip(old.nodes()).zip(new.nodes()) {
+            let (old_estimate, new_estimate) =
+                match (old_node.estimates.last(), new_node.estimates.last()) {
+                    (Some(old), Some(new)) => (old, new),
+                    _ => continue,
+                };
+            let fields = [
+                (
+                    "rows",
+                    old_estimate.output_row_count,
+                    new_estimate.output_row_count,
+                ),
+                (
+                    "bytes",
+                    old_estimate.output_size_in_bytes,
+                    new_estimate.output_size_in_bytes,
+                ),
+                ("cpu", old_estimate.cpu_cost, new_estimate.cpu_cost),
+            ];
+            for (field, old, new) in fields {
+                let moved = match (old, new) {
+                    (Some(old), Some(new)) => (new - old).abs() > old.abs() * estimate_threshold,
+                    (old, new) => old.is_some() != new.is_some(),
+                };
+                if moved {
+                    changes.push(Change::Estimate {
+                        operator: line.trim().to_string(),
+                        field,
+                        old,
+                        new,
+                    });
+                }
+            }
+        }
+    }
+    changes
+}
+
+// One line per operator, indented by depth; ids are left out as they change between runs
+fn shape(plan: &ExplainRoot) -> Vec<String> {
+    fn walk(node: &ExplainRoot, depth: usize, lines: &mut Vec<String>) {
+        let mut line = format!("{}{}", "  ".repeat(depth), node.name);
+        if let Some(table) = node.descriptor.table() {
+            line.push(' ');
+            line.push_str(table_name(table));
+        }
+        lines.push(line);
+        for child in &node.children {
+            walk(child, depth + 1, lines);
+        }
+    }
+    let mut lines = Vec::new();
+    walk(plan, 0, &mut lines);
+    lines
+}
+
+// The tables in join order, nested the way the joins are, e.g.
+// `InnerJoin(InnerJoin(orders, customer), nation)`; `None` for a plan without joins
+fn join_order(plan: &ExplainRoot) -> Option<String> {
+    fn walk(node: &ExplainRoot) -> Option<String> {
+        if let Some(table) = node.descriptor.table() {
+            return Some(table_name(table).to_string());
+        }
+        let children: Vec<String> = node.children.iter().filter_map(walk).collect();
+        if node.name.ends_with("Join") {
+            Some(format!("{}({})", node.name, children.join(", ")))
+        } else if children.len() > 1 {
+            Some(format!("[{}]", children.join(", ")))
+        } else {
+            children.into_iter().next()
+        }
+    }
+    if plan.nodes().any(|node| node.name.ends_with("Join")) {
+        walk(plan)
+    } else {
+        None
+    }
+}
+
+// A line diff through the longest common subsequence
+fn diff_lines(old: &[String], new: &[String]) -> Vec<String> {
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            lines.push(format!("+ {}", new[j]));
+            j += 1;
+        } else {
+            lines.push(format!("- {}", old[i]));
+            i += 1;
+        }
+    }
+    lines
+}
+
+impl fmt::Display for QueryDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.source)?;
+        let estimate = |value: &Option<f64>| match value {
+            Some(value) => format!("{}", value),
+            None => "?".to_string(),
+        };
+        for change in &self.changes {
+            match change {
+                Change::Added => writeln!(f, "  new query")?,
+                Change::Removed => writeln!(f, "  query removed")?,
+                Change::SqlChanged => writeln!(f, "  SQL changed")?,
+                Change::Degenerate => writeln!(
+                    f,
+                    "  plan with NULL parameters, the planner may have folded parts of it away"
+                )?,
+                Change::Operators(lines) => {
+                    writeln!(f, "  operators:")?;
+                    for line in lines {
+                        writeln!(f, "    {}", line)?;
+                    }
+                }
+                Change::JoinOrder { old, new } => {
+                    writeln!(f, "  join order:\n    - {}\n    + {}", old, new)?
+                }
+                Change::Estimate {
+                    operator,
+                    field,
+                    old,
+                    new,
+                } => writeln!(
+                    f,
+                    "  {} {}: {} -> {}",
+                    operator,
+                    field,
+                    estimate(old),
+                    estimate(new)
+                )?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(table: &str, rows: f64) -> serde_json::Value {
+        serde_json::json!({
+            "id": table,
+            "name": "TableScan",
+            "descriptor": { "table": table },
+            "estimates": [{ "outputRowCount": rows }]
+        })
+    }
+
+    fn join(left: serde_json::Value, right: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({ "id": "j", "name": "InnerJoin", "children": [left, right] })
+    }
+
+    fn snapshot(plans: Vec<(&str, &str, serde_json::Value)>) -> Snapshot {
+        Snapshot {
+            trino_version: None,
+            plans: plans
+                .into_iter()
+                .map(|(source, sql, plan)| {
+                    let plan =
+                        serde_json::json!({ "id": "0", "name": "Output", "children": [plan] });
+                    (
+                        source.to_string(),
+                        SnapshotEntry {
+                            query_hash: hash_query(sql),
+                            plan: serde_json::from_value(plan).unwrap(),
+                            degenerate: sql.contains('?'),
+                        },
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = snapshot(vec![
+            (
+                "orders.sql",
+                "SELECT 1",
+                join(scan("hive:s:orders", 100.0), scan("hive:s:customer", 10.0)),
+            ),
+            ("same.sql", "SELECT 2", scan("hive:s:nation", 25.0)),
+            ("gone.sql", "SELECT 3", scan("hive:s:nation", 25.0)),
+        ]);
+        let new = snapshot(vec![
+            (
+                "orders.sql",
+                "SELECT 1",
+                join(scan("hive:s:customer", 10.0), scan("hive:s:orders", 100.0)),
+            ),
+            (
+                "same.sql",
+                "SELECT 2",
+                scan("hive:s:nation constraint on [x]", 30.0),
+            ),
+            ("new.sql", "SELECT 4", scan("hive:s:nation", 25.0)),
+        ]);
+
+        let diffs = diff(&old, &new, 0.5);
+        let sources: Vec<&str> = diffs.iter().map(|d| d.source.as_str()).collect();
+        assert_eq!(sources, vec!["gone.sql", "new.sql", "orders.sql"]);
+        assert_eq!(diffs[0].changes, vec![Change::Removed]);
+        assert_eq!(
+            diffs[2].changes,
+            vec![
+                Change::Operators(vec![
+                    "  Output".to_string(),
+                    "    InnerJoin".to_string(),
+                    "+     TableScan hive:s:customer".to_string(),
+                    "      TableScan hive:s:orders".to_string(),
+                    "-     TableScan hive:s:customer".to_string(),
+                ]),
+                Change::JoinOrder {
+                    old: "InnerJoin(hive:s:orders, hive:s:customer)".to_string(),
+                    new: "InnerJoin(hive:s:customer, hive:s:orders)".to_string(),
+                },
+            ]
+        );
+
+        let diffs = diff(&old, &new, 0.1);
+        let same = diffs.iter().find(|d| d.source == "same.sql").unwrap();
+        assert_eq!(
+            same.changes,
+            vec![Change::Estimate {
+                operator: "TableScan hive:s:nation".to_string(),
+                field: "rows",
+                old: Some(25.0),
+                new: Some(30.0),
+            }]
+        );
+        assert_eq!(
+            same.to_string(),
+            "same.sql\n  TableScan hive:s:nation rows: 25 -> 30\n"
+        );
+
+        let old = snapshot(vec![("p.sql", "SELECT ?", scan("hive:s:nation", 25.0))]);
+        let new = snapshot(vec![("p.sql", "SELECT ?", scan("hive:s:region", 5.0))]);
+        let diffs = diff(&old, &new, 0.5);
+        assert_eq!(diffs[0].changes[0], Change::Degenerate);
+        assert!(diff(&old, &old, 0.5).is_empty());
+    }
+
+    #[test]
+    fn test_diff_ignores_snapshot_ids() {
+        let events = |handle: &str| {
+            join(
+                scan(&format!("iceberg:web.events$data@{}", handle), 100.0),
+                scan("iceberg:web.users$data@42", 10.0),
+            )
+        };
+        let old = snapshot(vec![("events.sql", "SELECT 1", events("8123456"))]);
+        let new = snapshot(vec![(
+            "events.sql",
+            "SELECT 1",
+            events("8123999 constraint on [day]"),
+        )]);
+        assert!(diff(&old, &new, 0.5).is_empty());
+    }
+}
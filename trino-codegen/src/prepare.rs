@@ -0,0 +1,130 @@
+use std::path::Path;
+
+use trino::params::{Placeholders, STATEMENT_NAME};
+use trino::{ExplainFormat, ExplainOptions, ExplainType};
+
+use crate::cache::CacheEntry;
+use crate::explain::ExplainRoot;
+use crate::nullability::{self, NotNullColumns};
+
+// Everything codegen needs to know about a query from the server, ready to be cached
+pub async fn prepare(
+    client: &trino::Client,
+    source: &Path,
+    sql: &str,
+    placeholders: &Placeholders,
+) -> trino::Result<CacheEntry> {
+    let explained = explain(client, sql, placeholders).await?;
+    if explained.degenerate {
+        tracing::warn!(
+            "{}: {}, its columns are all left nullable",
+            source.display(),
+            DEGENERATE
+        );
+    }
+    let mut entry = CacheEntry::new(source, sql, explained.plan);
+    entry.parameters = explained.parameters;
+    entry.degenerate = explained.degenerate;
+    entry.not_null_columns = not_null_columns(client, &entry.plan).await;
+    Ok(entry)
+}
+
+pub const DEGENERATE: &str = "the server can't plan this query with its parameters unbound, \
+    so the plan is of the query with NULL parameters, which the planner may have folded \
+    predicates like `id = NULL` away in";
+
+pub struct Explained {
+    pub plan: ExplainRoot,
+    // `DESCRIBE INPUT` types, one per placeholder
+    pub parameters: Vec<String>,
+    // the plan is of the query with a typed NULL bound to every parameter, see `DEGENERATE`
+    pub degenerate: bool,
+}
+
+// A parameterized query is prepared, its inputs described, and the prepared statement
+// explained with its parameters left unbound. Servers that insist on values for them get a
+// typed NULL for every parameter instead, and the plan is marked degenerate.
+pub async fn explain(
+    client: &trino::Client,
+    query: &str,
+    placeholders: &Placeholders,
+) -> trino::Result<Explained> {
+    let options = ExplainOptions::new(ExplainType::Logical, ExplainFormat::Json);
+    if placeholders.is_empty() {
+        let plan = client.explain(query, options).await?.into_logical()?;
+        return Ok(Explained {
+            plan,
+            parameters: Vec::new(),
+            degenerate: false,
+        });
+    }
+
+    let parameters = client.describe_input(query).await?;
+    let unbound = client
+        .explain_prepared(
+            &format!("EXECUTE {}", STATEMENT_NAME),
+            &placeholders.sql,
+            options,
+        )
+        .await;
+    match unbound {
+        Ok(explain) => {
+            return Ok(Explained {
+                plan: explain.into_logical()?,
+                parameters,
+                degenerate: false,
+            })
+        }
+        Err(trino::Error::Query(e)) => {
+            tracing::debug!("can't explain with unbound parameters: {}", e)
+        }
+        Err(e) => return Err(e),
+    }
+
+    let nulls: Vec<String> = parameters
+        .iter()
+        .map(|ty| match ty.as_str() {
+            "unknown" => "NULL".to_string(),
+            ty => format!("CAST(NULL AS {})", ty),
+        })
+        .collect();
+    let plan = client
+        .explain_prepared(
+            &format!("EXECUTE {} USING {}", STATEMENT_NAME, nulls.join(", ")),
+            &placeholders.sql,
+            options,
+        )
+        .await?
+        .into_logical()?;
+    Ok(Explained {
+        plan,
+        parameters,
+        degenerate: true,
+    })
+}
+
+// Connectors that don't track NOT NULL report every column as nullable, and a table we
+// can't read metadata for just means its columns stay `Option`
+pub async fn not_null_columns(client: &trino::Client, plan: &ExplainRoot) -> NotNullColumns {
+    let mut columns = NotNullColumns::new();
+    for table in nullability::scanned_tables(plan) {
+        let (catalog, schema, name) = match nullability::split_table(&table) {
+            Some(parts) => parts,
+            None => continue,
+        };
+        let query = format!(
+            "SELECT column_name FROM {}.information_schema.columns \
+             WHERE table_schema = {} AND table_name = {} AND is_nullable = 'NO'",
+            trino::params::quote_identifier(catalog),
+            trino::params::quote_string(schema),
+            trino::params::quote_string(name),
+        );
+        match client.query::<(String,)>(&query).await {
+            Ok(rows) => {
+                columns.insert(table, rows.into_iter().map(|(c,)| c).collect());
+            }
+            Err(e) => tracing::warn!("couldn't read NOT NULL columns of {}: {}", table, e),
+        }
+    }
+    columns
+}
@@ -0,0 +1,282 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+
+use quote::quote;
+use trino::params::{quote_identifier, quote_string};
+
+use crate::cache::hash_query;
+use crate::config::Config;
+use crate::output::{render_file, serde_import, Source};
+use crate::utils::generate_table_struct;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Column {
+    pub name: String,
+    pub r#type: String,
+    pub non_null: bool,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub catalog: String,
+    pub schema: String,
+    pub name: String,
+    pub comment: Option<String>,
+    pub columns: Vec<Column>,
+}
+
+// `catalog.schema.table`, or `catalog.schema` for every table and view in the schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub catalog: String,
+    pub schema: String,
+    pub table: Option<String>,
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('.').collect();
+        match parts.as_slice() {
+            [catalog, schema] if !catalog.is_empty() && !schema.is_empty() => Ok(Target {
+                catalog: catalog.to_string(),
+                schema: schema.to_string(),
+                table: None,
+            }),
+            [catalog, schema, table]
+                if !catalog.is_empty() && !schema.is_empty() && !table.is_empty() =>
+            {
+                Ok(Target {
+                    catalog: catalog.to_string(),
+                    schema: schema.to_string(),
+                    table: Some(table.to_string()),
+                })
+            }
+            _ => Err(format!(
+                "`{}` isn't `catalog.schema.table` or `catalog.schema`",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.catalog, self.schema)?;
+        if let Some(table) = &self.table {
+            write!(f, ".{}", table)?;
+        }
+        Ok(())
+    }
+}
+
+// Glob patterns on table names, for whole-schema generation
+#[derive(Debug, Default)]
+pub struct Filter {
+    include: Vec<glob::Pattern>,
+    exclude: Vec<glob::Pattern>,
+}
+
+impl Filter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, glob::PatternError> {
+        let patterns = |raw: &[String]| {
+            raw.iter()
+                .map(|p| glob::Pattern::new(p))
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Filter {
+            include: patterns(include)?,
+            exclude: patterns(exclude)?,
+        })
+    }
+
+    // Everything is included when there are no include patterns
+    pub fn matches(&self, table: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|p| p.matches(table)))
+            && !self.exclude.iter().any(|p| p.matches(table))
+    }
+}
+
+// Column types and comments come from `DESCRIBE`, nullability from `information_schema.columns`
+pub async fn describe(
+    client: &trino::Client,
+    target: &Target,
+    filter: &Filter,
+) -> trino::Result<Vec<Table>> {
+    let catalog = quote_identifier(&target.catalog);
+    let schema = quote_string(&target.schema);
+
+    let names = match &target.table {
+        Some(table) => vec![table.clone()],
+        None => {
+            let rows: Vec<(String,)> = client
+                .query(&format!(
+                    "SELECT table_name FROM {}.information_schema.tables \
+                     WHERE table_schema = {} ORDER BY table_name",
+                    catalog, schema
+                ))
+                .await?;
+            rows.into_iter()
+                .map(|(name,)| name)
+                .filter(|name| filter.matches(name))
+                .collect()
+        }
+    };
+
+    let not_null: HashSet<(String, String)> = client
+        .query(&format!(
+            "SELECT table_name, column_name FROM {}.information_schema.columns \
+             WHERE table_schema = {} AND is_nullable = 'NO'",
+            catalog, schema
+        ))
+        .await?
+        .into_iter()
+        .collect();
+
+    let comments = table_comments(client, target).await;
+
+    let mut tables = Vec::new();
+    for name in names {
+        let rows: Vec<(String, String, String, Option<String>)> = client
+            .query(&format!(
+                "DESCRIBE {}.{}.{}",
+                catalog,
+                quote_identifier(&target.schema),
+                quote_identifier(&name)
+            ))
+            .await?;
+        let columns = rows
+            .into_iter()
+            .map(|(column, ty, _extra, comment)| Column {
+                non_null: not_null.contains(&(name.clone(), column.clone())),
+                name: column,
+                r#type: ty,
+                comment: comment.filter(|c| !c.is_empty()),
+            })
+            .collect();
+        tables.push(Table {
+            catalog: target.catalog.clone(),
+            schema: target.schema.clone(),
+            comment: comments.get(&name).cloned(),
+            name,
+            columns,
+        });
+    }
+    Ok(tables)
+}
+
+// Not every connector exposes table comments, in which case the structs just go undocumented
+async fn table_comments(client: &trino::Client, target: &Target) -> HashMap<String, String> {
+    let query = format!(
+        "SELECT table_name, comment FROM system.metadata.table_comments \
+         WHERE catalog_name = {} AND schema_name = {} AND comment IS NOT NULL",
+        quote_string(&target.catalog),
+        quote_string(&target.schema)
+    );
+    match client.query::<(String, String)>(&query).await {
+        Ok(rows) => rows.into_iter().collect(),
+        Err(e) => {
+            tracing::warn!("couldn't read table comments of {}: {}", target, e);
+            HashMap::new()
+        }
+    }
+}
+
+// One formatted file with a struct per table. The header lists every table along with a hash
+// of its columns, so a changed table shows up in the diff of the generated file.
+pub fn render(tables: &[Table], config: &Config) -> syn::Result<String> {
+    let sources: Vec<Source> = tables
+        .iter()
+        .map(|table| Source {
+            path: format!("{}.{}.{}", table.catalog, table.schema, table.name),
+            hash: hash_query(&columns_signature(table)),
+        })
+        .collect();
+    let structs = tables
+        .iter()
+        .map(|table| generate_table_struct(table, config));
+    let import = serde_import(config.serialize);
+    render_file(
+        &sources,
+        quote! {
+            #import
+            #(#structs)*
+        },
+    )
+}
+
+fn columns_signature(table: &Table) -> String {
+    table
+        .columns
+        .iter()
+        .map(|c| {
+            let null = if c.non_null { "NOT NULL" } else { "NULL" };
+            format!("{} {} {}\n", c.name, c.r#type, null)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_target_and_filter() {
+        let target: Target = "hive.sales.orders".parse().unwrap();
+        assert_eq!(target.table.as_deref(), Some("orders"));
+        assert_eq!(target.to_string(), "hive.sales.orders");
+        assert_eq!("hive.sales".parse::<Target>().unwrap().table, None);
+        assert!("hive".parse::<Target>().is_err());
+        assert!("hive..orders".parse::<Target>().is_err());
+
+        let filter = Filter::new(&["order*".to_string()], &["*_tmp".to_string()]).unwrap();
+        assert!(filter.matches("orders"));
+        assert!(!filter.matches("orders_tmp"));
+        assert!(!filter.matches("customers"));
+        assert!(Filter::default().matches("anything"));
+    }
+
+    #[test]
+    fn test_render_table() {
+        let table = Table {
+            catalog: "hive".to_string(),
+            schema: "sales".to_string(),
+            name: "order_items".to_string(),
+            comment: Some("One row per item\nof an order".to_string()),
+            columns: vec![
+                Column {
+                    name: "order_id".to_string(),
+                    r#type: "bigint".to_string(),
+                    non_null: true,
+                    comment: Some("references orders.id".to_string()),
+                },
+                Column {
+                    name: "Price".to_string(),
+                    r#type: "decimal(10,2)".to_string(),
+                    non_null: false,
+                    comment: None,
+                },
+            ],
+        };
+        let rendered = render(&[table], &Config::default()).unwrap();
+        let expected = format!(
+            "// @generated by trino-codegen, don't edit by hand\n//\n// sources:\n\
+             //   hive.sales.order_items sha256:{}\n\n\
+             use serde::Deserialize;\n\
+             /// One row per item\n\
+             /// of an order\n\
+             #[derive(Debug, Deserialize)]\n\
+             pub struct OrderItems {{\n    \
+                 /// references orders.id\n    \
+                 pub order_id: i64,\n    \
+                 #[serde(rename = \"Price\")]\n    \
+                 pub price: Option<bigdecimal::BigDecimal>,\n\
+             }}\n",
+            hash_query("order_id bigint NOT NULL\nPrice decimal(10,2) NULL\n")
+        );
+        assert_eq!(rendered, expected);
+    }
+}
@@ -1,12 +1,16 @@
-use std::path::Path;
+use std::collections::HashSet;
+use std::path::{Component, Path, PathBuf};
 
-use crate::explain::{ExplainRoot, Output};
+use crate::config::Config;
+use crate::explain::ExplainRoot;
+use crate::tables::Table;
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::quote;
-use regex::Regex;
+use trino::params::Placeholders;
+use trino::types::TrinoType;
 
 pub mod array {
-    use serde::de::{self, Deserializer};
+    use serde::de::Deserializer;
     use serde::Deserialize;
 
     #[derive(Debug, Deserialize)]
@@ -50,7 +54,7 @@ pub mod binary {
 
 pub mod dates {
 
-    use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+    use chrono::{DateTime, NaiveDate, Utc};
     use serde::de::Error;
     use serde::{Deserialize, Deserializer};
 
@@ -107,109 +111,1547 @@ pub mod dates {
     }
 }
 
-pub fn generate_struct(root: &ExplainRoot, file: &Path) -> TokenStream {
-    let varchar_re = Regex::new(r"^varchar\(\d+\)$").unwrap();
-    let decimal_re = Regex::new(r"^decimal\(\d+,\d+\)$").unwrap();
-    let timestamp_re = Regex::new(r"^timestamp\(\d+\)$").unwrap();
-    let time_re = Regex::new(r"^time\(\d+\)$").unwrap();
-    let array_re = Regex::new(r"^array\(.+\)$").unwrap();
-    let map_re = Regex::new(r"^map\(.+\)$").unwrap();
+// Deserialization for generated fields. Trino sends dates, varbinary and non-finite floats as
+// strings, and `deserialize_with` only reaches the outermost type, so every type that can
+// appear inside an array, map or row implements `TrinoDeserialize` and containers recurse
+// through it.
+pub mod de {
+    use std::collections::HashMap;
+    use std::fmt;
+    use std::hash::Hash;
+    use std::marker::PhantomData;
 
-    // when the columnNames field is set, it means that the columns are aliased in the plan
-    let colnames = &root.descriptor.column_names;
-    // Parse "[activity_date, rh_user_id_v13, ebs_account_number_v63, url_no_query_v18]" into a Vec<String>
-    let colnames_vec: Vec<String> = colnames
-        .trim_matches(|c| c == '[' || c == ']')
-        .split(',')
-        .map(|s| s.trim().to_string())
-        .collect();
+    use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+    use serde::de::{DeserializeSeed, Error, MapAccess, SeqAccess, Visitor};
+    use serde::{Deserialize, Deserializer};
+
+    pub trait TrinoDeserialize: Sized {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>;
+    }
+
+    // For `#[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]`
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+        T: TrinoDeserialize,
+    {
+        T::trino_deserialize(deserializer)
+    }
+
+    pub struct Wrap<T>(pub T);
+
+    impl<'de, T: TrinoDeserialize> Deserialize<'de> for Wrap<T> {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            T::trino_deserialize(deserializer).map(Wrap)
+        }
+    }
+
+    // Types serde already reads the way Trino writes them
+    #[macro_export]
+    macro_rules! trino_deserialize_via_serde {
+        ($($ty:ty),* $(,)?) => {
+            $(impl $crate::utils::de::TrinoDeserialize for $ty {
+                fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+                where
+                    D: serde::Deserializer<'de>,
+                {
+                    <$ty as serde::Deserialize>::deserialize(deserializer)
+                }
+            })*
+        };
+    }
+
+    trino_deserialize_via_serde!(
+        bool,
+        i8,
+        i16,
+        i32,
+        i64,
+        String,
+        serde_json::Value,
+        bigdecimal::BigDecimal,
+        uuid::Uuid,
+        std::net::IpAddr,
+    );
+
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Float {
+        Number(f64),
+        Text(String),
+    }
+
+    fn float<'de, D>(deserializer: D) -> Result<f64, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        match Float::deserialize(deserializer)? {
+            Float::Number(n) => Ok(n),
+            Float::Text(s) => match s.as_str() {
+                "NaN" => Ok(f64::NAN),
+                "Infinity" => Ok(f64::INFINITY),
+                "-Infinity" => Ok(f64::NEG_INFINITY),
+                _ => Err(Error::custom(format!(
+                    "invalid floating point value `{}`",
+                    s
+                ))),
+            },
+        }
+    }
+
+    impl TrinoDeserialize for f64 {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            float(deserializer)
+        }
+    }
+
+    impl TrinoDeserialize for f32 {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            float(deserializer).map(|f| f as f32)
+        }
+    }
+
+    impl TrinoDeserialize for Vec<u8> {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            data_encoding::BASE64
+                .decode(s.as_bytes())
+                .map_err(Error::custom)
+        }
+    }
+
+    fn parse_str<'de, D, T>(
+        deserializer: D,
+        parse: fn(&str) -> chrono::ParseResult<T>,
+    ) -> Result<T, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        parse(&s).map_err(|e| Error::custom(format!("`{}`: {}", s, e)))
+    }
+
+    impl TrinoDeserialize for NaiveDate {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            parse_str(deserializer, |s| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+        }
+    }
+
+    impl TrinoDeserialize for NaiveTime {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            parse_str(deserializer, |s| {
+                NaiveTime::parse_from_str(s, "%H:%M:%S%.f")
+            })
+        }
+    }
+
+    impl TrinoDeserialize for NaiveDateTime {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            parse_str(deserializer, |s| {
+                NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S%.f")
+            })
+        }
+    }
+
+    // Trino writes the zone the value was created in: `UTC`, a numeric offset or a region
+    impl TrinoDeserialize for DateTime<Utc> {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            timestamp_with_time_zone(&s)
+                .ok_or_else(|| Error::custom(format!("invalid timestamp with time zone `{}`", s)))
+        }
+    }
+
+    fn timestamp_with_time_zone(s: &str) -> Option<DateTime<Utc>> {
+        let (naive, zone) = s.rsplit_once(' ')?;
+        let naive = NaiveDateTime::parse_from_str(naive, "%Y-%m-%d %H:%M:%S%.f").ok()?;
+        if zone == "UTC" {
+            return Some(naive.and_utc());
+        }
+        if let Ok(offset) = zone.parse::<FixedOffset>() {
+            return offset
+                .from_local_datetime(&naive)
+                .single()
+                .map(|dt| dt.with_timezone(&Utc));
+        }
+        let tz: chrono_tz::Tz = zone.parse().ok()?;
+        tz.from_local_datetime(&naive)
+            .earliest()
+            .map(|dt| dt.with_timezone(&Utc))
+    }
+
+    // `time with time zone`, written as `01:02:03.456+05:30`
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub struct TimeWithTimeZone {
+        pub time: NaiveTime,
+        pub offset: FixedOffset,
+    }
+
+    impl TrinoDeserialize for TimeWithTimeZone {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            let parsed = s.rfind(['+', '-']).and_then(|split| {
+                let time = NaiveTime::parse_from_str(&s[..split], "%H:%M:%S%.f").ok()?;
+                let offset = s[split..].parse::<FixedOffset>().ok()?;
+                Some(TimeWithTimeZone { time, offset })
+            });
+            parsed.ok_or_else(|| Error::custom(format!("invalid time with time zone `{}`", s)))
+        }
+    }
+
+    // `interval year to month`, written as `1-2` for one year and two months
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+    pub struct IntervalYearToMonth {
+        pub months: i32,
+    }
+
+    impl TrinoDeserialize for IntervalYearToMonth {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            let (sign, unsigned) = match s.strip_prefix('-') {
+                Some(rest) => (-1, rest),
+                None => (1, s.as_str()),
+            };
+            let parsed = unsigned.split_once('-').and_then(|(years, months)| {
+                Some(years.parse::<i32>().ok()? * 12 + months.parse::<i32>().ok()?)
+            });
+            parsed
+                .map(|months| IntervalYearToMonth {
+                    months: sign * months,
+                })
+                .ok_or_else(|| Error::custom(format!("invalid interval year to month `{}`", s)))
+        }
+    }
+
+    // `interval day to second`, written as `2 03:04:05.678`
+    impl TrinoDeserialize for chrono::Duration {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            let s = String::deserialize(deserializer)?;
+            let (negative, unsigned) = match s.strip_prefix('-') {
+                Some(rest) => (true, rest),
+                None => (false, s.as_str()),
+            };
+            let parsed = unsigned.split_once(' ').and_then(|(days, time)| {
+                let days = days.parse::<i64>().ok()?;
+                let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f").ok()?;
+                Some(chrono::Duration::days(days) + (time - NaiveTime::MIN))
+            });
+            parsed
+                .map(|d| if negative { -d } else { d })
+                .ok_or_else(|| Error::custom(format!("invalid interval day to second `{}`", s)))
+        }
+    }
+
+    impl<T: TrinoDeserialize> TrinoDeserialize for Option<T> {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Option::<Wrap<T>>::deserialize(deserializer).map(|v| v.map(|w| w.0))
+        }
+    }
+
+    impl<T: TrinoDeserialize> TrinoDeserialize for Vec<T> {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Vec::<Wrap<T>>::deserialize(deserializer).map(|v| v.into_iter().map(|w| w.0).collect())
+        }
+    }
+
+    // Map keys arrive as JSON object keys, which serde_json still lets us read as numbers etc.
+    impl<K, V> TrinoDeserialize for HashMap<K, V>
+    where
+        K: TrinoDeserialize + Eq + Hash,
+        V: TrinoDeserialize,
+    {
+        fn trino_deserialize<'de, D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct MapVisitor<K, V>(PhantomData<(K, V)>);
+
+            impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+            where
+                K: TrinoDeserialize + Eq + Hash,
+                V: TrinoDeserialize,
+            {
+                type Value = HashMap<K, V>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a trino map")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(
+                    self,
+                    mut access: A,
+                ) -> Result<Self::Value, A::Error> {
+                    let mut map = HashMap::with_capacity(access.size_hint().unwrap_or(0));
+                    while let Some((Wrap(k), Wrap(v))) = access.next_entry::<Wrap<K>, Wrap<V>>()? {
+                        map.insert(k, v);
+                    }
+                    Ok(map)
+                }
+            }
+
+            deserializer.deserialize_map(MapVisitor(PhantomData))
+        }
+    }
+
+    // Decoders describe how to read one value, and compose into the shape of a column, so a
+    // generated field can mix `TrinoDeserialize` types with ones mapped in trino-codegen.toml,
+    // which can't implement `TrinoDeserialize` from the crate that uses them.
+    pub trait Decoder: Copy {
+        type Value;
+
+        fn decode<'de, D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>;
+    }
+
+    pub fn decode<'de, D, C>(deserializer: D, decoder: C) -> Result<C::Value, D::Error>
+    where
+        D: Deserializer<'de>,
+        C: Decoder,
+    {
+        decoder.decode(deserializer)
+    }
+
+    struct Seed<C>(C);
+
+    impl<'de, C: Decoder> DeserializeSeed<'de> for Seed<C> {
+        type Value = C::Value;
+
+        fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            self.0.decode(deserializer)
+        }
+    }
+
+    // Reads `T` through `TrinoDeserialize`
+    pub struct Trino<T>(PhantomData<fn() -> T>);
+
+    // Reads `T` through plain serde
+    pub struct Serde<T>(PhantomData<fn() -> T>);
+
+    macro_rules! leaf_decoder {
+        ($name:ident, $bound:path, $read:expr) => {
+            impl<T> $name<T> {
+                pub fn new() -> Self {
+                    $name(PhantomData)
+                }
+            }
+
+            impl<T> Default for $name<T> {
+                fn default() -> Self {
+                    Self::new()
+                }
+            }
+
+            impl<T> Clone for $name<T> {
+                fn clone(&self) -> Self {
+                    *self
+                }
+            }
+
+            impl<T> Copy for $name<T> {}
+
+            impl<T: $bound> Decoder for $name<T> {
+                type Value = T;
+
+                fn decode<'de, D>(self, deserializer: D) -> Result<T, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    $read(deserializer)
+                }
+            }
+        };
+    }
+
+    leaf_decoder!(Trino, TrinoDeserialize, T::trino_deserialize);
+    leaf_decoder!(Serde, serde::de::DeserializeOwned, T::deserialize);
+
+    #[derive(Clone, Copy)]
+    pub struct Nullable<C>(pub C);
+
+    impl<C: Decoder> Decoder for Nullable<C> {
+        type Value = Option<C::Value>;
+
+        fn decode<'de, D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct OptionVisitor<C>(C);
+
+            impl<'de, C: Decoder> Visitor<'de> for OptionVisitor<C> {
+                type Value = Option<C::Value>;
 
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a nullable value")
+                }
+
+                fn visit_none<E: Error>(self) -> Result<Self::Value, E> {
+                    Ok(None)
+                }
+
+                fn visit_unit<E: Error>(self) -> Result<Self::Value, E> {
+                    Ok(None)
+                }
+
+                fn visit_some<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+                where
+                    D: Deserializer<'de>,
+                {
+                    self.0.decode(deserializer).map(Some)
+                }
+            }
+
+            deserializer.deserialize_option(OptionVisitor(self.0))
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct Array<C>(pub C);
+
+    impl<C: Decoder> Decoder for Array<C> {
+        type Value = Vec<C::Value>;
+
+        fn decode<'de, D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct SeqVisitor<C>(C);
+
+            impl<'de, C: Decoder> Visitor<'de> for SeqVisitor<C> {
+                type Value = Vec<C::Value>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a trino array")
+                }
+
+                fn visit_seq<A: SeqAccess<'de>>(
+                    self,
+                    mut access: A,
+                ) -> Result<Self::Value, A::Error> {
+                    let mut values = Vec::with_capacity(access.size_hint().unwrap_or(0));
+                    while let Some(value) = access.next_element_seed(Seed(self.0))? {
+                        values.push(value);
+                    }
+                    Ok(values)
+                }
+            }
+
+            deserializer.deserialize_seq(SeqVisitor(self.0))
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    pub struct Map<K, V>(pub K, pub V);
+
+    impl<K, V> Decoder for Map<K, V>
+    where
+        K: Decoder,
+        K::Value: Eq + Hash,
+        V: Decoder,
+    {
+        type Value = HashMap<K::Value, V::Value>;
+
+        fn decode<'de, D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            struct MapVisitor<K, V>(K, V);
+
+            impl<'de, K, V> Visitor<'de> for MapVisitor<K, V>
+            where
+                K: Decoder,
+                K::Value: Eq + Hash,
+                V: Decoder,
+            {
+                type Value = HashMap<K::Value, V::Value>;
+
+                fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                    f.write_str("a trino map")
+                }
+
+                fn visit_map<A: MapAccess<'de>>(
+                    self,
+                    mut access: A,
+                ) -> Result<Self::Value, A::Error> {
+                    let mut map = HashMap::with_capacity(access.size_hint().unwrap_or(0));
+                    while let Some(key) = access.next_key_seed(Seed(self.0))? {
+                        let value = access.next_value_seed(Seed(self.1))?;
+                        map.insert(key, value);
+                    }
+                    Ok(map)
+                }
+            }
+
+            deserializer.deserialize_map(MapVisitor(self.0, self.1))
+        }
+    }
+}
+
+// The other direction, for generated structs that also derive `Serialize`: scalars are written
+// the way Trino sends them, so a serialized row deserializes back into the same struct. Nested
+// rows are written as JSON objects rather than Trino's positional arrays; serde reads both, and
+// objects are what `CAST(JSON ... AS ROW(...))` matches by field name.
+pub mod ser {
+    use std::collections::HashMap;
+
+    use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+    use serde::ser::SerializeMap;
+    use serde::{Serialize, Serializer};
+
+    use super::de::{IntervalYearToMonth, TimeWithTimeZone};
+
+    pub trait TrinoSerialize {
+        fn trino_serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer;
+    }
+
+    // For `#[serde(serialize_with = "trino_codegen::utils::ser::serialize")]`
+    pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        T: TrinoSerialize,
+        S: Serializer,
+    {
+        value.trino_serialize(serializer)
+    }
+
+    pub struct Wrap<'a, T>(pub &'a T);
+
+    impl<T: TrinoSerialize> Serialize for Wrap<'_, T> {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            self.0.trino_serialize(serializer)
+        }
+    }
+
+    #[macro_export]
+    macro_rules! trino_serialize_via_serde {
+        ($($ty:ty),* $(,)?) => {
+            $(impl $crate::utils::ser::TrinoSerialize for $ty {
+                fn trino_serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+                where
+                    S: serde::Serializer,
+                {
+                    <$ty as serde::Serialize>::serialize(self, serializer)
+                }
+            })*
+        };
+    }
+
+    trino_serialize_via_serde!(
+        bool,
+        i8,
+        i16,
+        i32,
+        i64,
+        String,
+        serde_json::Value,
+        bigdecimal::BigDecimal,
+        uuid::Uuid,
+        std::net::IpAddr,
+    );
+
+    fn float<S: Serializer>(f: f64, serializer: S) -> Result<S::Ok, S::Error> {
+        if f.is_nan() {
+            serializer.serialize_str("NaN")
+        } else if f.is_infinite() {
+            serializer.serialize_str(if f > 0.0 { "Infinity" } else { "-Infinity" })
+        } else {
+            serializer.serialize_f64(f)
+        }
+    }
+
+    impl TrinoSerialize for f64 {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            float(*self, serializer)
+        }
+    }
+
+    impl TrinoSerialize for f32 {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            float(*self as f64, serializer)
+        }
+    }
+
+    impl TrinoSerialize for Vec<u8> {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&data_encoding::BASE64.encode(self))
+        }
+    }
+
+    macro_rules! format_str {
+        ($($ty:ty => $format:literal),* $(,)?) => {
+            $(impl TrinoSerialize for $ty {
+                fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                    serializer.collect_str(&self.format($format))
+                }
+            })*
+        };
+    }
+
+    format_str!(
+        NaiveDate => "%Y-%m-%d",
+        NaiveTime => "%H:%M:%S%.f",
+        NaiveDateTime => "%Y-%m-%d %H:%M:%S%.f",
+        DateTime<Utc> => "%Y-%m-%d %H:%M:%S%.f UTC",
+    );
+
+    impl TrinoSerialize for TimeWithTimeZone {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_str(&format_args!(
+                "{}{}",
+                self.time.format("%H:%M:%S%.f"),
+                self.offset
+            ))
+        }
+    }
+
+    impl TrinoSerialize for IntervalYearToMonth {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let sign = if self.months < 0 { "-" } else { "" };
+            let months = self.months.unsigned_abs();
+            serializer.collect_str(&format_args!("{}{}-{}", sign, months / 12, months % 12))
+        }
+    }
+
+    impl TrinoSerialize for chrono::Duration {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let sign = if *self < chrono::Duration::zero() {
+                "-"
+            } else {
+                ""
+            };
+            let millis = self.abs().num_milliseconds();
+            serializer.collect_str(&format_args!(
+                "{}{} {:02}:{:02}:{:02}.{:03}",
+                sign,
+                millis / 86_400_000,
+                millis / 3_600_000 % 24,
+                millis / 60_000 % 60,
+                millis / 1_000 % 60,
+                millis % 1_000
+            ))
+        }
+    }
+
+    impl<T: TrinoSerialize> TrinoSerialize for Option<T> {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            match self {
+                Some(value) => serializer.serialize_some(&Wrap(value)),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
+
+    impl<T: TrinoSerialize> TrinoSerialize for Vec<T> {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(self.iter().map(Wrap))
+        }
+    }
+
+    impl<K: TrinoSerialize, V: TrinoSerialize> TrinoSerialize for HashMap<K, V> {
+        fn trino_serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut map = serializer.serialize_map(Some(self.len()))?;
+            for (k, v) in self {
+                map.serialize_entry(&Wrap(k), &Wrap(v))?;
+            }
+            map.end()
+        }
+    }
+
+    // A SQL literal for a value whose type can't implement `trino::ToTrinoLiteral`, such as a
+    // type mapped in trino-codegen.toml, to be wrapped in a `CAST` to the column type.
+    // Containers go through JSON, which Trino casts to arrays, maps and rows.
+    pub fn serde_literal<T: Serialize>(value: &T) -> trino::Result<String> {
+        let json = serde_json::to_value(value).map_err(|e| {
+            trino::Error::Parameter(format!("value can't be written as a trino literal: {}", e))
+        })?;
+        Ok(match json {
+            serde_json::Value::Null => "NULL".to_string(),
+            serde_json::Value::Bool(b) => trino::ToTrinoLiteral::to_literal(&b),
+            serde_json::Value::Number(n) => n.to_string(),
+            serde_json::Value::String(s) => trino::params::quote_string(&s),
+            json => format!("JSON {}", trino::params::quote_string(&json.to_string())),
+        })
+    }
+}
+
+// `INSERT` statements for generated table structs
+pub mod insert {
+    use trino::ToTrinoLiteral;
+
+    use super::de::{IntervalYearToMonth, TimeWithTimeZone};
+
+    // Trino's default `query.max-length`
+    pub const MAX_STATEMENT_LENGTH: usize = 1_000_000;
+
+    pub trait InsertRow {
+        // the quoted `catalog.schema.table`
+        const TABLE: &'static str;
+        // the quoted column list
+        const COLUMNS: &'static str;
+
+        // The comma separated values of the row, without parentheses
+        fn values(&self) -> trino::Result<String>;
+    }
+
+    pub fn cast(literal: String, trino_type: &str) -> String {
+        format!("CAST({} AS {})", literal, trino_type)
+    }
+
+    // Multi-row `INSERT ... VALUES` statements, each at most `max_length` bytes unless a
+    // single row is longer than that by itself
+    pub fn insert_statements<R: InsertRow>(
+        rows: &[R],
+        max_length: usize,
+    ) -> trino::Result<Vec<String>> {
+        let prefix = format!("INSERT INTO {} ({}) VALUES ", R::TABLE, R::COLUMNS);
+        let mut statements = Vec::new();
+        let mut current = String::new();
+        for row in rows {
+            let values = format!("({})", row.values()?);
+            if !current.is_empty() && current.len() + 2 + values.len() > max_length {
+                statements.push(std::mem::take(&mut current));
+            }
+            if current.is_empty() {
+                current.push_str(&prefix);
+            } else {
+                current.push_str(", ");
+            }
+            current.push_str(&values);
+        }
+        if !current.is_empty() {
+            statements.push(current);
+        }
+        Ok(statements)
+    }
+
+    // Returns the number of rows inserted
+    pub async fn insert_batch<R: InsertRow>(
+        client: &trino::Client,
+        rows: &[R],
+    ) -> trino::Result<u64> {
+        insert_batch_with_limit(client, rows, MAX_STATEMENT_LENGTH).await
+    }
+
+    pub async fn insert_batch_with_limit<R: InsertRow>(
+        client: &trino::Client,
+        rows: &[R],
+        max_length: usize,
+    ) -> trino::Result<u64> {
+        let mut inserted = 0;
+        for statement in insert_statements(rows, max_length)? {
+            inserted += client.execute(&statement).await?.update_count;
+        }
+        Ok(inserted)
+    }
+
+    impl ToTrinoLiteral for TimeWithTimeZone {
+        fn to_literal(&self) -> String {
+            format!("TIME '{}{}'", self.time.format("%H:%M:%S%.f"), self.offset)
+        }
+    }
+
+    impl ToTrinoLiteral for IntervalYearToMonth {
+        fn to_literal(&self) -> String {
+            let sign = if self.months < 0 { "-" } else { "" };
+            let months = self.months.unsigned_abs();
+            format!(
+                "INTERVAL '{}{}-{}' YEAR TO MONTH",
+                sign,
+                months / 12,
+                months % 12
+            )
+        }
+    }
+}
+
+// The output column names, which the plan only has in its `columnNames` descriptor,
+// e.g. "[activity_date, rh_user_id_v13, ebs_account_number_v63, url_no_query_v18]"
+pub fn column_names(root: &ExplainRoot) -> Vec<String> {
+    root.descriptor.column_names()
+}
+
+// `non_null` has one entry per column, see `nullability::infer`; columns past its end are nullable
+pub fn generate_struct(
+    root: &ExplainRoot,
+    file: &Path,
+    non_null: &[bool],
+    config: &Config,
+) -> TokenStream {
+    let colnames_vec = column_names(root);
     let root_columns = &root.outputs;
 
     if colnames_vec.len() != root_columns.len() {
         panic!("The columns and aliases are not aligned!")
     }
 
-    let struct_fields = root_columns.iter().enumerate().map(|(i, x)| {
-        let identifier = Ident::new(&colnames_vec[i], Span::call_site());
-        let (rust_data_type, attr) = match x.r#type.as_str() {
-            _ if varchar_re.is_match(x.r#type.as_str()) => {
-                (quote! { Option<String> }, TokenStream::new())
+    let fields: Vec<Field> = colnames_vec
+        .into_iter()
+        .zip(root_columns)
+        .enumerate()
+        .map(|(i, (name, output))| Field {
+            name,
+            r#type: parse_type(&output.r#type),
+            non_null: non_null.get(i).copied().unwrap_or(false),
+            doc: None,
+        })
+        .collect();
+
+    let mut structs = StructGenerator {
+        config,
+        literals: false,
+        serializing: false,
+        structs: Vec::new(),
+    };
+    structs.generate(&struct_name(file).to_string(), None, &fields, false);
+    structs.finish()
+}
+
+// A struct mirroring a table, with the table and column comments as docs
+pub fn generate_table_struct(table: &Table, config: &Config) -> TokenStream {
+    let fields: Vec<Field> = table
+        .columns
+        .iter()
+        .map(|column| Field {
+            name: column.name.clone(),
+            r#type: parse_type(&column.r#type),
+            non_null: column.non_null,
+            doc: column.comment.clone(),
+        })
+        .collect();
+
+    let ident = table_struct_name(&table.name);
+    let mut structs = StructGenerator {
+        config,
+        literals: config.insert,
+        serializing: false,
+        structs: Vec::new(),
+    };
+    let values = structs.generate(&ident.to_string(), table.comment.as_deref(), &fields, false);
+    let structs = structs.finish();
+    if !config.insert {
+        return structs;
+    }
+
+    let quote_identifier = trino::params::quote_identifier;
+    let table_name = [&table.catalog, &table.schema, &table.name]
+        .iter()
+        .map(|part| quote_identifier(part))
+        .collect::<Vec<_>>()
+        .join(".");
+    let columns = fields
+        .iter()
+        .map(|field| quote_identifier(&field.name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    quote! {
+        #structs
+        impl trino_codegen::utils::insert::InsertRow for #ident {
+            const TABLE: &'static str = #table_name;
+            const COLUMNS: &'static str = #columns;
+            fn values(&self) -> trino::Result<String> {
+                Ok([#(#values),*].join(", "))
             }
-            _ if decimal_re.is_match(x.r#type.as_str()) => (
-                quote! { Option<bigdecimal::BigDecimal> },
-                TokenStream::new(),
-            ),
-            _ if timestamp_re.is_match(x.r#type.as_str()) => (
-                quote! { Option<chrono::NaiveDateTime> },
-                quote! { #[serde(deserialize_with = "dates::from_str_to_naive_datetime")] },
-            ),
-            _ if time_re.is_match(x.r#type.as_str()) => (
-                quote! { Option<chrono::NaiveTime> },
-                quote! { #[serde(deserialize_with = "dates::from_str_to_naive_time")] },
-            ),
-            _ if array_re.is_match(x.r#type.as_str()) => (
-                quote! { Option<Vec<array::Element>> },
-                quote! {  #[serde(deserialize_with = "array::from_homogeneous_array")]},
-            ),
-            _ if map_re.is_match(x.r#type.as_str()) => (
-                quote! { Option<std::collections::HashMap<String, serde_json::Value>> },
-                TokenStream::new(),
-            ),
+        }
+        impl #ident {
+            pub async fn insert_batch(client: &trino::Client, rows: &[Self]) -> trino::Result<u64> {
+                trino_codegen::utils::insert::insert_batch(client, rows).await
+            }
+        }
+    }
+}
 
-            "integer" | "int" => (quote! { Option<i32> }, TokenStream::new()),
-            "smallint" => (quote! { Option<i16> }, TokenStream::new()),
-            "tinyint" => (quote! { Option<i8> }, TokenStream::new()),
-            "bigint" => (quote! { Option<i64> }, TokenStream::new()),
-
-            "float" | "real" => (quote! { Option<f32> }, TokenStream::new()),
-            "double" => (quote! { Option<f64> }, TokenStream::new()),
-            "boolean" | "bool" => (quote! { Option<bool> }, TokenStream::new()),
-            "date" => (
-                quote! { Option<chrono::NaiveDate> },
-                quote! { #[serde(deserialize_with = "dates::from_str_to_naive_date")] },
-            ),
+fn doc_attrs(doc: Option<&str>) -> TokenStream {
+    let lines = doc
+        .into_iter()
+        .flat_map(str::lines)
+        .map(|line| format!(" {}", line.trim_end()));
+    quote! { #(#[doc = #lines])* }
+}
 
-            "timestamp with time zone" => (
-                quote! { Option<chrono::DateTime<chrono::Utc>> },
-                quote! { #[serde(deserialize_with = "dates::from_str_to_datetime")] },
-            ),
+struct Field {
+    name: String,
+    r#type: TrinoType,
+    non_null: bool,
+    doc: Option<String>,
+}
 
-            "binary" | "varbinary" => (
-                quote! { Option<Vec<u8>> },
-                quote! {  #[serde(deserialize_with = "binary::from_base64")]},
-            ),
-            "json" => (quote! { Option<serde_json::Value> }, TokenStream::new()),
-            "uuid" => (quote! { Option<uuid::Uuid> }, TokenStream::new()),
+// Types trino-codegen doesn't know still come through as their string form
+fn parse_type(ty: &str) -> TrinoType {
+    ty.parse()
+        .unwrap_or_else(|_| TrinoType::Other(ty.to_string()))
+}
+
+// How a field is read: its rust type, and a `de::Decoder` expression for it. Types mapped
+// in trino-codegen.toml can't implement `TrinoDeserialize`, so fields containing one get
+// their own function built from decoders, and `leaves` holds the local decoder types that
+// call each `deserialize_with`.
+struct Mapped {
+    rust_type: TokenStream,
+    decoder: TokenStream,
+    overridden: bool,
+}
+
+// Collects the struct for a query along with one nested struct per `row(...)` it contains,
+// named after the path to it, e.g. `OrdersQueryResultShippingAddress`. With `literals`
+// nested structs implement `trino::ToTrinoLiteral` so rows can be inserted, except those holding
+// a mapped type, which are serialized and inserted as JSON instead. `serializing` is set while
+// the fields of such a struct are generated, since the rows nested in it have to serialize too.
+struct StructGenerator<'a> {
+    config: &'a Config,
+    literals: bool,
+    serializing: bool,
+    structs: Vec<TokenStream>,
+}
 
-            _ => (quote! { Option<String> }, TokenStream::new()),
+impl StructGenerator<'_> {
+    fn finish(self) -> TokenStream {
+        let structs = self.structs.into_iter().rev();
+        quote! { #(#structs)* }
+    }
+
+    // Returns a SQL literal expression for each field of `self`, cast to its column type
+    fn generate(
+        &mut self,
+        name: &str,
+        doc: Option<&str>,
+        fields: &[Field],
+        nested: bool,
+    ) -> Vec<TokenStream> {
+        let ident = Ident::new(name, Span::call_site());
+        let doc = doc_attrs(doc);
+        let names = field_names(fields.iter().map(|f| f.name.as_str()));
+        let json = fields
+            .iter()
+            .any(|field| contains_mapped(self.config, &field.r#type));
+        // nested rows holding a mapped type are inserted as JSON, so with `literals` they serialize
+        let serialize =
+            self.config.serialize || self.serializing || (nested && self.literals && json);
+        let serializing = std::mem::replace(&mut self.serializing, serialize);
+        let mut field_fns = Vec::new();
+        let mut values = Vec::new();
+        let fields: Vec<TokenStream> = fields
+            .iter()
+            .zip(names)
+            .map(|(field, field_name)| {
+                let identifier = field_ident(&field_name);
+                let hint = format!("{}{}", name, pascal_case(&field_name));
+                let mut leaves = Vec::new();
+                let mapped = self.rust_type(&field.r#type, &hint, &mut leaves);
+                let (rust_type, decoder) = if field.non_null {
+                    (mapped.rust_type, mapped.decoder)
+                } else {
+                    let (ty, decoder) = (mapped.rust_type, mapped.decoder);
+                    (
+                        quote! { Option<#ty> },
+                        quote! { trino_codegen::utils::de::Nullable(#decoder) },
+                    )
+                };
+
+                let trino_type = field.r#type.to_string();
+                values.push(if contains_mapped(self.config, &field.r#type) {
+                    quote! {
+                        trino_codegen::utils::insert::cast(
+                            trino_codegen::utils::ser::serde_literal(&self.#identifier)?,
+                            #trino_type,
+                        )
+                    }
+                } else {
+                    quote! {
+                        trino_codegen::utils::insert::cast(
+                            trino::ToTrinoLiteral::to_literal(&self.#identifier),
+                            #trino_type,
+                        )
+                    }
+                });
+
+                let attr = if mapped.overridden {
+                    let fn_ident =
+                        Ident::new(&format!("deserialize_{}", field_name), Span::call_site());
+                    let path = format!("{}::{}", name, fn_ident);
+                    field_fns.push(quote! {
+                        fn #fn_ident<'de, D>(deserializer: D) -> Result<#rust_type, D::Error>
+                        where
+                            D: serde::Deserializer<'de>,
+                        {
+                            #(#leaves)*
+                            trino_codegen::utils::de::decode(deserializer, #decoder)
+                        }
+                    });
+                    quote! { #[serde(deserialize_with = #path)] }
+                } else if needs_trino_deserialize(&field.r#type) && serialize {
+                    quote! {
+                        #[serde(
+                            deserialize_with = "trino_codegen::utils::de::deserialize",
+                            serialize_with = "trino_codegen::utils::ser::serialize"
+                        )]
+                    }
+                } else if needs_trino_deserialize(&field.r#type) {
+                    quote! { #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")] }
+                } else {
+                    TokenStream::new()
+                };
+                let rename = if field_name != field.name {
+                    let original = &field.name;
+                    quote! { #[serde(rename = #original)] }
+                } else {
+                    TokenStream::new()
+                };
+                let field_doc = doc_attrs(field.doc.as_deref());
+                quote! { #field_doc #rename #attr pub #identifier: #rust_type, }
+            })
+            .collect();
+        self.serializing = serializing;
+
+        let field_fns = if field_fns.is_empty() {
+            TokenStream::new()
+        } else {
+            quote! {
+                impl #ident {
+                    #(#field_fns)*
+                }
+            }
         };
-        quote! { #attr pub #identifier: #rust_data_type, }
-    });
 
-    let combined_fields = struct_fields.collect::<TokenStream>();
+        // nested rows end up inside `Vec`s and `HashMap`s, which deserialize through `TrinoDeserialize`
+        let mut nested_impls = TokenStream::new();
+        if nested {
+            nested_impls.extend(quote! { trino_codegen::trino_deserialize_via_serde!(#ident); });
+            if serialize {
+                nested_impls.extend(quote! { trino_codegen::trino_serialize_via_serde!(#ident); });
+            }
+            if self.literals && !json {
+                nested_impls.extend(quote! {
+                    impl trino::ToTrinoLiteral for #ident {
+                        fn to_literal(&self) -> String {
+                            let values = [#(#values),*];
+                            format!("ROW({})", values.join(", "))
+                        }
+                    }
+                });
+            }
+        }
 
-    let structname = Ident::new(
-        &format!(
-            "{}QueryResult",
-            capitalize_first(
-                &file
-                    .file_stem()
-                    .expect("there better be a file stem!")
-                    .to_string_lossy()
-            )
+        // without `Config::serialize` the file only imports `Deserialize`
+        let derive = if self.config.serialize {
+            quote! { #[derive(Debug, Deserialize, Serialize)] }
+        } else if serialize {
+            quote! { #[derive(Debug, Deserialize, serde::Serialize)] }
+        } else {
+            quote! { #[derive(Debug, Deserialize)] }
+        };
+        self.structs.push(quote! {
+            #doc
+            #derive
+            pub struct #ident {
+                #(#fields)*
+            }
+            #field_fns
+            #nested_impls
+        });
+        values
+    }
+
+    fn rust_type(&mut self, ty: &TrinoType, hint: &str, leaves: &mut Vec<TokenStream>) -> Mapped {
+        if let Some(mapping) = self.config.lookup(ty) {
+            let rust_type = mapping.rust_type();
+            let decoder = match mapping.deserialize_with() {
+                Some(with) => {
+                    let leaf = Ident::new(&format!("Leaf{}", leaves.len()), Span::call_site());
+                    leaves.push(quote! {
+                        #[derive(Clone, Copy)]
+                        struct #leaf;
+                        impl trino_codegen::utils::de::Decoder for #leaf {
+                            type Value = #rust_type;
+                            fn decode<'de, D>(self, deserializer: D) -> Result<#rust_type, D::Error>
+                            where
+                                D: serde::Deserializer<'de>,
+                            {
+                                #with(deserializer)
+                            }
+                        }
+                    });
+                    quote! { #leaf }
+                }
+                None => quote! { trino_codegen::utils::de::Serde::<#rust_type>::new() },
+            };
+            return Mapped {
+                rust_type,
+                decoder,
+                overridden: true,
+            };
+        }
+
+        let rust_type = match ty {
+            TrinoType::Array(element) => {
+                let element = self.rust_type(element, &format!("{}Item", hint), leaves);
+                let (ty, decoder) = (element.rust_type, element.decoder);
+                return Mapped {
+                    rust_type: quote! { Vec<Option<#ty>> },
+                    decoder: quote! {
+                        trino_codegen::utils::de::Array(trino_codegen::utils::de::Nullable(#decoder))
+                    },
+                    overridden: element.overridden,
+                };
+            }
+            TrinoType::Map(key, value) => {
+                let key = self.rust_type(key, &format!("{}Key", hint), leaves);
+                let value = self.rust_type(value, &format!("{}Value", hint), leaves);
+                let (key_ty, key_decoder) = (key.rust_type, key.decoder);
+                let (value_ty, value_decoder) = (value.rust_type, value.decoder);
+                return Mapped {
+                    rust_type: quote! { std::collections::HashMap<#key_ty, Option<#value_ty>> },
+                    decoder: quote! {
+                        trino_codegen::utils::de::Map(
+                            #key_decoder,
+                            trino_codegen::utils::de::Nullable(#value_decoder),
+                        )
+                    },
+                    overridden: key.overridden || value.overridden,
+                };
+            }
+            // the nested struct reads its own overridden fields
+            TrinoType::Row(row_fields) => {
+                let fields: Vec<Field> = row_fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, f)| Field {
+                        name: f.name.clone().unwrap_or_else(|| format!("field{}", i)),
+                        r#type: f.r#type.clone(),
+                        non_null: false,
+                        doc: None,
+                    })
+                    .collect();
+                self.generate(hint, None, &fields, true);
+                let ident = Ident::new(hint, Span::call_site());
+                quote! { #ident }
+            }
+            _ => default_rust_type(ty),
+        };
+        Mapped {
+            decoder: quote! { trino_codegen::utils::de::Trino::<#rust_type>::new() },
+            rust_type,
+            overridden: false,
+        }
+    }
+}
+
+fn default_rust_type(ty: &TrinoType) -> TokenStream {
+    match ty {
+        TrinoType::Boolean => quote! { bool },
+        TrinoType::TinyInt => quote! { i8 },
+        TrinoType::SmallInt => quote! { i16 },
+        TrinoType::Integer => quote! { i32 },
+        TrinoType::BigInt => quote! { i64 },
+        TrinoType::Real => quote! { f32 },
+        TrinoType::Double => quote! { f64 },
+        TrinoType::Decimal { .. } => quote! { bigdecimal::BigDecimal },
+        TrinoType::Varchar(_) | TrinoType::Char(_) => quote! { String },
+        TrinoType::Varbinary => quote! { Vec<u8> },
+        TrinoType::Json => quote! { serde_json::Value },
+        TrinoType::Date => quote! { chrono::NaiveDate },
+        TrinoType::Time {
+            with_time_zone: false,
+            ..
+        } => quote! { chrono::NaiveTime },
+        TrinoType::Time {
+            with_time_zone: true,
+            ..
+        } => quote! { trino_codegen::utils::de::TimeWithTimeZone },
+        TrinoType::Timestamp {
+            with_time_zone: false,
+            ..
+        } => quote! { chrono::NaiveDateTime },
+        TrinoType::Timestamp {
+            with_time_zone: true,
+            ..
+        } => quote! { chrono::DateTime<chrono::Utc> },
+        TrinoType::IntervalDayToSecond => quote! { chrono::Duration },
+        TrinoType::IntervalYearToMonth => quote! { trino_codegen::utils::de::IntervalYearToMonth },
+        TrinoType::Uuid => quote! { uuid::Uuid },
+        TrinoType::IpAddress => quote! { std::net::IpAddr },
+        TrinoType::Other(raw) => match other_kind(raw) {
+            OtherKind::Binary => quote! { Vec<u8> },
+            OtherKind::Text => quote! { String },
+            OtherKind::Unknown => quote! { serde_json::Value },
+        },
+        TrinoType::Array(_) | TrinoType::Map(..) | TrinoType::Row(_) => {
+            unreachable!("containers are handled by StructGenerator::rust_type")
+        }
+    }
+}
+
+enum OtherKind {
+    Binary,
+    Text,
+    Unknown,
+}
+
+// Sketches come back base64 encoded like varbinary, geospatial types as WKT, and anything
+// else (including `unknown`, the type of a bare NULL) is kept as whatever JSON Trino sends
+fn other_kind(raw: &str) -> OtherKind {
+    let name = raw
+        .split('(')
+        .next()
+        .unwrap_or(raw)
+        .trim()
+        .to_ascii_lowercase();
+    match name.as_str() {
+        "hyperloglog" | "p4hyperloglog" | "qdigest" | "tdigest" | "setdigest" => OtherKind::Binary,
+        "geometry" | "sphericalgeography" | "bingtile" => OtherKind::Text,
+        _ => OtherKind::Unknown,
+    }
+}
+
+// Whether a value of `ty` holds a type mapped in trino-codegen.toml, which can't implement
+// `trino::ToTrinoLiteral`, so its literal goes through `ser::serde_literal`
+fn contains_mapped(config: &Config, ty: &TrinoType) -> bool {
+    config.lookup(ty).is_some()
+        || match ty {
+            TrinoType::Array(element) => contains_mapped(config, element),
+            TrinoType::Map(key, value) => {
+                contains_mapped(config, key) || contains_mapped(config, value)
+            }
+            TrinoType::Row(fields) => fields.iter().any(|f| contains_mapped(config, &f.r#type)),
+            _ => false,
+        }
+}
+
+// Everything else is read fine by plain serde, rows included since their fields carry their own attributes
+fn needs_trino_deserialize(ty: &TrinoType) -> bool {
+    match ty {
+        TrinoType::Other(raw) => matches!(other_kind(raw), OtherKind::Binary),
+        _ => matches!(
+            ty,
+            TrinoType::Real
+                | TrinoType::Double
+                | TrinoType::Varbinary
+                | TrinoType::Date
+                | TrinoType::Time { .. }
+                | TrinoType::Timestamp { .. }
+                | TrinoType::IntervalDayToSecond
+                | TrinoType::IntervalYearToMonth
+                | TrinoType::Array(_)
+                | TrinoType::Map(..)
         ),
-        Span::call_site(),
-    );
+    }
+}
+
+fn pascal_case(s: &str) -> String {
+    s.split('_').map(capitalize_first).collect()
+}
+
+// Column names as snake_case field names, `_2`, `_3`, ... appended when two of them collide.
+// Keywords are left to `field_ident`, apart from the few that can't be raw identifiers.
+fn field_names<'a>(columns: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut used = HashSet::new();
+    columns
+        .map(|column| {
+            let mut base = snake_case(column);
+            if matches!(base.as_str(), "self" | "super" | "crate") {
+                base.push('_');
+            }
+            let mut name = base.clone();
+            let mut n = 1;
+            while !used.insert(name.clone()) {
+                n += 1;
+                name = format!("{}_{}", base, n);
+            }
+            name
+        })
+        .collect()
+}
+
+// `Order ID`, `order-id` and `orderId` all become `order_id`; anything that can't be part of
+// an identifier separates words, and a leading digit gets a `_` in front
+pub fn snake_case(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut out = String::with_capacity(name.len());
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphanumeric() {
+            if !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+            continue;
+        }
+        if c.is_ascii_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_ascii_lowercase());
+            let boundary = prev.is_ascii_lowercase()
+                || prev.is_ascii_digit()
+                || (prev.is_ascii_uppercase() && next_lower);
+            if boundary && !out.is_empty() && !out.ends_with('_') {
+                out.push('_');
+            }
+        }
+        out.push(c.to_ascii_lowercase());
+    }
+    let out = out.trim_end_matches('_');
+    match out.chars().next() {
+        None => "column".to_string(),
+        Some(c) if c.is_ascii_digit() => format!("_{}", out),
+        Some(_) => out.to_string(),
+    }
+}
+
+fn field_ident(name: &str) -> Ident {
+    syn::parse_str::<Ident>(name).unwrap_or_else(|_| Ident::new_raw(name, Span::call_site()))
+}
+
+// `order_items` becomes `OrderItems`
+pub fn table_struct_name(table: &str) -> Ident {
+    let name = pascal_case(&snake_case(table));
+    match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => Ident::new(&name, Span::call_site()),
+        _ => Ident::new(&format!("Table{}", name), Span::call_site()),
+    }
+}
+
+pub fn struct_name(file: &Path) -> Ident {
+    let stem: String = file_stem(file)
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    let stem = match stem.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => capitalize_first(&stem),
+        _ => format!("Query{}", stem),
+    };
+    Ident::new(&format!("{}QueryResult", stem), Span::call_site())
+}
+
+// `daily_sales.sql` becomes `daily_sales`, anything that can't go in an identifier turns into `_`
+pub fn fn_name(file: &Path) -> Ident {
+    let name: String = file_stem(file)
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let name = match name.chars().next() {
+        Some(c) if c.is_ascii_alphabetic() => unreserved(name),
+        _ => format!("query_{}", name),
+    };
+    Ident::new(&name, Span::call_site())
+}
+
+// Keywords get a `_` suffix rather than a raw identifier, the names are used for modules
+// too and `r#self`, `r#super`, `r#crate` and `r#mod.rs` aren't possible there
+pub fn unreserved(name: String) -> String {
+    if syn::parse_str::<Ident>(&name).is_ok() {
+        name
+    } else {
+        format!("{}_", name)
+    }
+}
+
+fn file_stem(file: &Path) -> String {
+    file.file_stem()
+        .expect("there better be a file stem!")
+        .to_string_lossy()
+        .into_owned()
+}
+
+// One argument of a generated query function: a `:name` placeholder (however many times it's
+// used) or a single `?`, with the type Trino inferred for it
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryParam {
+    pub name: Option<String>,
+    pub r#type: String,
+}
+
+// Pairs placeholders with the `DESCRIBE INPUT` types, which come one per `?` after rewriting
+pub fn query_params(placeholders: &Placeholders, types: &[String]) -> Vec<QueryParam> {
+    let type_at = |i: usize| {
+        types
+            .get(i)
+            .cloned()
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    let mut params: Vec<QueryParam> = Vec::new();
+    for (i, name) in placeholders.names.iter().enumerate() {
+        match name {
+            None => params.push(QueryParam {
+                name: None,
+                r#type: type_at(i),
+            }),
+            Some(name) => match params.iter_mut().find(|p| p.name.as_ref() == Some(name)) {
+                Some(existing) if existing.r#type == "unknown" => existing.r#type = type_at(i),
+                Some(_) => {}
+                None => params.push(QueryParam {
+                    name: Some(name.clone()),
+                    r#type: type_at(i),
+                }),
+            },
+        }
+    }
+    params
+}
+
+// Argument types for parameters, borrowed where that's cheaper for the caller;
+// anything without an obvious Rust counterpart takes any literal-able value
+pub fn param_type(trino_type: &str) -> TokenStream {
+    let any = quote! { impl trino::ToTrinoLiteral };
+    let ty: TrinoType = match trino_type.parse() {
+        Ok(ty) => ty,
+        Err(_) => return any,
+    };
+    match ty {
+        TrinoType::Boolean => quote! { bool },
+        TrinoType::TinyInt => quote! { i8 },
+        TrinoType::SmallInt => quote! { i16 },
+        TrinoType::Integer => quote! { i32 },
+        TrinoType::BigInt => quote! { i64 },
+        TrinoType::Real => quote! { f32 },
+        TrinoType::Double => quote! { f64 },
+        TrinoType::Decimal { .. } => quote! { &bigdecimal::BigDecimal },
+        TrinoType::Varchar(_) | TrinoType::Char(_) => quote! { &str },
+        TrinoType::Varbinary => quote! { &[u8] },
+        TrinoType::Date => quote! { chrono::NaiveDate },
+        TrinoType::Time {
+            with_time_zone: false,
+            ..
+        } => quote! { chrono::NaiveTime },
+        TrinoType::Timestamp {
+            with_time_zone: false,
+            ..
+        } => quote! { chrono::NaiveDateTime },
+        TrinoType::Timestamp {
+            with_time_zone: true,
+            ..
+        } => quote! { chrono::DateTime<chrono::Utc> },
+        TrinoType::Json => quote! { &serde_json::Value },
+        _ => any,
+    }
+}
+
+// The SQL is pulled in with `include_str!`, so the query text and its row type live and change together.
+// `sql_path` is relative to the generated file, which is how `include_str!` resolves it.
+pub fn generate_query_fn(file: &Path, sql_path: &str, params: &[QueryParam]) -> TokenStream {
+    let fn_name = fn_name(file);
+    let structname = struct_name(file);
+
+    if params.is_empty() {
+        return quote! {
+            pub async fn #fn_name(client: &trino::Client) -> trino::Result<Vec<#structname>> {
+                client.query(include_str!(#sql_path)).await
+            }
+        };
+    }
+
+    let mut args = Vec::new();
+    let mut binds = Vec::new();
+    for (i, param) in params.iter().enumerate() {
+        let ty = param_type(&param.r#type);
+        match &param.name {
+            Some(name) => {
+                let ident = param_ident(name);
+                args.push(quote! { #ident: #ty });
+                binds.push(quote! { .bind(#name, #ident) });
+            }
+            None => {
+                let ident = Ident::new(&format!("p{}", i + 1), Span::call_site());
+                args.push(quote! { #ident: #ty });
+                binds.push(quote! { .push(#ident) });
+            }
+        }
+    }
 
     quote! {
-        #[derive(Debug, Deserialize)]
-        pub struct #structname {
-            #combined_fields
+        pub async fn #fn_name(
+            client: &trino::Client,
+            #(#args),*
+        ) -> trino::Result<Vec<#structname>> {
+            client
+                .query_with(include_str!(#sql_path), &trino::Params::new()#(#binds)*)
+                .await
+        }
+    }
+}
+
+// `:client` would clash with the client argument, and `self`, `super`, `crate`, `Self` and
+// `_` can't be raw identifiers, so those get a `_` suffix
+fn param_ident(name: &str) -> Ident {
+    match name {
+        "client" | "self" | "super" | "crate" | "Self" | "_" => {
+            Ident::new(&format!("{}_", name), Span::call_site())
         }
+        _ => field_ident(name),
     }
 }
 
+// Path to `target` as seen from `base_dir`, both relative to the current directory or both absolute
+pub fn relative_path(base_dir: &Path, target: &Path) -> PathBuf {
+    let base: Vec<Component> = base_dir
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let target: Vec<Component> = target
+        .components()
+        .filter(|c| *c != Component::CurDir)
+        .collect();
+    let common = base.iter().zip(&target).take_while(|(a, b)| a == b).count();
+
+    let mut path = PathBuf::new();
+    for _ in common..base.len() {
+        path.push("..");
+    }
+    for component in &target[common..] {
+        path.push(component);
+    }
+    path
+}
+
 pub fn capitalize_first(s: &str) -> String {
     s.chars()
         .take(1)
@@ -222,8 +1664,11 @@ pub fn capitalize_first(s: &str) -> String {
 mod tests {
     use std::path::Path;
 
+    use crate::config::Config;
     use crate::explain::ExplainRoot;
-    use crate::utils::generate_struct;
+    use trino::params::Placeholders;
+
+    use crate::utils::{fn_name, generate_query_fn, generate_struct, query_params, relative_path};
 
     #[test]
     fn test_generate_struct() {
@@ -232,8 +1677,604 @@ mod tests {
         let root: ExplainRoot =
             serde_json::from_str(include_str!("../data/explain2.json")).unwrap();
 
-        let generated_struct = generate_struct(&root, file);
+        let generated_struct = generate_struct(&root, file, &[], &Config::default());
 
         println!("{generated_struct:#?}")
     }
+
+    #[test]
+    fn test_generate_query_fn() {
+        let file = Path::new("./src/queries/daily_sales.sql");
+        let generated = generate_query_fn(file, "queries/daily_sales.sql", &[]);
+
+        assert_eq!(
+            generated.to_string(),
+            quote::quote! {
+                pub async fn daily_sales(client: &trino::Client) -> trino::Result<Vec<Daily_salesQueryResult>> {
+                    client.query(include_str!("queries/daily_sales.sql")).await
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_generate_query_fn_reserved_params() {
+        let placeholders =
+            Placeholders::parse("SELECT * FROM t WHERE a = :client AND b = :self AND c = :type")
+                .unwrap();
+        let types = ["bigint", "bigint", "bigint"].map(String::from);
+        let params = query_params(&placeholders, &types);
+
+        assert_eq!(
+            generate_query_fn(Path::new("t.sql"), "t.sql", &params).to_string(),
+            quote::quote! {
+                pub async fn t(
+                    client: &trino::Client,
+                    client_: i64,
+                    self_: i64,
+                    r#type: i64
+                ) -> trino::Result<Vec<TQueryResult>> {
+                    client
+                        .query_with(
+                            include_str!("t.sql"),
+                            &trino::Params::new().bind("client", client_).bind("self", self_).bind("type", r#type)
+                        )
+                        .await
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_fn_name_keywords() {
+        assert_eq!(fn_name(Path::new("sql/match.sql")).to_string(), "match_");
+        assert_eq!(fn_name(Path::new("sql/self.sql")).to_string(), "self_");
+        assert_eq!(fn_name(Path::new("sql/matches.sql")).to_string(), "matches");
+    }
+
+    #[test]
+    fn test_relative_path() {
+        assert_eq!(
+            relative_path(Path::new("./src"), Path::new("./src/queries/a.sql")),
+            Path::new("queries/a.sql")
+        );
+        assert_eq!(
+            relative_path(Path::new("src/generated"), Path::new("./queries/a.sql")),
+            Path::new("../../queries/a.sql")
+        );
+    }
+
+    #[test]
+    fn test_generate_query_fn_with_params() {
+        let file = Path::new("./src/queries/daily_sales.sql");
+        let placeholders = Placeholders::parse(
+            "SELECT * FROM sales WHERE day >= :start AND day < :end AND tenant = :tenant AND day <> :start",
+        )
+        .unwrap();
+        let types = ["date", "date", "varchar", "date"].map(String::from);
+        let params = query_params(&placeholders, &types);
+        assert_eq!(params.len(), 3);
+
+        assert_eq!(
+            generate_query_fn(file, "queries/daily_sales.sql", &params).to_string(),
+            quote::quote! {
+                pub async fn daily_sales(
+                    client: &trino::Client,
+                    start: chrono::NaiveDate,
+                    end: chrono::NaiveDate,
+                    tenant: &str
+                ) -> trino::Result<Vec<Daily_salesQueryResult>> {
+                    client
+                        .query_with(
+                            include_str!("queries/daily_sales.sql"),
+                            &trino::Params::new().bind("start", start).bind("end", end).bind("tenant", tenant)
+                        )
+                        .await
+                }
+            }
+            .to_string()
+        );
+    }
+
+    #[test]
+    fn test_generate_nested_struct() {
+        let root: ExplainRoot = serde_json::from_value(serde_json::json!({
+            "id": "0",
+            "name": "Output",
+            "descriptor": { "columnNames": "[id, customer]" },
+            "outputs": [
+                { "symbol": "id", "type": "bigint" },
+                {
+                    "symbol": "customer",
+                    "type": "row(name varchar, addresses array(row(city varchar, since date)), tags map(varchar, array(integer)))"
+                }
+            ],
+            "details": [],
+            "estimates": [],
+            "children": []
+        }))
+        .unwrap();
+
+        let generated =
+            generate_struct(&root, Path::new("orders.sql"), &[true], &Config::default());
+        let de = "trino_codegen::utils::de::deserialize";
+        assert_eq!(
+            generated.to_string().replace(' ', ""),
+            quote::quote! {
+                #[derive(Debug, Deserialize)]
+                pub struct OrdersQueryResult {
+                    pub id: i64,
+                    pub customer: Option<OrdersQueryResultCustomer>,
+                }
+                #[derive(Debug, Deserialize)]
+                pub struct OrdersQueryResultCustomer {
+                    pub name: Option<String>,
+                    #[serde(deserialize_with = #de)]
+                    pub addresses: Option<Vec<Option<OrdersQueryResultCustomerAddressesItem>>>,
+                    #[serde(deserialize_with = #de)]
+                    pub tags: Option<std::collections::HashMap<String, Option<Vec<Option<i32>>>>>,
+                }
+                trino_codegen::trino_deserialize_via_serde!(OrdersQueryResultCustomer);
+                #[derive(Debug, Deserialize)]
+                pub struct OrdersQueryResultCustomerAddressesItem {
+                    pub city: Option<String>,
+                    #[serde(deserialize_with = #de)]
+                    pub since: Option<chrono::NaiveDate>,
+                }
+                trino_codegen::trino_deserialize_via_serde!(OrdersQueryResultCustomerAddressesItem);
+            }
+            .to_string()
+            .replace(' ', "")
+        );
+    }
+
+    #[test]
+    fn test_trino_deserialize_containers() {
+        use std::collections::HashMap;
+
+        use super::de::deserialize;
+
+        let mut de =
+            serde_json::Deserializer::from_str(r#"[["2023-01-02", null], ["2024-02-29"]]"#);
+        let dates: Vec<Option<Vec<Option<chrono::NaiveDate>>>> = deserialize(&mut de).unwrap();
+        assert_eq!(dates[0].as_ref().unwrap()[1], None);
+        assert_eq!(
+            dates[1].as_ref().unwrap()[0],
+            chrono::NaiveDate::from_ymd_opt(2024, 2, 29)
+        );
+
+        let mut de = serde_json::Deserializer::from_str(r#"{"1": ["NaN", 1.5], "2": null}"#);
+        let map: HashMap<i32, Option<Vec<f64>>> = deserialize(&mut de).unwrap();
+        assert!(map[&1].as_ref().unwrap()[0].is_nan());
+        assert_eq!(map[&2], None);
+
+        let mut de = serde_json::Deserializer::from_str(r#""2023-01-01 01:02:03.456 +02:00""#);
+        let ts: chrono::DateTime<chrono::Utc> = deserialize(&mut de).unwrap();
+        assert_eq!(ts.to_rfc3339(), "2022-12-31T23:02:03.456+00:00");
+    }
+
+    #[test]
+    fn test_generate_struct_with_overrides() {
+        let root: ExplainRoot = serde_json::from_value(serde_json::json!({
+            "id": "0",
+            "name": "Output",
+            "descriptor": { "columnNames": "[price, history, seen, ip]" },
+            "outputs": [
+                { "symbol": "price", "type": "decimal(38,2)" },
+                { "symbol": "history", "type": "map(varchar, array(decimal(10,2)))" },
+                { "symbol": "seen", "type": "timestamp(3) with time zone" },
+                { "symbol": "ip", "type": "ipaddress" }
+            ],
+            "details": [],
+            "estimates": [],
+            "children": []
+        }))
+        .unwrap();
+        let config = Config::from_toml(
+            r#"
+            [types]
+            decimal = { rust_type = "rust_decimal::Decimal", deserialize_with = "rust_decimal::serde::str::deserialize" }
+            varchar = { rust_type = "Box<str>" }
+            "#,
+            Path::new("trino-codegen.toml"),
+        )
+        .unwrap();
+
+        let generated = generate_struct(&root, Path::new("prices.sql"), &[true], &config);
+        let de = "trino_codegen::utils::de::deserialize";
+        let leaf = |n: &str| {
+            let leaf = quote::format_ident!("{}", n);
+            quote::quote! {
+                #[derive(Clone, Copy)]
+                struct #leaf;
+                impl trino_codegen::utils::de::Decoder for #leaf {
+                    type Value = rust_decimal::Decimal;
+                    fn decode<'de, D>(self, deserializer: D) -> Result<rust_decimal::Decimal, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                    {
+                        rust_decimal::serde::str::deserialize(deserializer)
+                    }
+                }
+            }
+        };
+        let leaf0 = leaf("Leaf0");
+        assert_eq!(
+            generated.to_string().replace(' ', ""),
+            quote::quote! {
+                #[derive(Debug, Deserialize)]
+                pub struct PricesQueryResult {
+                    #[serde(deserialize_with = "PricesQueryResult::deserialize_price")]
+                    pub price: rust_decimal::Decimal,
+                    #[serde(deserialize_with = "PricesQueryResult::deserialize_history")]
+                    pub history: Option<std::collections::HashMap<Box<str>, Option<Vec<Option<rust_decimal::Decimal>>>>>,
+                    #[serde(deserialize_with = #de)]
+                    pub seen: Option<chrono::DateTime<chrono::Utc> >,
+                    pub ip: Option<std::net::IpAddr>,
+                }
+                impl PricesQueryResult {
+                    fn deserialize_price<'de, D>(deserializer: D) -> Result<rust_decimal::Decimal, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                    {
+                        #leaf0
+                        trino_codegen::utils::de::decode(deserializer, Leaf0)
+                    }
+                    fn deserialize_history<'de, D>(
+                        deserializer: D
+                    ) -> Result<Option<std::collections::HashMap<Box<str>, Option<Vec<Option<rust_decimal::Decimal>>>>>, D::Error>
+                    where
+                        D: serde::Deserializer<'de>,
+                    {
+                        #leaf0
+                        trino_codegen::utils::de::decode(
+                            deserializer,
+                            trino_codegen::utils::de::Nullable(trino_codegen::utils::de::Map(
+                                trino_codegen::utils::de::Serde::<Box<str>>::new(),
+                                trino_codegen::utils::de::Nullable(trino_codegen::utils::de::Array(
+                                    trino_codegen::utils::de::Nullable(Leaf0)
+                                )),
+                            ))
+                        )
+                    }
+                }
+            }
+            .to_string()
+            .replace(' ', "")
+        );
+    }
+
+    #[test]
+    fn test_trino_deserialize_temporal() {
+        use super::de::{deserialize, IntervalYearToMonth, TimeWithTimeZone, TrinoDeserialize};
+
+        fn parse<T: TrinoDeserialize>(json: &str) -> T {
+            deserialize(&mut serde_json::Deserializer::from_str(json)).unwrap()
+        }
+
+        let ts: chrono::DateTime<chrono::Utc> = parse(r#""2023-07-01 12:00:00.000 Europe/Berlin""#);
+        assert_eq!(ts.to_rfc3339(), "2023-07-01T10:00:00+00:00");
+
+        let time: TimeWithTimeZone = parse(r#""01:02:03.456-05:30""#);
+        assert_eq!(time.offset.local_minus_utc(), -(5 * 3600 + 30 * 60));
+        assert_eq!(time.time.to_string(), "01:02:03.456");
+
+        let interval: chrono::Duration = parse(r#""-2 03:04:05.500""#);
+        assert_eq!(
+            interval.num_milliseconds(),
+            -((2 * 86400 + 3 * 3600 + 4 * 60 + 5) * 1000 + 500)
+        );
+
+        let interval: IntervalYearToMonth = parse(r#""-1-2""#);
+        assert_eq!(interval.months, -14);
+    }
+
+    #[test]
+    fn test_field_names() {
+        use super::{field_ident, field_names};
+
+        let columns = [
+            "Order ID",
+            "order-id",
+            "2nd place",
+            "type",
+            "HTTPStatusCode",
+            "self",
+            "_col0",
+            "!!",
+        ];
+        let names = field_names(columns.iter().copied());
+        assert_eq!(
+            names,
+            vec![
+                "order_id",
+                "order_id_2",
+                "_2nd_place",
+                "type",
+                "http_status_code",
+                "self_",
+                "col0",
+                "column"
+            ]
+        );
+        assert_eq!(field_ident("type").to_string(), "r#type");
+        assert_eq!(field_ident("match").to_string(), "r#match");
+        assert_eq!(field_ident("order_id").to_string(), "order_id");
+    }
+
+    #[test]
+    fn test_generate_struct_renames() {
+        let root: ExplainRoot = serde_json::from_value(serde_json::json!({
+            "id": "0",
+            "name": "Output",
+            "descriptor": { "columnNames": "[Order ID, type, point]" },
+            "outputs": [
+                { "symbol": "a", "type": "bigint" },
+                { "symbol": "b", "type": "varchar" },
+                { "symbol": "c", "type": "row(\"X Coord\" double, match integer)" }
+            ],
+            "details": [],
+            "estimates": [],
+            "children": []
+        }))
+        .unwrap();
+
+        let generated =
+            generate_struct(&root, Path::new("2024-orders.sql"), &[], &Config::default());
+        let de = "trino_codegen::utils::de::deserialize";
+        assert_eq!(
+            generated.to_string().replace(' ', ""),
+            quote::quote! {
+                #[derive(Debug, Deserialize)]
+                pub struct Query2024_ordersQueryResult {
+                    #[serde(rename = "Order ID")]
+                    pub order_id: Option<i64>,
+                    pub r#type: Option<String>,
+                    pub point: Option<Query2024_ordersQueryResultPoint>,
+                }
+                #[derive(Debug, Deserialize)]
+                pub struct Query2024_ordersQueryResultPoint {
+                    #[serde(rename = "X Coord")]
+                    #[serde(deserialize_with = #de)]
+                    pub x_coord: Option<f64>,
+                    pub r#match: Option<i32>,
+                }
+                trino_codegen::trino_deserialize_via_serde!(Query2024_ordersQueryResultPoint);
+            }
+            .to_string()
+            .replace(' ', "")
+        );
+    }
+
+    #[test]
+    fn test_trino_serialize() {
+        use super::de::{deserialize, IntervalYearToMonth, TimeWithTimeZone, TrinoDeserialize};
+        use super::ser::{TrinoSerialize, Wrap};
+
+        // serializing what Trino sent gives back the same JSON, up to trailing zeros
+        fn roundtrip<T: TrinoDeserialize + TrinoSerialize>(json: &str) -> String {
+            let value: T = deserialize(&mut serde_json::Deserializer::from_str(json)).unwrap();
+            serde_json::to_string(&Wrap(&value)).unwrap()
+        }
+
+        assert_eq!(
+            roundtrip::<chrono::NaiveDate>(r#""2024-01-02""#),
+            r#""2024-01-02""#
+        );
+        assert_eq!(
+            roundtrip::<chrono::NaiveDateTime>(r#""2024-01-02 03:04:05.123""#),
+            r#""2024-01-02 03:04:05.123""#
+        );
+        assert_eq!(
+            roundtrip::<chrono::DateTime<chrono::Utc>>(
+                r#""2023-07-01 12:00:00.500 Europe/Berlin""#
+            ),
+            r#""2023-07-01 10:00:00.500 UTC""#
+        );
+        assert_eq!(
+            roundtrip::<TimeWithTimeZone>(r#""01:02:03.456-05:30""#),
+            r#""01:02:03.456-05:30""#
+        );
+        assert_eq!(roundtrip::<IntervalYearToMonth>(r#""-1-2""#), r#""-1-2""#);
+        assert_eq!(
+            roundtrip::<chrono::Duration>(r#""-2 03:04:05.500""#),
+            r#""-2 03:04:05.500""#
+        );
+        assert_eq!(roundtrip::<Vec<u8>>(r#""AQI=""#), r#""AQI=""#);
+        assert_eq!(roundtrip::<Option<f64>>(r#""-Infinity""#), r#""-Infinity""#);
+        assert_eq!(
+            roundtrip::<Vec<Option<std::collections::HashMap<String, Option<f32>>>>>(
+                r#"[{"a": "NaN"}, null]"#
+            ),
+            r#"[{"a":"NaN"},null]"#
+        );
+    }
+
+    #[test]
+    fn test_insert_statements() {
+        use super::insert::{cast, insert_statements, InsertRow};
+        use super::ser::serde_literal;
+
+        struct Row(i64, &'static str);
+        impl InsertRow for Row {
+            const TABLE: &'static str = "\"memory\".\"default\".\"t\"";
+            const COLUMNS: &'static str = "\"id\", \"name\"";
+            fn values(&self) -> trino::Result<String> {
+                use trino::ToTrinoLiteral;
+                Ok([
+                    cast(self.0.to_literal(), "bigint"),
+                    cast(self.1.to_literal(), "varchar"),
+                ]
+                .join(", "))
+            }
+        }
+
+        let rows = [Row(1, "it's"), Row(2, "b"), Row(3, "c")];
+        let prefix = "INSERT INTO \"memory\".\"default\".\"t\" (\"id\", \"name\") VALUES ";
+        let one = "(CAST(BIGINT '1' AS bigint), CAST('it''s' AS varchar))";
+        assert_eq!(
+            insert_statements(&rows[..1], 0).unwrap(),
+            vec![format!("{}{}", prefix, one)]
+        );
+
+        let statements = insert_statements(&rows, prefix.len() + 2 * one.len() + 2).unwrap();
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0]
+            .ends_with("AS varchar)), (CAST(BIGINT '2' AS bigint), CAST('b' AS varchar))"));
+        assert_eq!(
+            statements[1],
+            format!(
+                "{}(CAST(BIGINT '3' AS bigint), CAST('c' AS varchar))",
+                prefix
+            )
+        );
+        assert!(insert_statements::<Row>(&[], 100).unwrap().is_empty());
+
+        assert_eq!(serde_literal(&Some("it's")).unwrap(), "'it''s'");
+        assert_eq!(serde_literal(&None::<i32>).unwrap(), "NULL");
+        assert_eq!(serde_literal(&vec![1.5, 2.0]).unwrap(), "JSON '[1.5,2.0]'");
+        // a map with non-string keys can't be written as JSON
+        let map: std::collections::HashMap<_, _> = vec![((1, 2), 3)].into_iter().collect();
+        assert!(matches!(
+            serde_literal(&map),
+            Err(trino::Error::Parameter(_))
+        ));
+    }
+
+    #[test]
+    fn test_generate_table_struct_insert() {
+        use crate::tables::{Column, Table};
+
+        let column = |name: &str, ty: &str, non_null| Column {
+            name: name.to_string(),
+            r#type: ty.to_string(),
+            non_null,
+            comment: None,
+        };
+        let table = Table {
+            catalog: "hive".to_string(),
+            schema: "web".to_string(),
+            name: "visits".to_string(),
+            comment: None,
+            columns: vec![
+                column("id", "bigint", true),
+                column("Visited At", "date", false),
+                column("point", "row(x double)", false),
+            ],
+        };
+        let config = Config {
+            serialize: true,
+            insert: true,
+            ..Config::default()
+        };
+        let generated = super::generate_table_struct(&table, &config);
+
+        let de = "trino_codegen::utils::de::deserialize";
+        let ser = "trino_codegen::utils::ser::serialize";
+        let cast = |field: proc_macro2::TokenStream, ty: &str| {
+            quote::quote! {
+                trino_codegen::utils::insert::cast(trino::ToTrinoLiteral::to_literal(&self.#field), #ty,)
+            }
+        };
+        let (id, visited_at, point, x) = (
+            cast(quote::quote!(id), "bigint"),
+            cast(quote::quote!(visited_at), "date"),
+            cast(quote::quote!(point), "row(x double)"),
+            cast(quote::quote!(x), "double"),
+        );
+        let expected = quote::quote! {
+            #[derive(Debug, Deserialize, Serialize)]
+            pub struct Visits {
+                pub id: i64,
+                #[serde(rename = "Visited At")]
+                #[serde(deserialize_with = #de, serialize_with = #ser)]
+                pub visited_at: Option<chrono::NaiveDate>,
+                pub point: Option<VisitsPoint>,
+            }
+            #[derive(Debug, Deserialize, Serialize)]
+            pub struct VisitsPoint {
+                #[serde(deserialize_with = #de, serialize_with = #ser)]
+                pub x: Option<f64>,
+            }
+            trino_codegen::trino_deserialize_via_serde!(VisitsPoint);
+            trino_codegen::trino_serialize_via_serde!(VisitsPoint);
+            impl trino::ToTrinoLiteral for VisitsPoint {
+                fn to_literal(&self) -> String {
+                    let values = [#x];
+                    format!("ROW({})", values.join(", "))
+                }
+            }
+            impl trino_codegen::utils::insert::InsertRow for Visits {
+                const TABLE: &'static str = "\"hive\".\"web\".\"visits\"";
+                const COLUMNS: &'static str = "\"id\", \"Visited At\", \"point\"";
+                fn values(&self) -> trino::Result<String> {
+                    Ok([#id, #visited_at, #point].join(", "))
+                }
+            }
+            impl Visits {
+                pub async fn insert_batch(client: &trino::Client, rows: &[Self]) -> trino::Result<u64> {
+                    trino_codegen::utils::insert::insert_batch(client, rows).await
+                }
+            }
+        };
+        assert_eq!(generated.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn test_generate_table_struct_insert_mapped_row() {
+        use crate::tables::{render, Column, Table};
+
+        let column = |name: &str, ty: &str| Column {
+            name: name.to_string(),
+            r#type: ty.to_string(),
+            non_null: true,
+            comment: None,
+        };
+        let table = Table {
+            catalog: "hive".to_string(),
+            schema: "web".to_string(),
+            name: "orders".to_string(),
+            comment: None,
+            columns: vec![
+                column("total", "row(amount decimal(10,2), origin row(lat double))"),
+                column("pt", "row(x double)"),
+            ],
+        };
+        let config = Config::from_toml(
+            r#"
+            insert = true
+            [types]
+            decimal = { rust_type = "rust_decimal::Decimal" }
+            "#,
+            Path::new("trino-codegen.toml"),
+        )
+        .unwrap();
+        let generated = super::generate_table_struct(&table, &config).to_string();
+
+        // the row can't be a `ToTrinoLiteral`, so it's serialized and cast from JSON
+        let literal = quote::quote! {
+            trino_codegen::utils::ser::serde_literal(&self.total)?
+        };
+        assert!(generated.contains(&literal.to_string()));
+        assert!(!generated.contains("ToTrinoLiteral for OrdersTotal "));
+
+        // only `total` and the row nested in it serialize, and the file imports just `Deserialize`
+        let rendered = render(&[table], &config).unwrap();
+        assert!(rendered.contains("\nuse serde::Deserialize;\n"));
+        for (name, derive) in [
+            ("OrdersTotal", "Debug, Deserialize, serde::Serialize"),
+            ("OrdersTotalOrigin", "Debug, Deserialize, serde::Serialize"),
+            ("OrdersPt", "Debug, Deserialize"),
+            ("Orders", "Debug, Deserialize"),
+        ]
+        .iter()
+        {
+            let expected = format!("#[derive({})]\npub struct {} {{", derive, name);
+            assert!(rendered.contains(&expected), "{}", rendered);
+        }
+        assert!(rendered.contains("impl trino::ToTrinoLiteral for OrdersPt {"));
+        assert!(rendered.contains("impl trino::ToTrinoLiteral for OrdersTotalOrigin {"));
+    }
 }
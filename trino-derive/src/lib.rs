@@ -0,0 +1,171 @@
+mod query;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::{quote, ToTokens};
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitInt, LitStr};
+
+// #[derive(TrinoRow)] maps fields to columns by name (or `#[trino(rename = "...")]`),
+// or by position for tuple structs, `#[trino(by_position)]` and `#[trino(position = N)]`
+#[proc_macro_derive(TrinoRow, attributes(trino))]
+pub fn derive_trino_row(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+struct FieldSpec {
+    member: TokenStream2,
+    label: String,
+    column: Option<String>,
+    position: Option<usize>,
+    ty: syn::Type,
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let fields = match &input.data {
+        Data::Struct(data) => &data.fields,
+        _ => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "TrinoRow can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut by_position = matches!(fields, Fields::Unnamed(_));
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("trino")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("by_position") {
+                by_position = true;
+                Ok(())
+            } else {
+                Err(meta.error("unsupported trino container attribute"))
+            }
+        })?;
+    }
+
+    let mut specs = Vec::new();
+    for (i, field) in fields.iter().enumerate() {
+        let (member, label) = match &field.ident {
+            Some(ident) => (
+                ident.to_token_stream(),
+                ident.to_string().trim_start_matches("r#").to_string(),
+            ),
+            None => {
+                let index = syn::Index::from(i);
+                (index.to_token_stream(), i.to_string())
+            }
+        };
+        let mut spec = FieldSpec {
+            column: if by_position {
+                None
+            } else {
+                Some(label.clone())
+            },
+            position: if by_position { Some(i) } else { None },
+            member,
+            label,
+            ty: field.ty.clone(),
+        };
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("trino")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    let name: LitStr = meta.value()?.parse()?;
+                    spec.column = Some(name.value());
+                    spec.position = None;
+                    Ok(())
+                } else if meta.path.is_ident("position") {
+                    let position: LitInt = meta.value()?.parse()?;
+                    spec.position = Some(position.base10_parse()?);
+                    Ok(())
+                } else {
+                    Err(meta.error("unsupported trino field attribute"))
+                }
+            })?;
+        }
+        specs.push(spec);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    let bind_fields = specs.iter().map(|spec| {
+        let ty = &spec.ty;
+        let label = &spec.label;
+        let column = match &spec.column {
+            Some(c) => quote! { ::std::option::Option::Some(#c) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let position = match spec.position {
+            Some(p) => quote! { ::std::option::Option::Some(#p) },
+            None => quote! { ::std::option::Option::None },
+        };
+        let rust_type = ty.to_token_stream().to_string().replace(' ', "");
+        quote! {
+            ::trino::row::find_column::<#ty>(columns, #label, #column, #position, #rust_type, &mut errors)
+        }
+    });
+
+    let decode_fields = specs.iter().enumerate().map(|(i, spec)| {
+        let ty = &spec.ty;
+        let label = &spec.label;
+        let member = &spec.member;
+        quote! {
+            #member: ::trino::row::decode_field::<#ty>(row, types, binding[#i], #label)?
+        }
+    });
+
+    let krate = quote! { ::trino };
+    let result = quote! { #krate::error::Result };
+
+    Ok(quote! {
+        impl #impl_generics #krate::row::TrinoRow for #ident #ty_generics #where_clause {
+            fn bind(
+                columns: &[#krate::row::ColumnRef<'_>],
+            ) -> ::std::result::Result<::std::vec::Vec<usize>, ::std::vec::Vec<::std::string::String>> {
+                let mut errors = ::std::vec::Vec::new();
+                let binding = ::std::vec![#(#bind_fields),*];
+                if errors.is_empty() {
+                    ::std::result::Result::Ok(binding)
+                } else {
+                    ::std::result::Result::Err(errors)
+                }
+            }
+
+            fn from_row(
+                row: &[#krate::row::Value],
+                types: &[#krate::types::TrinoType],
+                binding: &[usize],
+            ) -> #result<Self> {
+                ::std::result::Result::Ok(#ident {
+                    #(#decode_fields,)*
+                })
+            }
+        }
+
+        impl #impl_generics #krate::row::FromTrino for #ident #ty_generics #where_clause {
+            fn accepts(ty: &#krate::types::TrinoType) -> bool {
+                #krate::row::row_accepts::<Self>(ty)
+            }
+
+            fn from_trino(value: &#krate::row::Value, ty: &#krate::types::TrinoType) -> #result<Self> {
+                #krate::row::row_from_trino::<Self>(value, ty)
+            }
+        }
+
+        impl #impl_generics #krate::macros::Accepts<#krate::macros::Row> for #ident #ty_generics #where_clause {}
+    })
+}
+
+// query_as!(Struct, "SELECT ...") checks the query's output columns against `Struct` at
+// compile time against the `.trino/` cache, which TRINO_QUERY_PREPARE=1 refreshes with an
+// EXPLAIN against TRINO_HOST
+#[proc_macro]
+pub fn query_as(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as query::QueryAsInput);
+    query::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
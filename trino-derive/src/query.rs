@@ -0,0 +1,315 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr, Path, Token};
+use trino_types::TrinoType;
+
+pub struct QueryAsInput {
+    target: Path,
+    sql: LitStr,
+}
+
+impl Parse for QueryAsInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let target = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let sql = input.parse()?;
+        input.parse::<Option<Token![,]>>()?;
+        Ok(QueryAsInput { target, sql })
+    }
+}
+
+// What gets committed under `.trino/` so builds work without a server
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedQuery {
+    pub query: String,
+    pub columns: Vec<CachedColumn>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CachedColumn {
+    pub name: String,
+    pub r#type: String,
+}
+
+pub fn expand(input: QueryAsInput) -> syn::Result<TokenStream> {
+    let sql = input.sql.value();
+    let described = describe(&sql).map_err(|e| syn::Error::new(input.sql.span(), e))?;
+
+    let target = &input.target;
+    let mut checks = Vec::new();
+    let mut fields = Vec::new();
+    let mut names = Vec::new();
+    for (i, column) in described.columns.iter().enumerate() {
+        let field = field_ident(&column.name).ok_or_else(|| {
+            syn::Error::new(
+                input.sql.span(),
+                format!(
+                    "column `{}` is not a valid Rust identifier, alias it in the query",
+                    column.name
+                ),
+            )
+        })?;
+        let marker = marker(&column.r#type);
+        let name = &column.name;
+        checks.push(quote! {
+            ::trino::macros::accepts::<#marker, _>(&row.#field);
+        });
+        fields.push(quote! {
+            #field: ::trino::row::decode_field(row, types, binding[#i], #name)?
+        });
+        names.push(name);
+    }
+
+    Ok(quote! {
+        {
+            #[allow(dead_code)]
+            fn __trino_check_columns(row: &#target) {
+                #(#checks)*
+            }
+
+            ::trino::macros::CheckedQuery::<#target>::new(
+                #sql,
+                |columns| ::trino::macros::bind_columns(columns, &[#(#names),*]),
+                |row, types, binding| {
+                    ::std::result::Result::Ok(#target {
+                        #(#fields,)*
+                    })
+                },
+            )
+        }
+    })
+}
+
+// Keywords come back as raw identifiers, so a `type` column lands in `r#type`
+fn field_ident(name: &str) -> Option<Ident> {
+    syn::parse_str::<Ident>(name)
+        .or_else(|_| syn::parse_str::<Ident>(&format!("r#{}", name)))
+        .ok()
+}
+
+fn hash_query(sql: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(sql.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn cache_path(sql: &str) -> Result<PathBuf, String> {
+    let dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| "CARGO_MANIFEST_DIR is not set".to_string())?;
+    Ok(PathBuf::from(dir)
+        .join(".trino")
+        .join(format!("query-{}.json", hash_query(sql))))
+}
+
+// Offline by default: the cache must already have an entry for this exact query text.
+// Only with TRINO_QUERY_PREPARE=1 (and TRINO_HOST) is the query explained against the
+// server and the cache refreshed, so a plain build never makes network calls or writes
+// into the source tree.
+fn describe(sql: &str) -> Result<CachedQuery, String> {
+    let path = cache_path(sql)?;
+    let prepare = std::env::var("TRINO_QUERY_PREPARE")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false);
+
+    if prepare {
+        let host = std::env::var("TRINO_HOST")
+            .map_err(|_| "TRINO_QUERY_PREPARE is set but TRINO_HOST isn't".to_string())?;
+        let described = explain(&host, sql)?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        let json = serde_json::to_string_pretty(&described).map_err(|e| e.to_string())?;
+        std::fs::write(&path, json).map_err(|e| e.to_string())?;
+        return Ok(described);
+    }
+
+    let cached = std::fs::read_to_string(&path).map_err(|_| {
+        format!(
+            "no cached description for this query at {}, build once with \
+             TRINO_QUERY_PREPARE=1 and TRINO_HOST set to prepare it",
+            path.display()
+        )
+    })?;
+    serde_json::from_str(&cached).map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Page {
+    next_uri: Option<String>,
+    data: Option<Vec<Vec<serde_json::Value>>>,
+    error: Option<serde_json::Value>,
+}
+
+#[derive(Deserialize)]
+struct PlanRoot {
+    descriptor: PlanDescriptor,
+    outputs: Vec<PlanOutput>,
+}
+
+#[derive(Deserialize)]
+struct PlanDescriptor {
+    #[serde(rename = "columnNames")]
+    column_names: String,
+}
+
+#[derive(Deserialize)]
+struct PlanOutput {
+    r#type: String,
+}
+
+// The same EXPLAIN trino-codegen runs, over the blocking client since we're in a proc macro
+fn explain(host: &str, sql: &str) -> Result<CachedQuery, String> {
+    let port = std::env::var("TRINO_PORT").unwrap_or_else(|_| "8080".to_string());
+    let user = std::env::var("TRINO_USER").unwrap_or_else(|_| "trino".to_string());
+    let password = std::env::var("TRINO_PASSWORD").ok();
+
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(60))
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut request = client
+        .post(format!("{}:{}/v1/statement", host, port))
+        .header("X-Trino-User", &user)
+        .body(format!("EXPLAIN (TYPE LOGICAL, FORMAT JSON) {}", sql));
+    if let Some(password) = &password {
+        request = request.basic_auth(&user, Some(password));
+    }
+
+    let mut page: Page = request
+        .send()
+        .and_then(|r| r.json())
+        .map_err(|e| e.to_string())?;
+    loop {
+        if let Some(error) = page.error {
+            return Err(format!("trino rejected the query: {}", error));
+        }
+        if let Some(plan) = page
+            .data
+            .as_ref()
+            .and_then(|rows| rows.first())
+            .and_then(|row| row.first())
+            .and_then(|plan| plan.as_str())
+        {
+            let root: PlanRoot = serde_json::from_str(plan).map_err(|e| e.to_string())?;
+            let names = root
+                .descriptor
+                .column_names
+                .trim_matches(|c| c == '[' || c == ']')
+                .split(',')
+                .map(|s| s.trim().to_string());
+            return Ok(CachedQuery {
+                query: sql.to_string(),
+                columns: names
+                    .zip(root.outputs)
+                    .map(|(name, output)| CachedColumn {
+                        name,
+                        r#type: output.r#type,
+                    })
+                    .collect(),
+            });
+        }
+        let next_uri = page
+            .next_uri
+            .ok_or_else(|| "EXPLAIN finished without returning a plan".to_string())?;
+        let mut request = client.get(&next_uri);
+        if let Some(password) = &password {
+            request = request.basic_auth(&user, Some(password));
+        }
+        page = request
+            .send()
+            .and_then(|r| r.json())
+            .map_err(|e| e.to_string())?;
+    }
+}
+
+// Maps a Trino type onto the marker the runtime crate uses for its `Accepts` checks
+pub fn marker(ty: &str) -> TokenStream {
+    match ty.parse::<TrinoType>() {
+        Ok(ty) => type_marker(&ty),
+        Err(_) => quote! { ::trino::macros::Unknown },
+    }
+}
+
+fn type_marker(ty: &TrinoType) -> TokenStream {
+    let m = |name: &str| {
+        let ident = Ident::new(name, Span::call_site());
+        quote! { ::trino::macros::#ident }
+    };
+    match ty {
+        TrinoType::Boolean => m("Boolean"),
+        TrinoType::TinyInt => m("TinyInt"),
+        TrinoType::SmallInt => m("SmallInt"),
+        TrinoType::Integer => m("Integer"),
+        TrinoType::BigInt => m("BigInt"),
+        TrinoType::Real => m("Real"),
+        TrinoType::Double => m("Double"),
+        TrinoType::Decimal { .. } => m("Decimal"),
+        TrinoType::Varchar(_) | TrinoType::Char(_) => m("Text"),
+        TrinoType::Varbinary => m("Varbinary"),
+        TrinoType::Json => m("Json"),
+        TrinoType::Date => m("Date"),
+        TrinoType::Time {
+            with_time_zone: true,
+            ..
+        } => m("TimeTz"),
+        TrinoType::Time { .. } => m("Time"),
+        TrinoType::Timestamp {
+            with_time_zone: true,
+            ..
+        } => m("TimestampTz"),
+        TrinoType::Timestamp { .. } => m("Timestamp"),
+        TrinoType::IntervalDayToSecond | TrinoType::IntervalYearToMonth => m("Interval"),
+        TrinoType::Uuid => m("Uuid"),
+        TrinoType::IpAddress => m("IpAddress"),
+        TrinoType::Row(_) => m("Row"),
+        TrinoType::Array(element) => {
+            let element = type_marker(element);
+            quote! { ::trino::macros::Array<#element> }
+        }
+        TrinoType::Map(key, value) => {
+            let key = type_marker(key);
+            let value = type_marker(value);
+            quote! { ::trino::macros::Map<#key, #value> }
+        }
+        TrinoType::Other(_) => m("Unknown"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_marker() {
+        assert_eq!(
+            marker("map(varchar(4), array(timestamp(3) with time zone))").to_string(),
+            quote! {
+                ::trino::macros::Map<
+                    ::trino::macros::Text,
+                    ::trino::macros::Array<::trino::macros::TimestampTz>
+                >
+            }
+            .to_string()
+        );
+        assert_eq!(
+            marker("row(a integer, b varchar)").to_string(),
+            quote! { ::trino::macros::Row }.to_string()
+        );
+        assert_eq!(
+            marker("interval year to month").to_string(),
+            quote! { ::trino::macros::Interval }.to_string()
+        );
+        assert_eq!(
+            marker("HyperLogLog").to_string(),
+            quote! { ::trino::macros::Unknown }.to_string()
+        );
+    }
+}
@@ -0,0 +1,391 @@
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrinoType {
+    Boolean,
+    TinyInt,
+    SmallInt,
+    Integer,
+    BigInt,
+    Real,
+    Double,
+    Decimal {
+        precision: Option<u32>,
+        scale: Option<u32>,
+    },
+    Varchar(Option<u32>),
+    Char(Option<u32>),
+    Varbinary,
+    Json,
+    Date,
+    Time {
+        precision: Option<u32>,
+        with_time_zone: bool,
+    },
+    Timestamp {
+        precision: Option<u32>,
+        with_time_zone: bool,
+    },
+    IntervalDayToSecond,
+    IntervalYearToMonth,
+    Array(Box<TrinoType>),
+    Map(Box<TrinoType>, Box<TrinoType>),
+    Row(Vec<RowField>),
+    Uuid,
+    IpAddress,
+    // Anything we don't model explicitly, kept verbatim
+    Other(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowField {
+    pub name: Option<String>,
+    pub r#type: TrinoType,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTypeError(String);
+
+impl fmt::Display for ParseTypeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid trino type: {}", self.0)
+    }
+}
+
+impl std::error::Error for ParseTypeError {}
+
+impl FromStr for TrinoType {
+    type Err = ParseTypeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_type(s.trim())
+    }
+}
+
+fn parse_type(s: &str) -> Result<TrinoType, ParseTypeError> {
+    let err = || ParseTypeError(s.to_string());
+
+    let name_len = s
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(s.len());
+    if name_len == 0 {
+        return Err(err());
+    }
+    let name = s[..name_len].to_ascii_lowercase();
+    let mut rest = &s[name_len..];
+
+    let mut args = None;
+    if rest.starts_with('(') {
+        let close = matching_paren(rest).ok_or_else(err)?;
+        args = Some(split_top_level(&rest[1..close]));
+        rest = &rest[close + 1..];
+    }
+    let suffix = rest.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    let numbers = |args: &Option<Vec<&str>>| -> Result<Vec<u32>, ParseTypeError> {
+        args.iter()
+            .flatten()
+            .map(|a| a.trim().parse().map_err(|_| err()))
+            .collect()
+    };
+    let types = |args: &Option<Vec<&str>>| -> Result<Vec<TrinoType>, ParseTypeError> {
+        args.iter()
+            .flatten()
+            .map(|a| parse_type(a.trim()))
+            .collect()
+    };
+
+    let time_zone = match suffix.as_str() {
+        "with time zone" => Some(true),
+        "" | "without time zone" => Some(false),
+        _ => None,
+    };
+
+    let ty = match (name.as_str(), suffix.as_str()) {
+        ("time", _) | ("timestamp", _) => {
+            let with_time_zone = time_zone.ok_or_else(err)?;
+            let precision = numbers(&args)?.first().copied();
+            if name == "time" {
+                TrinoType::Time {
+                    precision,
+                    with_time_zone,
+                }
+            } else {
+                TrinoType::Timestamp {
+                    precision,
+                    with_time_zone,
+                }
+            }
+        }
+        ("interval", "day to second") => TrinoType::IntervalDayToSecond,
+        ("interval", "year to month") => TrinoType::IntervalYearToMonth,
+        ("double", "precision") => TrinoType::Double,
+        (_, "") => match name.as_str() {
+            "boolean" => TrinoType::Boolean,
+            "tinyint" => TrinoType::TinyInt,
+            "smallint" => TrinoType::SmallInt,
+            "integer" | "int" => TrinoType::Integer,
+            "bigint" => TrinoType::BigInt,
+            "real" => TrinoType::Real,
+            "double" => TrinoType::Double,
+            "decimal" => {
+                let n = numbers(&args)?;
+                TrinoType::Decimal {
+                    precision: n.first().copied(),
+                    scale: n.get(1).copied(),
+                }
+            }
+            "varchar" => TrinoType::Varchar(numbers(&args)?.first().copied()),
+            "char" => TrinoType::Char(numbers(&args)?.first().copied()),
+            "varbinary" => TrinoType::Varbinary,
+            "json" => TrinoType::Json,
+            "date" => TrinoType::Date,
+            "uuid" => TrinoType::Uuid,
+            "ipaddress" => TrinoType::IpAddress,
+            "array" => match types(&args)?.as_slice() {
+                [element] => TrinoType::Array(Box::new(element.clone())),
+                _ => return Err(err()),
+            },
+            "map" => match types(&args)?.as_slice() {
+                [key, value] => TrinoType::Map(Box::new(key.clone()), Box::new(value.clone())),
+                _ => return Err(err()),
+            },
+            "row" => TrinoType::Row(
+                args.iter()
+                    .flatten()
+                    .map(|f| parse_row_field(f.trim()))
+                    .collect::<Result<_, _>>()?,
+            ),
+            _ => TrinoType::Other(s.to_string()),
+        },
+        _ => return Err(err()),
+    };
+    Ok(ty)
+}
+
+// Row fields are either `type` or `name type`, where the name may be quoted
+fn parse_row_field(s: &str) -> Result<RowField, ParseTypeError> {
+    if let Some(quoted) = s.strip_prefix('"') {
+        let mut name = String::new();
+        let mut chars = quoted.char_indices();
+        while let Some((i, c)) = chars.next() {
+            if c == '"' {
+                if quoted[i + 1..].starts_with('"') {
+                    name.push('"');
+                    chars.next();
+                    continue;
+                }
+                return Ok(RowField {
+                    name: Some(name),
+                    r#type: parse_type(quoted[i + 1..].trim())?,
+                });
+            }
+            name.push(c);
+        }
+        return Err(ParseTypeError(s.to_string()));
+    }
+
+    if let Ok(ty) = parse_type(s) {
+        return Ok(RowField {
+            name: None,
+            r#type: ty,
+        });
+    }
+    match s.split_once(char::is_whitespace) {
+        Some((name, ty)) => Ok(RowField {
+            name: Some(name.to_string()),
+            r#type: parse_type(ty.trim())?,
+        }),
+        None => Err(ParseTypeError(s.to_string())),
+    }
+}
+
+fn matching_paren(s: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut quoted = false;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0;
+    let mut quoted = false;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '"' => quoted = !quoted,
+            '(' if !quoted => depth += 1,
+            ')' if !quoted => depth -= 1,
+            ',' if !quoted && depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
+
+impl fmt::Display for TrinoType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let time_zone = |with_time_zone: &bool| {
+            if *with_time_zone {
+                " with time zone"
+            } else {
+                ""
+            }
+        };
+        match self {
+            TrinoType::Boolean => write!(f, "boolean"),
+            TrinoType::TinyInt => write!(f, "tinyint"),
+            TrinoType::SmallInt => write!(f, "smallint"),
+            TrinoType::Integer => write!(f, "integer"),
+            TrinoType::BigInt => write!(f, "bigint"),
+            TrinoType::Real => write!(f, "real"),
+            TrinoType::Double => write!(f, "double"),
+            TrinoType::Decimal {
+                precision: Some(p),
+                scale: Some(s),
+            } => write!(f, "decimal({},{})", p, s),
+            TrinoType::Decimal {
+                precision: Some(p),
+                scale: None,
+            } => write!(f, "decimal({})", p),
+            TrinoType::Decimal { .. } => write!(f, "decimal"),
+            TrinoType::Varchar(Some(n)) => write!(f, "varchar({})", n),
+            TrinoType::Varchar(None) => write!(f, "varchar"),
+            TrinoType::Char(Some(n)) => write!(f, "char({})", n),
+            TrinoType::Char(None) => write!(f, "char"),
+            TrinoType::Varbinary => write!(f, "varbinary"),
+            TrinoType::Json => write!(f, "json"),
+            TrinoType::Date => write!(f, "date"),
+            TrinoType::Time {
+                precision,
+                with_time_zone,
+            } => match precision {
+                Some(p) => write!(f, "time({}){}", p, time_zone(with_time_zone)),
+                None => write!(f, "time{}", time_zone(with_time_zone)),
+            },
+            TrinoType::Timestamp {
+                precision,
+                with_time_zone,
+            } => match precision {
+                Some(p) => write!(f, "timestamp({}){}", p, time_zone(with_time_zone)),
+                None => write!(f, "timestamp{}", time_zone(with_time_zone)),
+            },
+            TrinoType::IntervalDayToSecond => write!(f, "interval day to second"),
+            TrinoType::IntervalYearToMonth => write!(f, "interval year to month"),
+            TrinoType::Array(element) => write!(f, "array({})", element),
+            TrinoType::Map(key, value) => write!(f, "map({}, {})", key, value),
+            TrinoType::Row(fields) => {
+                write!(f, "row(")?;
+                for (i, field) in fields.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match &field.name {
+                        Some(name)
+                            if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') =>
+                        {
+                            write!(f, "{} {}", name, field.r#type)?
+                        }
+                        Some(name) => {
+                            write!(f, "\"{}\" {}", name.replace('"', "\"\""), field.r#type)?
+                        }
+                        None => write!(f, "{}", field.r#type)?,
+                    }
+                }
+                write!(f, ")")
+            }
+            TrinoType::Uuid => write!(f, "uuid"),
+            TrinoType::IpAddress => write!(f, "ipaddress"),
+            TrinoType::Other(raw) => write!(f, "{}", raw),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_simple_types() {
+        let cases = vec![
+            ("integer", TrinoType::Integer),
+            ("varchar", TrinoType::Varchar(None)),
+            ("varchar(4)", TrinoType::Varchar(Some(4))),
+            (
+                "decimal(38,0)",
+                TrinoType::Decimal {
+                    precision: Some(38),
+                    scale: Some(0),
+                },
+            ),
+            (
+                "timestamp(3) with time zone",
+                TrinoType::Timestamp {
+                    precision: Some(3),
+                    with_time_zone: true,
+                },
+            ),
+            (
+                "time with time zone",
+                TrinoType::Time {
+                    precision: None,
+                    with_time_zone: true,
+                },
+            ),
+            ("interval day to second", TrinoType::IntervalDayToSecond),
+            ("HyperLogLog", TrinoType::Other("HyperLogLog".to_string())),
+        ];
+        for (raw, exp) in cases {
+            assert_eq!(raw.parse::<TrinoType>().unwrap(), exp, "{}", raw);
+        }
+    }
+
+    #[test]
+    fn parse_nested_types() {
+        let ty: TrinoType =
+            r#"map(varchar(4), array(row(a integer, "b c" timestamp(6) with time zone, double)))"#
+                .parse()
+                .unwrap();
+        let exp = TrinoType::Map(
+            Box::new(TrinoType::Varchar(Some(4))),
+            Box::new(TrinoType::Array(Box::new(TrinoType::Row(vec![
+                RowField {
+                    name: Some("a".to_string()),
+                    r#type: TrinoType::Integer,
+                },
+                RowField {
+                    name: Some("b c".to_string()),
+                    r#type: TrinoType::Timestamp {
+                        precision: Some(6),
+                        with_time_zone: true,
+                    },
+                },
+                RowField {
+                    name: None,
+                    r#type: TrinoType::Double,
+                },
+            ])))),
+        );
+        assert_eq!(ty, exp);
+        assert!("array(integer, integer)".parse::<TrinoType>().is_err());
+        assert!("integer junk".parse::<TrinoType>().is_err());
+    }
+}
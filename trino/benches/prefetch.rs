@@ -0,0 +1,50 @@
+#[path = "../tests/mock.rs"]
+mod mock;
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::Deserialize;
+use trino::PrefetchConfig;
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Row {
+    id: u64,
+    name: String,
+    score: f64,
+    comment: String,
+}
+
+const PAGES: usize = 20;
+const ROWS_PER_PAGE: usize = 2000;
+
+fn bench_prefetch(c: &mut Criterion) {
+    let rt = tokio::runtime::Runtime::new().unwrap();
+    let server = rt.block_on(mock::start(PAGES, ROWS_PER_PAGE, Duration::from_millis(2)));
+
+    let mut group = c.benchmark_group("query");
+    group.throughput(Throughput::Elements((PAGES * ROWS_PER_PAGE) as u64));
+    group.sample_size(20);
+
+    for max_pages in [1, 2, 4, 8] {
+        let client = server.client(PrefetchConfig {
+            max_pages,
+            ..PrefetchConfig::default()
+        });
+        group.bench_with_input(
+            BenchmarkId::new("max_pages", max_pages),
+            &client,
+            |b, client| {
+                b.to_async(&rt).iter(|| async {
+                    let rows: Vec<Row> = client.query("SELECT * FROM mock").await.unwrap();
+                    assert_eq!(rows.len(), PAGES * ROWS_PER_PAGE);
+                })
+            },
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_prefetch);
+criterion_main!(benches);
@@ -0,0 +1,524 @@
+// `EXPLAIN ANALYZE` only comes as text, this parses it into per-operator runtime statistics,
+// see `Client::explain_analyze`. Lines the parser doesn't know are kept in `details`, so
+// output from other Trino versions still parses, with fewer statistics filled in.
+use std::time::Duration;
+
+use crate::error::{Error, Result};
+use crate::explain::Estimate;
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AnalyzedPlan {
+    pub version: Option<String>,
+    pub queued: Option<Duration>,
+    pub analysis: Option<Duration>,
+    pub planning: Option<Duration>,
+    pub execution: Option<Duration>,
+    pub fragments: Vec<AnalyzedFragment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct AnalyzedFragment {
+    pub id: u32,
+    // e.g. `SOURCE`, `HASH`, `SINGLE`
+    pub partitioning: String,
+    pub stats: RuntimeStats,
+    pub output_layout: Option<String>,
+    pub output_partitioning: Option<String>,
+    pub details: Vec<String>,
+    pub root: Option<Operator>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Operator {
+    pub name: String,
+    // what's inside `Name[...]`, unparsed
+    pub arguments: String,
+    pub layout: Option<String>,
+    // one per step of fused operators like `ScanFilterProject`, the last is the output
+    pub estimates: Vec<Estimate>,
+    pub stats: RuntimeStats,
+    pub details: Vec<String>,
+    pub children: Vec<Operator>,
+}
+
+// `wall_time` is what Trino calls `Scheduled`
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct RuntimeStats {
+    pub cpu_time: Option<Duration>,
+    pub wall_time: Option<Duration>,
+    pub blocked_time: Option<Duration>,
+    pub input_rows: Option<u64>,
+    pub input_bytes: Option<u64>,
+    pub output_rows: Option<u64>,
+    pub output_bytes: Option<u64>,
+    pub spilled_bytes: Option<u64>,
+}
+
+impl AnalyzedPlan {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut plan = AnalyzedPlan::default();
+        let mut lines = text.lines().peekable();
+
+        while let Some(line) = lines.peek() {
+            if line.starts_with("Fragment ") {
+                break;
+            }
+            let line = lines.next().expect("peeked").trim();
+            if let Some(version) = line.strip_prefix("Trino version: ") {
+                plan.version = Some(version.to_string());
+                continue;
+            }
+            for (key, value) in stat_items(line) {
+                match key {
+                    "Queued" => plan.queued = parse_duration(value),
+                    "Analysis" => plan.analysis = parse_duration(value),
+                    "Planning" => plan.planning = parse_duration(value),
+                    "Execution" => plan.execution = parse_duration(value),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut body: Vec<&str> = Vec::new();
+        let mut header: Option<&str> = None;
+        for line in lines {
+            if line.starts_with("Fragment ") {
+                if let Some(header) = header.take() {
+                    plan.fragments.push(parse_fragment(header, &body)?);
+                }
+                header = Some(line);
+                body.clear();
+            } else if !line.trim().is_empty() {
+                body.push(line);
+            }
+        }
+        if let Some(header) = header {
+            plan.fragments.push(parse_fragment(header, &body)?);
+        }
+
+        if plan.fragments.is_empty() {
+            return Err(Error::Decode(
+                "EXPLAIN ANALYZE output has no fragments".to_string(),
+            ));
+        }
+        Ok(plan)
+    }
+
+    // Every operator of every fragment, depth first
+    pub fn operators(&self) -> impl Iterator<Item = &Operator> {
+        self.fragments
+            .iter()
+            .filter_map(|f| f.root.as_ref())
+            .flat_map(Operator::operators)
+    }
+}
+
+impl Operator {
+    // This operator and everything below it in its fragment, depth first
+    pub fn operators(&self) -> Box<dyn Iterator<Item = &Operator> + '_> {
+        Box::new(std::iter::once(self).chain(self.children.iter().flat_map(Operator::operators)))
+    }
+
+    // The final output row estimate
+    pub fn estimated_rows(&self) -> Option<f64> {
+        self.estimates.last()?.output_row_count
+    }
+
+    // Actual over estimated output rows: above 1 the optimizer underestimated, below 1 it
+    // overestimated. `None` without both numbers, or when the estimate is zero.
+    pub fn estimate_ratio(&self) -> Option<f64> {
+        let estimated = self.estimated_rows()?;
+        let actual = self.stats.output_rows? as f64;
+        if estimated == 0.0 {
+            None
+        } else {
+            Some(actual / estimated)
+        }
+    }
+}
+
+// `Fragment 1 [HASH]` and the lines under it
+fn parse_fragment(header: &str, body: &[&str]) -> Result<AnalyzedFragment> {
+    let invalid = || Error::Decode(format!("invalid fragment header `{}`", header));
+    let rest = header.strip_prefix("Fragment ").ok_or_else(invalid)?;
+    let (id, partitioning) = match rest.split_once(' ') {
+        Some((id, partitioning)) => (id, partitioning.trim()),
+        None => (rest, ""),
+    };
+    let mut fragment = AnalyzedFragment {
+        id: id.parse().map_err(|_| invalid())?,
+        partitioning: partitioning
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string(),
+        ..AnalyzedFragment::default()
+    };
+
+    // operators in an arena with their depth, linked up once the fragment is read
+    let mut operators: Vec<(usize, Operator, Vec<usize>)> = Vec::new();
+    let mut stack: Vec<usize> = Vec::new();
+    let mut roots: Vec<usize> = Vec::new();
+    for line in body {
+        let (column, content, marker) = split_tree_prefix(line);
+        if let Some((name, arguments)) = operator_line(content) {
+            if !marker && !stack.is_empty() {
+                fragment.details.push(content.to_string());
+                continue;
+            }
+            while let Some(&top) = stack.last() {
+                if operators[top].0 >= column {
+                    stack.pop();
+                } else {
+                    break;
+                }
+            }
+            let index = operators.len();
+            operators.push((
+                column,
+                Operator {
+                    name: name.to_string(),
+                    arguments: arguments.to_string(),
+                    ..Operator::default()
+                },
+                Vec::new(),
+            ));
+            match stack.last() {
+                Some(&parent) => operators[parent].2.push(index),
+                None => roots.push(index),
+            }
+            stack.push(index);
+            continue;
+        }
+
+        while let Some(&top) = stack.last() {
+            if operators[top].0 >= column {
+                stack.pop();
+            } else {
+                break;
+            }
+        }
+        match stack.last() {
+            Some(&top) => operator_detail(&mut operators[top].1, content),
+            None => fragment_detail(&mut fragment, content),
+        }
+    }
+
+    fn build(index: usize, operators: &mut Vec<(usize, Operator, Vec<usize>)>) -> Operator {
+        let children = std::mem::take(&mut operators[index].2);
+        let mut operator = std::mem::take(&mut operators[index].1);
+        operator.children = children
+            .into_iter()
+            .map(|child| build(child, operators))
+            .collect();
+        operator
+    }
+    fragment.root = roots.first().map(|&root| build(root, &mut operators));
+    Ok(fragment)
+}
+
+// The column the content of a line starts at, past the `│`, `├─` and `└─` of the tree, and
+// whether it had a branch marker
+fn split_tree_prefix(line: &str) -> (usize, &str, bool) {
+    let mut marker = false;
+    for (column, (offset, c)) in line.char_indices().enumerate() {
+        match c {
+            ' ' | '│' => {}
+            '├' | '└' | '─' => marker = true,
+            _ => return (column, line[offset..].trim_end(), marker),
+        }
+    }
+    (line.chars().count(), "", marker)
+}
+
+// `Aggregate[type = FINAL, keys = [clerk]]` or a bare `Output`, as opposed to `Key: value`
+fn operator_line(content: &str) -> Option<(&str, &str)> {
+    let end = content
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .unwrap_or(content.len());
+    let (name, rest) = content.split_at(end);
+    if name.is_empty() || !name.starts_with(|c: char| c.is_ascii_uppercase()) {
+        return None;
+    }
+    if rest.is_empty() {
+        return Some((name, ""));
+    }
+    let arguments = rest.strip_prefix('[')?;
+    // older versions append ` => [layout]`
+    let arguments = arguments.split(" => ").next().unwrap_or(arguments);
+    Some((name, arguments.strip_suffix(']').unwrap_or(arguments)))
+}
+
+fn fragment_detail(fragment: &mut AnalyzedFragment, content: &str) {
+    if let Some(layout) = content.strip_prefix("Output layout: ") {
+        fragment.output_layout = Some(layout.to_string());
+    } else if let Some(partitioning) = content.strip_prefix("Output partitioning: ") {
+        fragment.output_partitioning = Some(partitioning.to_string());
+    } else if !parse_stats(&mut fragment.stats, content) {
+        fragment.details.push(content.to_string());
+    }
+}
+
+fn operator_detail(operator: &mut Operator, content: &str) {
+    if let Some(layout) = content.strip_prefix("Layout: ") {
+        operator.layout = Some(layout.to_string());
+    } else if let Some(estimates) = content.strip_prefix("Estimates: ") {
+        operator.estimates = estimates.split("}/{").map(parse_estimate).collect();
+    } else if !parse_stats(&mut operator.stats, content) {
+        operator.details.push(content.to_string());
+    }
+}
+
+// Fills in whatever `content` has, returning false if it isn't a statistics line at all
+fn parse_stats(stats: &mut RuntimeStats, content: &str) -> bool {
+    let mut known = false;
+    for (key, value) in stat_items(content) {
+        // `Input: 1000 rows (37.11kB); per task: ...`
+        let value = value.split(';').next().unwrap_or(value).trim();
+        match key {
+            "CPU" => stats.cpu_time = parse_duration(value),
+            "Scheduled" | "Wall" => stats.wall_time = parse_duration(value),
+            "Blocked" => stats.blocked_time = parse_duration(value),
+            "Input" => {
+                let (rows, bytes) = parse_rows(value);
+                stats.input_rows = rows.or(stats.input_rows);
+                stats.input_bytes = bytes.or(stats.input_bytes);
+            }
+            "Output" => {
+                let (rows, bytes) = parse_rows(value);
+                stats.output_rows = rows;
+                stats.output_bytes = bytes;
+            }
+            "Spilled" | "Spilled data" => stats.spilled_bytes = parse_data_size(value),
+            _ => continue,
+        }
+        known = true;
+    }
+    known
+}
+
+// `Key: value` pairs separated by commas, except those inside parentheses; the fragment
+// level `Blocked 46.21s (Input: ...)` has no colon
+fn stat_items(line: &str) -> Vec<(&str, &str)> {
+    fn item(item: &str) -> Option<(&str, &str)> {
+        let item = item.trim();
+        let paren = item.find('(').unwrap_or(item.len());
+        let (key, value) = match item[..paren].find(": ") {
+            Some(colon) => (&item[..colon], &item[colon + 2..]),
+            None => item.split_once(' ')?,
+        };
+        Some((key.trim(), value.trim()))
+    }
+
+    let mut items = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in line.char_indices() {
+        match c {
+            '(' | '{' | '[' => depth += 1,
+            ')' | '}' | ']' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                items.extend(item(&line[start..i]));
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    items.extend(item(&line[start..]));
+    items
+}
+
+// `{rows: 15000 (395.51kB), cpu: 1.23M, memory: 0B, network: ?}`, unknowns are `?`
+fn parse_estimate(text: &str) -> Estimate {
+    let text = text.trim().trim_start_matches('{').trim_end_matches('}');
+    let mut estimate = Estimate::default();
+    for (key, value) in stat_items(text) {
+        match key {
+            "rows" => {
+                let (rows, bytes) = value.split_once(' ').unwrap_or((value, ""));
+                estimate.output_row_count = parse_number(rows);
+                estimate.output_size_in_bytes =
+                    parse_data_size(bytes.trim().trim_start_matches('(').trim_end_matches(')'))
+                        .map(|b| b as f64);
+            }
+            "cpu" => estimate.cpu_cost = parse_number(value),
+            "memory" => estimate.memory_cost = parse_data_size(value).map(|b| b as f64),
+            "network" => estimate.network_cost = parse_data_size(value).map(|b| b as f64),
+            _ => {}
+        }
+    }
+    estimate
+}
+
+// `1000 rows (28.32kB)`
+fn parse_rows(value: &str) -> (Option<u64>, Option<u64>) {
+    let rows = value
+        .strip_suffix(')')
+        .and_then(|v| v.rsplit_once(" ("))
+        .map(|(rows, _)| rows)
+        .unwrap_or(value);
+    let bytes = value
+        .rsplit_once(" (")
+        .and_then(|(_, size)| size.strip_suffix(')'))
+        .and_then(parse_data_size);
+    let rows = rows
+        .trim()
+        .strip_suffix("rows")
+        .or_else(|| rows.trim().strip_suffix("row"))
+        .and_then(parse_number)
+        .map(|n| n as u64);
+    (rows, bytes)
+}
+
+// Trino's succinct durations: `374.17us`, `8.00ms`, `3.06s`, `1.50m`, `2.00h`, `1.00d`
+pub(crate) fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.split_whitespace().next()?;
+    let split = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "ns" => number / 1e9,
+        "us" => number / 1e6,
+        "ms" => number / 1e3,
+        "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        "d" => number * 86400.0,
+        _ => return None,
+    };
+    Some(Duration::from_secs_f64(seconds))
+}
+
+// Trino's succinct data sizes, powers of 1024: `0B`, `37.11kB`, `1.2MB`, ...
+pub(crate) fn parse_data_size(value: &str) -> Option<u64> {
+    let value = value.split_whitespace().next()?;
+    let split = value.find(|c: char| c.is_ascii_alphabetic())?;
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let power = match unit {
+        "B" => 0,
+        "kB" | "KB" => 1,
+        "MB" => 2,
+        "GB" => 3,
+        "TB" => 4,
+        "PB" => 5,
+        _ => return None,
+    };
+    Some((number * 1024f64.powi(power)).round() as u64)
+}
+
+// Plain or with a `K`/`M`/`B` (thousand, million, billion) suffix, `?` is unknown
+fn parse_number(value: &str) -> Option<f64> {
+    let value = value.trim().replace(',', "");
+    let (number, scale) = match value.chars().last()? {
+        'k' | 'K' => (&value[..value.len() - 1], 1e3),
+        'M' => (&value[..value.len() - 1], 1e6),
+        'B' | 'G' => (&value[..value.len() - 1], 1e9),
+        'T' => (&value[..value.len() - 1], 1e12),
+        _ => (value.as_str(), 1.0),
+    };
+    number.parse::<f64>().ok().map(|n| n * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const OUTPUT: &str = "\
+Trino version: 435
+Queued: 374.17us, Analysis: 190.96ms, Planning: 179.03ms, Execution: 3.06s
+Fragment 1 [HASH]
+    CPU: 22.58ms, Scheduled: 96.72ms, Blocked 46.21s (Input: 23.06s, Output: 0.00ns), Input: 1000 rows (37.11kB); per task: avg.: 1000.00 std.dev.: 0.00, Output: 1000 rows (28.32kB)
+    Output layout: [clerk, count]
+    Output partitioning: SINGLE []
+    Aggregate[type = FINAL, keys = [clerk], hash = [$hashvalue]]
+    │   Layout: [clerk:varchar(15), $hashvalue:bigint, count:bigint]
+    │   Estimates: {rows: 1000 (28.32kB), cpu: ?, memory: ?, network: 0B}
+    │   CPU: 8.00ms (3.51%), Scheduled: 22.00ms (5.28%), Blocked: 0.00ns (0.00%), Output: 1000 rows (37.11kB)
+    │   Input avg.: 15.63 rows, Input std.dev.: 24.36%
+    │   count := count(\"count_0\")
+    ├─ LocalExchange[partitioning = HASH, hashColumn = [$hashvalue], arguments = [\"clerk\"]]
+    │  │   Layout: [clerk:varchar(15), count_0:bigint, $hashvalue:bigint]
+    │  │   CPU: 2.00ms (0.88%), Scheduled: 4.00ms (0.96%), Blocked: 23.15s (50.10%), Output: 1000 rows (37.11kB)
+    │  └─ RemoteSource[sourceFragmentIds = [2]]
+    │         Layout: [clerk:varchar(15), count_0:bigint, $hashvalue_1:bigint]
+    │         CPU: 0.00ns (0.00%), Scheduled: 0.00ns (0.00%), Blocked: 23.06s (49.90%), Output: 1000 rows (37.11kB)
+    └─ Values
+           Output: 0 rows (0B)
+
+Fragment 2 [SOURCE]
+    CPU: 328.00ms, Scheduled: 1.20s, Blocked 0.00ns (Input: 0.00ns, Output: 0.00ns), Input: 15000 rows (0B); per task: avg.: 15000.00 std.dev.: 0.00, Output: 1000 rows (37.11kB)
+    Output layout: [clerk, count_0, $hashvalue_2]
+    Output partitioning: HASH [clerk][$hashvalue_2]
+    ScanFilterProject[table = tpch:tiny:orders, filterPredicate = (orderstatus = 'F')]
+           Layout: [clerk:varchar(15), $hashvalue_2:bigint]
+           Estimates: {rows: 15000 (395.51kB), cpu: 1.74M, memory: 0B, network: 0B}/{rows: 7500 (197.76kB), cpu: 2.1M, memory: 0B, network: 0B}
+           CPU: 36.00ms (15.79%), Scheduled: 1.00m (15.11%), Blocked: 0.00ns (0.00%), Output: 15000 rows (395.51kB), Spilled: 1.50MB
+           Input: 15000 rows (0B), Filtered: 0.00%, Physical input: 0B, Physical input time: 0.00ns
+";
+
+    #[test]
+    fn parse_explain_analyze() {
+        let plan = AnalyzedPlan::parse(OUTPUT).unwrap();
+        assert_eq!(plan.version.as_deref(), Some("435"));
+        assert_eq!(plan.execution, Some(Duration::from_millis(3060)));
+        assert_eq!(plan.fragments.len(), 2);
+
+        let fragment = &plan.fragments[0];
+        assert_eq!((fragment.id, fragment.partitioning.as_str()), (1, "HASH"));
+        assert_eq!(fragment.stats.cpu_time, Some(Duration::from_micros(22580)));
+        assert_eq!(
+            fragment.stats.blocked_time,
+            Some(Duration::from_millis(46210))
+        );
+        assert_eq!(fragment.stats.input_rows, Some(1000));
+        assert_eq!(fragment.stats.input_bytes, Some(38001));
+        assert_eq!(fragment.output_partitioning.as_deref(), Some("SINGLE []"));
+
+        let root = fragment.root.as_ref().unwrap();
+        assert_eq!(root.name, "Aggregate");
+        assert_eq!(
+            root.arguments,
+            "type = FINAL, keys = [clerk], hash = [$hashvalue]"
+        );
+        assert_eq!(
+            root.details,
+            vec![
+                "Input avg.: 15.63 rows, Input std.dev.: 24.36%",
+                "count := count(\"count_0\")"
+            ]
+        );
+        let children: Vec<&str> = root.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(children, vec!["LocalExchange", "Values"]);
+        assert_eq!(root.children[0].children[0].name, "RemoteSource");
+        assert_eq!(root.children[1].stats.output_rows, Some(0));
+        assert_eq!(root.estimate_ratio(), Some(1.0));
+
+        let scan = plan
+            .operators()
+            .find(|o| o.name == "ScanFilterProject")
+            .unwrap();
+        assert_eq!(scan.estimates.len(), 2);
+        assert_eq!(scan.estimated_rows(), Some(7500.0));
+        assert_eq!(scan.estimate_ratio(), Some(2.0));
+        assert_eq!(scan.estimates[0].cpu_cost, Some(1.74e6));
+        assert_eq!(scan.stats.wall_time, Some(Duration::from_secs(60)));
+        assert_eq!(scan.stats.input_rows, Some(15000));
+        assert_eq!(scan.stats.spilled_bytes, Some(1572864));
+        assert_eq!(plan.operators().count(), 5);
+    }
+
+    #[test]
+    fn parse_units() {
+        assert_eq!(
+            parse_duration("374.17us"),
+            Some(Duration::from_nanos(374170))
+        );
+        assert_eq!(parse_duration("2.00h"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_duration("?"), None);
+        assert_eq!(parse_data_size("37.11kB"), Some(38001));
+        assert_eq!(parse_data_size("2GB"), Some(2 << 30));
+        assert_eq!(parse_rows("1 row (10B)"), (Some(1), Some(10)));
+        assert_eq!(parse_number("?"), None);
+        assert!(AnalyzedPlan::parse("Trino version: 435\n").is_err());
+    }
+}
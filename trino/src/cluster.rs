@@ -0,0 +1,184 @@
+// The cluster as a whole, for capacity dashboards: the nodes from `system.runtime.nodes`
+// with their health from the coordinator's failure detector (`/v1/node`), and resource
+// group state from `/v1/resourceGroupState`. See `Client::nodes` and friends.
+use std::time::Duration;
+
+use serde::Deserialize;
+
+use crate::history::{data_size, duration};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeState {
+    Active,
+    Inactive,
+    ShuttingDown,
+    // states of newer Trino versions, verbatim
+    Other(String),
+}
+
+impl From<&str> for NodeState {
+    fn from(state: &str) -> Self {
+        match state {
+            "active" => NodeState::Active,
+            "inactive" => NodeState::Inactive,
+            "shutting_down" => NodeState::ShuttingDown,
+            other => NodeState::Other(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Node {
+    pub id: String,
+    pub uri: String,
+    pub version: String,
+    pub coordinator: bool,
+    pub state: NodeState,
+    // what the coordinator saw of the node lately, `None` for the coordinator itself or
+    // when the failure detector couldn't be read
+    pub health: Option<NodeHealth>,
+}
+
+// An entry of `/v1/node` and `/v1/node/failed`: request counts are exponentially decayed
+// over the last minute
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeHealth {
+    pub uri: String,
+    pub recent_requests: f64,
+    pub recent_failures: f64,
+    pub recent_successes: f64,
+    pub recent_failure_ratio: f64,
+    #[serde(default)]
+    pub last_request_time: Option<String>,
+    #[serde(default)]
+    pub last_response_time: Option<String>,
+    #[serde(default, deserialize_with = "duration")]
+    pub age: Option<Duration>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceGroup {
+    // the path from the root group, `["global", "adhoc"]`
+    pub id: Vec<String>,
+    // `CAN_RUN`, `CAN_QUEUE` or `FULL`
+    pub state: String,
+    #[serde(default)]
+    pub scheduling_policy: Option<String>,
+    #[serde(default)]
+    pub scheduling_weight: Option<u64>,
+    #[serde(default, deserialize_with = "data_size")]
+    pub soft_memory_limit: Option<u64>,
+    #[serde(default)]
+    pub soft_concurrency_limit: Option<u64>,
+    #[serde(default)]
+    pub hard_concurrency_limit: Option<u64>,
+    #[serde(default)]
+    pub max_queued_queries: Option<u64>,
+    #[serde(default, deserialize_with = "data_size")]
+    pub memory_usage: Option<u64>,
+    #[serde(default, deserialize_with = "duration")]
+    pub cpu_usage: Option<Duration>,
+    #[serde(default)]
+    pub num_queued_queries: u64,
+    #[serde(default)]
+    pub num_running_queries: u64,
+    #[serde(default)]
+    pub sub_groups: Vec<ResourceGroup>,
+}
+
+pub(crate) const NODES: &str = "SELECT node_id, http_uri, node_version, coordinator, state \
+    FROM system.runtime.nodes ORDER BY coordinator DESC, node_id";
+
+pub(crate) type NodeRow = (String, String, String, bool, String);
+
+// Nodes are matched to their health by URI, which the failure detector knows them by
+pub(crate) fn nodes(rows: Vec<NodeRow>, health: &[NodeHealth]) -> Vec<Node> {
+    rows.into_iter()
+        .map(|(id, uri, version, coordinator, state)| Node {
+            health: health
+                .iter()
+                .find(|h| h.uri.trim_end_matches('/') == uri.trim_end_matches('/'))
+                .cloned(),
+            id,
+            uri,
+            version,
+            coordinator,
+            state: NodeState::from(state.as_str()),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_with_health() {
+        let health: Vec<NodeHealth> = serde_json::from_str(
+            r#"[{
+              "uri": "http://10.0.0.2:8080",
+              "recentRequests": 120.5,
+              "recentFailures": 6.0,
+              "recentSuccesses": 114.5,
+              "lastRequestTime": "2024-01-02T03:04:05.678Z",
+              "lastResponseTime": "2024-01-02T03:04:05.680Z",
+              "recentFailureRatio": 0.05,
+              "age": "2.00h",
+              "recentFailuresByType": {}
+            }]"#,
+        )
+        .unwrap();
+        let rows = vec![
+            (
+                "coordinator".to_string(),
+                "http://10.0.0.1:8080".to_string(),
+                "435".to_string(),
+                true,
+                "active".to_string(),
+            ),
+            (
+                "worker-1".to_string(),
+                "http://10.0.0.2:8080/".to_string(),
+                "435".to_string(),
+                false,
+                "shutting_down".to_string(),
+            ),
+        ];
+        let nodes = nodes(rows, &health);
+        assert_eq!(nodes[0].health, None);
+        assert_eq!(nodes[1].state, NodeState::ShuttingDown);
+        let worker = nodes[1].health.as_ref().unwrap();
+        assert_eq!(worker.recent_failure_ratio, 0.05);
+        assert_eq!(worker.age, Some(Duration::from_secs(7200)));
+    }
+
+    #[test]
+    fn deserialize_resource_group() {
+        let group: ResourceGroup = serde_json::from_str(
+            r#"{
+              "id": ["global"],
+              "state": "CAN_QUEUE",
+              "schedulingPolicy": "FAIR",
+              "schedulingWeight": 1,
+              "softMemoryLimit": "10GB",
+              "softConcurrencyLimit": 100,
+              "hardConcurrencyLimit": 100,
+              "maxQueuedQueries": 1000,
+              "memoryUsage": "512MB",
+              "cpuUsage": "1.50m",
+              "numQueuedQueries": 3,
+              "numRunningQueries": 100,
+              "numEligibleSubGroups": 1,
+              "subGroups": [{ "id": ["global", "adhoc"], "state": "FULL", "numRunningQueries": 100 }]
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(group.soft_memory_limit, Some(10 << 30));
+        assert_eq!(group.memory_usage, Some(512 << 20));
+        assert_eq!(group.cpu_usage, Some(Duration::from_secs(90)));
+        assert_eq!(group.sub_groups[0].id, vec!["global", "adhoc"]);
+        assert_eq!(group.sub_groups[0].num_queued_queries, 0);
+    }
+}
@@ -0,0 +1,77 @@
+use std::fmt;
+
+use crate::guardrails::Violation;
+use crate::response::QueryError;
+use crate::types::ParseTypeError;
+
+#[derive(Debug)]
+pub enum Error {
+    Http(reqwest::Error),
+    Json(serde_json::Error),
+    Decode(String),
+    ColumnMismatch(Vec<String>),
+    Parameter(String),
+    // the query broke rules of `Guardrails` and wasn't run
+    Guardrails(Vec<Violation>),
+    // Trino ran the statement and it failed
+    Query(QueryError),
+    // `Client::execute` ran a query that returns rows
+    NotAnUpdate(String),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "http error: {}", e),
+            Error::Json(e) => write!(f, "failed to decode trino response: {}", e),
+            Error::Decode(msg) => write!(f, "failed to decode value: {}", msg),
+            Error::ColumnMismatch(problems) => {
+                write!(f, "query columns don't match the row type:")?;
+                for problem in problems {
+                    write!(f, "\n  - {}", problem)?;
+                }
+                Ok(())
+            }
+            Error::Parameter(msg) => write!(f, "invalid query parameters: {}", msg),
+            Error::Guardrails(violations) => {
+                write!(f, "query rejected by guardrails:")?;
+                for violation in violations {
+                    write!(f, "\n  - {}", violation)?;
+                }
+                Ok(())
+            }
+            Error::Query(e) => write!(f, "query failed: {}", e),
+            Error::NotAnUpdate(sql) => write!(f, "not an update statement: {}", sql),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Http(e) => Some(e),
+            Error::Json(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<ParseTypeError> for Error {
+    fn from(e: ParseTypeError) -> Self {
+        Error::Decode(e.to_string())
+    }
+}
@@ -0,0 +1,470 @@
+// Typed results of `EXPLAIN`, see `Client::explain`
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::error::{Error, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExplainType {
+    #[default]
+    Logical,
+    Distributed,
+    Io,
+    Validate,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExplainFormat {
+    #[default]
+    Text,
+    Json,
+    Graphviz,
+}
+
+// Defaults to Trino's own, a logical plan as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ExplainOptions {
+    pub r#type: ExplainType,
+    pub format: ExplainFormat,
+}
+
+impl ExplainOptions {
+    pub fn new(r#type: ExplainType, format: ExplainFormat) -> Self {
+        ExplainOptions { r#type, format }
+    }
+
+    // The `EXPLAIN (...)` prefix for a statement, `VALIDATE` takes no format
+    pub fn statement(&self, query: &str) -> String {
+        match self.r#type {
+            ExplainType::Validate => format!("EXPLAIN (TYPE VALIDATE) {}", query),
+            ty => format!("EXPLAIN (TYPE {}, FORMAT {}) {}", ty, self.format, query),
+        }
+    }
+}
+
+impl fmt::Display for ExplainType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExplainType::Logical => "LOGICAL",
+            ExplainType::Distributed => "DISTRIBUTED",
+            ExplainType::Io => "IO",
+            ExplainType::Validate => "VALIDATE",
+        })
+    }
+}
+
+impl fmt::Display for ExplainFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ExplainFormat::Text => "TEXT",
+            ExplainFormat::Json => "JSON",
+            ExplainFormat::Graphviz => "GRAPHVIZ",
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Explain {
+    Text(String),
+    Graphviz(String),
+    Logical(PlanNode),
+    // one plan per stage, ordered by fragment id
+    Distributed(Vec<Fragment>),
+    Io(IoPlan),
+    // a query that doesn't validate fails instead
+    Validate(bool),
+}
+
+impl Explain {
+    // `output` is the single value `EXPLAIN` returns for anything but `VALIDATE`
+    pub fn parse(options: ExplainOptions, output: &str) -> Result<Self> {
+        Ok(match (options.r#type, options.format) {
+            (ExplainType::Validate, _) => Explain::Validate(output.trim() == "true"),
+            (_, ExplainFormat::Text) => Explain::Text(output.to_string()),
+            (_, ExplainFormat::Graphviz) => Explain::Graphviz(output.to_string()),
+            (ExplainType::Logical, ExplainFormat::Json) => {
+                Explain::Logical(serde_json::from_str(output)?)
+            }
+            (ExplainType::Distributed, ExplainFormat::Json) => {
+                let fragments: BTreeMap<String, PlanNode> = serde_json::from_str(output)?;
+                let mut fragments: Vec<Fragment> = fragments
+                    .into_iter()
+                    .map(|(id, root)| Fragment { id, root })
+                    .collect();
+                fragments.sort_by_key(|f| (f.id.parse::<u64>().ok(), f.id.clone()));
+                Explain::Distributed(fragments)
+            }
+            (ExplainType::Io, ExplainFormat::Json) => Explain::Io(serde_json::from_str(output)?),
+        })
+    }
+
+    // The plan of `EXPLAIN (TYPE LOGICAL, FORMAT JSON)`
+    pub fn into_logical(self) -> Result<PlanNode> {
+        match self {
+            Explain::Logical(plan) => Ok(plan),
+            other => Err(Error::Decode(format!(
+                "expected a logical JSON plan, got {:?}",
+                other
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fragment {
+    pub id: String,
+    pub root: PlanNode,
+}
+
+// A node of a JSON plan: `name` is the operator (`TableScan`, `InnerJoin`, ...), `descriptor`
+// its main attributes and `details` the rest as rendered text, e.g. `symbol := expression`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanNode {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub descriptor: Descriptor,
+    #[serde(default)]
+    pub outputs: Vec<Output>,
+    #[serde(default)]
+    pub details: Vec<String>,
+    #[serde(default)]
+    pub estimates: Vec<Estimate>,
+    #[serde(default)]
+    pub children: Vec<PlanNode>,
+}
+
+impl PlanNode {
+    // This node and everything below it, depth first
+    pub fn nodes(&self) -> Nodes<'_> {
+        Nodes { stack: vec![self] }
+    }
+
+    pub fn find(&self, name: &str) -> impl Iterator<Item = &PlanNode> {
+        let name = name.to_owned();
+        self.nodes().filter(move |node| node.name == name)
+    }
+}
+
+pub struct Nodes<'a> {
+    stack: Vec<&'a PlanNode>,
+}
+
+impl<'a> Iterator for Nodes<'a> {
+    type Item = &'a PlanNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.stack.extend(node.children.iter().rev());
+        Some(node)
+    }
+}
+
+// Which keys a node has depends on its operator, e.g. `columnNames` on `Output` and `table`
+// on `TableScan`. Values are always strings.
+#[derive(Debug, Clone, PartialEq, Default, Serialize)]
+#[serde(transparent)]
+pub struct Descriptor(pub BTreeMap<String, String>);
+
+impl Descriptor {
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.0.get(key).map(String::as_str)
+    }
+
+    pub fn table(&self) -> Option<&str> {
+        self.get("table")
+    }
+
+    // `columnNames` of the `Output` node, rendered as `[a, b, c]`
+    pub fn column_names(&self) -> Vec<String> {
+        self.get("columnNames")
+            .map(|names| {
+                names
+                    .trim_matches(|c| c == '[' || c == ']')
+                    .split(',')
+                    .map(|s| s.trim().to_string())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+// A plan's table without the connector's handle details, which can change while the table
+// stays the same, e.g. `iceberg:web.events` for `iceberg:web.events$data@123 constraint on [day]`
+pub fn table_name(table: &str) -> &str {
+    let name = table.split_whitespace().next().unwrap_or(table);
+    name.split(['$', '@']).next().unwrap_or(name)
+}
+
+// Absent keys are sometimes written as `null`
+impl<'de> Deserialize<'de> for Descriptor {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let raw: BTreeMap<String, Option<String>> = BTreeMap::deserialize(deserializer)?;
+        Ok(Descriptor(
+            raw.into_iter()
+                .filter_map(|(k, v)| v.map(|v| (k, v)))
+                .collect(),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Output {
+    pub symbol: String,
+    pub r#type: String,
+}
+
+// Unknown estimates come back as "NaN" and end up `None`
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Estimate {
+    #[serde(default, deserialize_with = "estimate")]
+    pub output_row_count: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub output_size_in_bytes: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub cpu_cost: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub memory_cost: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub network_cost: Option<f64>,
+}
+
+fn estimate<'de, D>(deserializer: D) -> std::result::Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Number {
+        Number(f64),
+        String(String),
+    }
+    Ok(match Option::<Number>::deserialize(deserializer)? {
+        Some(Number::Number(n)) => Some(n),
+        Some(Number::String(s)) => s.parse().ok(),
+        None => None,
+    }
+    .filter(|n: &f64| !n.is_nan()))
+}
+
+// `EXPLAIN (TYPE IO, FORMAT JSON)`: the tables a query reads and writes
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IoPlan {
+    #[serde(default)]
+    pub input_table_column_infos: Vec<IoInput>,
+    pub output_table: Option<IoTable>,
+    pub estimate: Option<IoEstimate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IoInput {
+    pub table: IoTable,
+    pub constraint: Option<IoConstraint>,
+    pub estimate: Option<IoEstimate>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IoTable {
+    pub catalog: String,
+    pub schema_table: SchemaTable,
+}
+
+impl fmt::Display for IoTable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}.{}.{}",
+            self.catalog, self.schema_table.schema, self.schema_table.table
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SchemaTable {
+    pub schema: String,
+    pub table: String,
+}
+
+// `none` means the predicate can't match anything
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IoConstraint {
+    #[serde(default)]
+    pub none: bool,
+    #[serde(default)]
+    pub column_constraints: Vec<ColumnConstraint>,
+}
+
+// `domain` is kept as Trino renders it, its ranges depend on the column type
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ColumnConstraint {
+    pub column_name: String,
+    pub r#type: String,
+    pub domain: serde_json::Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IoEstimate {
+    #[serde(default, deserialize_with = "estimate")]
+    pub output_row_count: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub output_size_in_bytes: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub cpu_cost: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub max_memory: Option<f64>,
+    #[serde(default, deserialize_with = "estimate")]
+    pub network_cost: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn json(options: ExplainOptions, value: serde_json::Value) -> Explain {
+        Explain::parse(options, &value.to_string()).unwrap()
+    }
+
+    #[test]
+    fn statement() {
+        let options = ExplainOptions::new(ExplainType::Distributed, ExplainFormat::Json);
+        assert_eq!(
+            options.statement("SELECT 1"),
+            "EXPLAIN (TYPE DISTRIBUTED, FORMAT JSON) SELECT 1"
+        );
+        assert_eq!(
+            ExplainOptions::default().statement("SELECT 1"),
+            "EXPLAIN (TYPE LOGICAL, FORMAT TEXT) SELECT 1"
+        );
+        let validate = ExplainOptions::new(ExplainType::Validate, ExplainFormat::Json);
+        assert_eq!(
+            validate.statement("SELECT 1"),
+            "EXPLAIN (TYPE VALIDATE) SELECT 1"
+        );
+    }
+
+    #[test]
+    fn logical_plan() {
+        let scan = serde_json::json!({
+            "id": "0",
+            "name": "TableScan",
+            "descriptor": { "table": "tpch:tiny:orders" },
+            "outputs": [{ "symbol": "orderkey", "type": "bigint" }],
+            "details": ["orderkey := tpch:orderkey"],
+            "estimates": [{
+                "outputRowCount": 15000.0,
+                "outputSizeInBytes": "NaN",
+                "cpuCost": 135000.0,
+                "memoryCost": 0.0,
+                "networkCost": 0.0
+            }],
+            "children": []
+        });
+        let plan = json(
+            ExplainOptions::new(ExplainType::Logical, ExplainFormat::Json),
+            serde_json::json!({
+                "id": "5",
+                "name": "Output",
+                "descriptor": { "columnNames": "[orderkey, total]" },
+                "outputs": [],
+                "details": [],
+                "estimates": [],
+                "children": [{
+                    "id": "1",
+                    "name": "Aggregate",
+                    "descriptor": { "type": "FINAL", "keys": "[orderkey]", "hash": null },
+                    "outputs": [],
+                    "details": [],
+                    "estimates": [],
+                    "children": [scan]
+                }]
+            }),
+        )
+        .into_logical()
+        .unwrap();
+
+        assert_eq!(plan.descriptor.column_names(), vec!["orderkey", "total"]);
+        let names: Vec<&str> = plan.nodes().map(|n| n.name.as_str()).collect();
+        assert_eq!(names, vec!["Output", "Aggregate", "TableScan"]);
+        assert_eq!(plan.children[0].descriptor.get("hash"), None);
+
+        let scan = plan.find("TableScan").next().unwrap();
+        assert_eq!(scan.descriptor.table(), Some("tpch:tiny:orders"));
+        assert_eq!(scan.estimates[0].output_row_count, Some(15000.0));
+        assert_eq!(scan.estimates[0].output_size_in_bytes, None);
+
+        // plans written back out read the same
+        let written = serde_json::to_string(&plan).unwrap();
+        assert_eq!(serde_json::from_str::<PlanNode>(&written).unwrap(), plan);
+    }
+
+    #[test]
+    fn other_plans() {
+        let node = |id: &str| serde_json::json!({ "id": id, "name": "Output" });
+        let fragments = match json(
+            ExplainOptions::new(ExplainType::Distributed, ExplainFormat::Json),
+            serde_json::json!({ "10": node("a"), "2": node("b"), "0": node("c") }),
+        ) {
+            Explain::Distributed(fragments) => fragments,
+            other => panic!("{:?}", other),
+        };
+        let ids: Vec<&str> = fragments.iter().map(|f| f.id.as_str()).collect();
+        assert_eq!(ids, vec!["0", "2", "10"]);
+
+        let io = match json(
+            ExplainOptions::new(ExplainType::Io, ExplainFormat::Json),
+            serde_json::json!({
+                "inputTableColumnInfos": [{
+                    "table": {
+                        "catalog": "hive",
+                        "schemaTable": { "schema": "sales", "table": "orders" }
+                    },
+                    "constraint": {
+                        "none": false,
+                        "columnConstraints": [{
+                            "columnName": "day",
+                            "type": "date",
+                            "domain": { "nullsAllowed": false, "ranges": [] }
+                        }]
+                    },
+                    "estimate": { "outputRowCount": 10.0, "cpuCost": "NaN" }
+                }],
+                "estimate": { "outputRowCount": "NaN" }
+            }),
+        ) {
+            Explain::Io(io) => io,
+            other => panic!("{:?}", other),
+        };
+        let input = &io.input_table_column_infos[0];
+        assert_eq!(input.table.to_string(), "hive.sales.orders");
+        let constraint = input.constraint.as_ref().unwrap();
+        assert_eq!(constraint.column_constraints[0].column_name, "day");
+        assert_eq!(
+            input.estimate.as_ref().unwrap().output_row_count,
+            Some(10.0)
+        );
+        assert!(io.output_table.is_none());
+
+        let validate = ExplainOptions::new(ExplainType::Validate, ExplainFormat::Text);
+        assert_eq!(
+            Explain::parse(validate, "true").unwrap(),
+            Explain::Validate(true)
+        );
+        let text = ExplainOptions::default();
+        assert!(matches!(
+            Explain::parse(text, "Fragment 0").unwrap(),
+            Explain::Text(t) if t == "Fragment 0"
+        ));
+    }
+}
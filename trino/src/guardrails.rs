@@ -0,0 +1,395 @@
+// Checks a query's distributed plan against limits before it runs, see `Client::query_guarded`
+//
+// let guardrails = Guardrails::new()
+//     .full_scan("hive.web.events", Severity::Reject)
+//     .cross_joins(Severity::Reject)
+//     .max_scan_bytes(100 << 30, Severity::Warn);
+//
+// Estimates Trino doesn't know are skipped by the limits, with a warning.
+use std::fmt;
+
+use crate::explain::{table_name, Fragment, PlanNode};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    // logged, the query still runs
+    Warn,
+    Reject,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    FullScan,
+    CrossJoin,
+    ScanBytes,
+    OutputRows,
+    CpuCost,
+    MemoryCost,
+    NetworkCost,
+    UnknownEstimate,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub rule: Rule,
+    pub severity: Severity,
+    pub message: String,
+    // the plan node it was found at, for rules about one node
+    pub node_id: Option<String>,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.node_id {
+            Some(id) => write!(f, "{} (plan node {})", self.message, id),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Limit {
+    max: f64,
+    severity: Severity,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Guardrails {
+    full_scans: Vec<(Vec<String>, Severity)>,
+    cross_joins: Option<Severity>,
+    scan_bytes: Option<Limit>,
+    output_rows: Option<Limit>,
+    cpu_cost: Option<Limit>,
+    memory_cost: Option<Limit>,
+    network_cost: Option<Limit>,
+}
+
+impl Guardrails {
+    pub fn new() -> Self {
+        Guardrails::default()
+    }
+
+    // Scans of `table` that don't filter anything; `table` is `catalog.schema.table`, or a
+    // suffix of it like `schema.table`
+    pub fn full_scan(mut self, table: &str, severity: Severity) -> Self {
+        let parts = table.split('.').map(str::to_ascii_lowercase).collect();
+        self.full_scans.push((parts, severity));
+        self
+    }
+
+    pub fn cross_joins(mut self, severity: Severity) -> Self {
+        self.cross_joins = Some(severity);
+        self
+    }
+
+    // The estimated bytes read by all table scans together
+    pub fn max_scan_bytes(mut self, max: u64, severity: Severity) -> Self {
+        self.scan_bytes = Some(Limit {
+            max: max as f64,
+            severity,
+        });
+        self
+    }
+
+    // The estimated rows the query returns
+    pub fn max_output_rows(mut self, max: u64, severity: Severity) -> Self {
+        self.output_rows = Some(Limit {
+            max: max as f64,
+            severity,
+        });
+        self
+    }
+
+    // Cost limits are compared against the sum over every plan node
+    pub fn max_cpu_cost(mut self, max: f64, severity: Severity) -> Self {
+        self.cpu_cost = Some(Limit { max, severity });
+        self
+    }
+
+    pub fn max_memory_cost(mut self, max: f64, severity: Severity) -> Self {
+        self.memory_cost = Some(Limit { max, severity });
+        self
+    }
+
+    pub fn max_network_cost(mut self, max: f64, severity: Severity) -> Self {
+        self.network_cost = Some(Limit { max, severity });
+        self
+    }
+
+    // Every rule the plan of `EXPLAIN (TYPE DISTRIBUTED, FORMAT JSON)` breaks, the most
+    // severe first
+    pub fn check(&self, fragments: &[Fragment]) -> Vec<Violation> {
+        let nodes: Vec<&PlanNode> = fragments.iter().flat_map(|f| f.root.nodes()).collect();
+        let mut violations = Vec::new();
+
+        for node in &nodes {
+            if let Some(table) = scanned_table(node) {
+                if !is_filtered(node) {
+                    if let Some((_, severity)) = self
+                        .full_scans
+                        .iter()
+                        .find(|(t, _)| table_matches(t, table))
+                    {
+                        violations.push(Violation {
+                            rule: Rule::FullScan,
+                            severity: *severity,
+                            message: format!("full scan of {}", table),
+                            node_id: Some(node.id.clone()),
+                        });
+                    }
+                }
+            }
+            if let (Some(severity), "CrossJoin") = (self.cross_joins, node.name.as_str()) {
+                violations.push(Violation {
+                    rule: Rule::CrossJoin,
+                    severity,
+                    message: "cross join".to_string(),
+                    node_id: Some(node.id.clone()),
+                });
+            }
+        }
+
+        let scans: Vec<&PlanNode> = nodes
+            .iter()
+            .copied()
+            .filter(|n| scanned_table(n).is_some())
+            .collect();
+        self.limit(
+            &mut violations,
+            Rule::ScanBytes,
+            "estimated bytes scanned",
+            self.scan_bytes,
+            &scans,
+            |n| n.estimates.first().map(|e| e.output_size_in_bytes),
+        );
+        // the output of the query is the output of the root fragment, `0`
+        let output: Vec<&PlanNode> = fragments
+            .iter()
+            .find(|f| f.id == "0")
+            .or_else(|| fragments.first())
+            .map(|f| vec![&f.root])
+            .unwrap_or_default();
+        self.limit(
+            &mut violations,
+            Rule::OutputRows,
+            "estimated output rows",
+            self.output_rows,
+            &output,
+            |n| n.estimates.last().map(|e| e.output_row_count),
+        );
+        self.limit(
+            &mut violations,
+            Rule::CpuCost,
+            "estimated cpu cost",
+            self.cpu_cost,
+            &nodes,
+            |n| n.estimates.last().map(|e| e.cpu_cost),
+        );
+        self.limit(
+            &mut violations,
+            Rule::MemoryCost,
+            "estimated memory cost",
+            self.memory_cost,
+            &nodes,
+            |n| n.estimates.last().map(|e| e.memory_cost),
+        );
+        self.limit(
+            &mut violations,
+            Rule::NetworkCost,
+            "estimated network cost",
+            self.network_cost,
+            &nodes,
+            |n| n.estimates.last().map(|e| e.network_cost),
+        );
+
+        violations.sort_by_key(|v| std::cmp::Reverse(v.severity));
+        violations
+    }
+
+    // Sums `estimate` over `nodes`, nodes without estimates at all don't count but
+    // unknown ones make the total unknown
+    fn limit(
+        &self,
+        violations: &mut Vec<Violation>,
+        rule: Rule,
+        what: &str,
+        limit: Option<Limit>,
+        nodes: &[&PlanNode],
+        estimate: impl Fn(&PlanNode) -> Option<Option<f64>>,
+    ) {
+        let limit = match limit {
+            Some(limit) => limit,
+            None => return,
+        };
+        let mut total = 0.0;
+        for node in nodes {
+            match estimate(node) {
+                Some(Some(value)) => total += value,
+                Some(None) => {
+                    violations.push(Violation {
+                        rule: Rule::UnknownEstimate,
+                        severity: Severity::Warn,
+                        message: format!("{} is unknown, the limit wasn't checked", what),
+                        node_id: Some(node.id.clone()),
+                    });
+                    return;
+                }
+                None => {}
+            }
+        }
+        if total > limit.max {
+            violations.push(Violation {
+                rule,
+                severity: limit.severity,
+                message: format!("{} {} over the limit of {}", what, total, limit.max),
+                node_id: None,
+            });
+        }
+    }
+}
+
+// Scans show up as `TableScan`, or fused with what follows as `ScanFilter`, `ScanProject`
+// and `ScanFilterProject`
+fn scanned_table(node: &PlanNode) -> Option<&str> {
+    if node.name == "TableScan" || node.name.starts_with("Scan") {
+        node.descriptor.table()
+    } else {
+        None
+    }
+}
+
+// A filter the connector took over shows in the table handle (`constraint on [day]`) or as
+// domain lines (`:: [[2024-01-01]]`) in the details
+fn is_filtered(node: &PlanNode) -> bool {
+    node.name.contains("Filter")
+        || node.descriptor.get("filterPredicate").is_some()
+        || node
+            .descriptor
+            .table()
+            .is_some_and(|t| t.contains("constraint on"))
+        || node
+            .details
+            .iter()
+            .any(|d| d.trim_start().starts_with("::"))
+}
+
+// The plan names tables however the connector does, `hive:web:events` or
+// `iceberg:web.events$data@123 constraint on [day]`
+fn table_matches(wanted: &[String], table: &str) -> bool {
+    let parts: Vec<String> = table_name(table)
+        .split([':', '.'])
+        .map(str::to_ascii_lowercase)
+        .collect();
+    parts.len() >= wanted.len() && parts[parts.len() - wanted.len()..] == *wanted
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::{ExplainFormat, ExplainOptions, ExplainType};
+    use crate::Explain;
+
+    fn plan(value: serde_json::Value) -> Vec<Fragment> {
+        let options = ExplainOptions::new(ExplainType::Distributed, ExplainFormat::Json);
+        match Explain::parse(options, &value.to_string()).unwrap() {
+            Explain::Distributed(fragments) => fragments,
+            other => panic!("{:?}", other),
+        }
+    }
+
+    fn estimate(rows: f64, bytes: serde_json::Value) -> serde_json::Value {
+        serde_json::json!([{
+            "outputRowCount": rows,
+            "outputSizeInBytes": bytes,
+            "cpuCost": 10.0,
+            "memoryCost": 0.0,
+            "networkCost": "NaN"
+        }])
+    }
+
+    #[test]
+    fn check() {
+        let fragments = plan(serde_json::json!({
+            "0": {
+                "id": "9",
+                "name": "Output",
+                "estimates": estimate(5e6, serde_json::json!(1e9)),
+                "children": [{ "id": "8", "name": "RemoteSource" }]
+            },
+            "1": {
+                "id": "7",
+                "name": "CrossJoin",
+                "children": [
+                    {
+                        "id": "1",
+                        "name": "TableScan",
+                        "descriptor": { "table": "hive:web:events" },
+                        "estimates": estimate(1e9, serde_json::json!(8e10))
+                    },
+                    {
+                        "id": "2",
+                        "name": "ScanFilter",
+                        "descriptor": { "table": "hive:web:users", "filterPredicate": "(id = 1)" },
+                        "estimates": estimate(1.0, serde_json::json!("NaN"))
+                    },
+                    {
+                        "id": "3",
+                        "name": "TableScan",
+                        "descriptor": { "table": "hive:web:sessions constraint on [day]" },
+                        "estimates": estimate(1.0, serde_json::json!(100.0))
+                    }
+                ]
+            }
+        }));
+
+        let guardrails = Guardrails::new()
+            .full_scan("web.events", Severity::Reject)
+            .full_scan("hive.web.users", Severity::Reject)
+            .full_scan("sessions", Severity::Reject)
+            .cross_joins(Severity::Warn)
+            .max_output_rows(1_000_000, Severity::Reject)
+            .max_cpu_cost(100.0, Severity::Warn);
+        let violations = guardrails.check(&fragments);
+        let found: Vec<(Rule, Severity, Option<&str>)> = violations
+            .iter()
+            .map(|v| (v.rule, v.severity, v.node_id.as_deref()))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (Rule::FullScan, Severity::Reject, Some("1")),
+                (Rule::OutputRows, Severity::Reject, None),
+                (Rule::CrossJoin, Severity::Warn, Some("7")),
+            ]
+        );
+        assert_eq!(
+            violations[0].to_string(),
+            "full scan of hive:web:events (plan node 1)"
+        );
+
+        // the users scan doesn't know its size
+        let violations = Guardrails::new()
+            .max_scan_bytes(1 << 40, Severity::Reject)
+            .check(&fragments);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule, Rule::UnknownEstimate);
+        assert_eq!(violations[0].node_id.as_deref(), Some("2"));
+
+        assert!(Guardrails::new().check(&fragments).is_empty());
+    }
+
+    #[test]
+    fn tables() {
+        let wanted = |t: &str| t.split('.').map(str::to_string).collect::<Vec<_>>();
+        assert!(table_matches(
+            &wanted("iceberg.web.events"),
+            "iceberg:web.events$data@123"
+        ));
+        assert!(table_matches(&wanted("events"), "hive:web:EVENTS"));
+        assert!(!table_matches(&wanted("other.events"), "hive:web:events"));
+        assert!(!table_matches(
+            &wanted("a.hive.web.events"),
+            "hive:web:events"
+        ));
+    }
+}
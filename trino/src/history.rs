@@ -0,0 +1,305 @@
+// What the coordinator knows about running and recently finished queries, the data behind
+// the web UI: `/v1/query` for the list, `/v1/query/{id}` for one query and
+// `system.runtime.queries` from SQL. See `Client::list_queries` and friends.
+use std::time::Duration;
+
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+
+use crate::analyze::{parse_data_size, parse_duration};
+use crate::response::QueryState;
+
+// An entry of `/v1/query`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BasicQueryInfo {
+    pub query_id: String,
+    pub state: QueryState,
+    pub query: String,
+    #[serde(default)]
+    pub query_type: Option<String>,
+    pub session: Session,
+    #[serde(default)]
+    pub resource_group_id: Option<Vec<String>>,
+    pub query_stats: HistoryStats,
+    #[serde(default)]
+    pub error_code: Option<ErrorCode>,
+}
+
+// `/v1/query/{id}`, only the parts of the (very large) document that are stable across
+// Trino versions are typed, the rest is in `extra`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryInfo {
+    pub query_id: String,
+    pub state: QueryState,
+    pub query: String,
+    #[serde(default)]
+    pub query_type: Option<String>,
+    pub session: Session,
+    #[serde(default)]
+    pub resource_group_id: Option<Vec<String>>,
+    pub query_stats: HistoryStats,
+    #[serde(default)]
+    pub error_code: Option<ErrorCode>,
+    #[serde(default)]
+    pub failure_info: Option<FailureInfo>,
+    #[serde(default)]
+    pub output_stage: Option<StageInfo>,
+    #[serde(default)]
+    pub warnings: Vec<Value>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Session {
+    pub user: String,
+    #[serde(default)]
+    pub source: Option<String>,
+    #[serde(default)]
+    pub catalog: Option<String>,
+    #[serde(default)]
+    pub schema: Option<String>,
+    #[serde(default)]
+    pub remote_user_address: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+}
+
+// The `queryStats` of both endpoints. Trino writes durations (`1.23s`) and data sizes
+// (`12.5MB`) as succinct strings; ones it leaves out or that don't parse are `None`.
+#[derive(Debug, Clone, PartialEq, Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct HistoryStats {
+    pub create_time: Option<String>,
+    pub end_time: Option<String>,
+    #[serde(deserialize_with = "duration")]
+    pub queued_time: Option<Duration>,
+    #[serde(deserialize_with = "duration")]
+    pub elapsed_time: Option<Duration>,
+    #[serde(deserialize_with = "duration")]
+    pub execution_time: Option<Duration>,
+    #[serde(deserialize_with = "duration")]
+    pub total_cpu_time: Option<Duration>,
+    #[serde(deserialize_with = "duration")]
+    pub total_scheduled_time: Option<Duration>,
+    pub total_drivers: Option<u64>,
+    pub queued_drivers: Option<u64>,
+    pub running_drivers: Option<u64>,
+    pub completed_drivers: Option<u64>,
+    pub raw_input_positions: Option<u64>,
+    #[serde(deserialize_with = "data_size")]
+    pub raw_input_data_size: Option<u64>,
+    #[serde(deserialize_with = "data_size")]
+    pub physical_input_data_size: Option<u64>,
+    #[serde(deserialize_with = "data_size")]
+    pub user_memory_reservation: Option<u64>,
+    #[serde(deserialize_with = "data_size")]
+    pub total_memory_reservation: Option<u64>,
+    #[serde(deserialize_with = "data_size")]
+    pub peak_user_memory_reservation: Option<u64>,
+    #[serde(deserialize_with = "data_size")]
+    pub peak_total_memory_reservation: Option<u64>,
+    pub fully_blocked: Option<bool>,
+    pub progress_percentage: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ErrorCode {
+    pub code: i64,
+    pub name: String,
+    #[serde(rename = "type")]
+    pub r#type: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FailureInfo {
+    #[serde(rename = "type")]
+    pub r#type: String,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StageInfo {
+    pub stage_id: String,
+    // `PLANNED`, `SCHEDULING`, `RUNNING`, `FINISHED`... stage states differ from query ones
+    pub state: String,
+    #[serde(default)]
+    pub sub_stages: Vec<StageInfo>,
+}
+
+// A row of `system.runtime.queries`
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeQuery {
+    pub query_id: String,
+    pub state: QueryState,
+    pub user: String,
+    pub source: Option<String>,
+    pub query: String,
+    pub resource_group_id: Option<Vec<String>>,
+    pub queued_time: Option<Duration>,
+    pub analysis_time: Option<Duration>,
+    pub planning_time: Option<Duration>,
+    // timestamps as Trino formats them, `2024-01-02 03:04:05.678 UTC`
+    pub created: Option<String>,
+    pub started: Option<String>,
+    pub last_heartbeat: Option<String>,
+    pub end: Option<String>,
+    pub error_type: Option<String>,
+    pub error_code: Option<String>,
+}
+
+pub(crate) const RUNTIME_QUERIES: &str = "SELECT query_id, state, user, source, query, \
+    resource_group_id, queued_time_ms, analysis_time_ms, planning_time_ms, \
+    CAST(created AS varchar), CAST(started AS varchar), CAST(last_heartbeat AS varchar), \
+    CAST(\"end\" AS varchar), error_type, error_code \
+    FROM system.runtime.queries ORDER BY created DESC";
+
+pub(crate) type RuntimeQueryRow = (
+    String,
+    QueryState,
+    String,
+    Option<String>,
+    String,
+    Option<Vec<String>>,
+    Option<u64>,
+    Option<u64>,
+    Option<u64>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+    Option<String>,
+);
+
+impl From<RuntimeQueryRow> for RuntimeQuery {
+    fn from(row: RuntimeQueryRow) -> Self {
+        let millis = |ms: Option<u64>| ms.map(Duration::from_millis);
+        RuntimeQuery {
+            query_id: row.0,
+            state: row.1,
+            user: row.2,
+            source: row.3,
+            query: row.4,
+            resource_group_id: row.5,
+            queued_time: millis(row.6),
+            analysis_time: millis(row.7),
+            planning_time: millis(row.8),
+            created: row.9,
+            started: row.10,
+            last_heartbeat: row.11,
+            end: row.12,
+            error_type: row.13,
+            error_code: row.14,
+        }
+    }
+}
+
+pub(crate) fn duration<'de, D>(deserializer: D) -> Result<Option<Duration>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    Ok(value.as_deref().and_then(parse_duration))
+}
+
+pub(crate) fn data_size<'de, D>(deserializer: D) -> Result<Option<u64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Option<String> = Option::deserialize(deserializer)?;
+    Ok(value.as_deref().and_then(parse_data_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deserialize_query_list() {
+        let queries: Vec<BasicQueryInfo> = serde_json::from_str(
+            r#"[{
+              "queryId": "20240102_030405_00012_abcde",
+              "session": {
+                "queryId": "20240102_030405_00012_abcde",
+                "user": "etl",
+                "source": "trino-cli",
+                "catalog": "hive",
+                "systemProperties": {}
+              },
+              "resourceGroupId": ["global", "etl"],
+              "state": "FAILED",
+              "memoryPool": "general",
+              "scheduled": true,
+              "self": "http://coordinator:8080/v1/query/20240102_030405_00012_abcde",
+              "query": "SELECT * FROM missing",
+              "queryStats": {
+                "createTime": "2024-01-02T03:04:05.678Z",
+                "endTime": "2024-01-02T03:04:06.000Z",
+                "queuedTime": "1.50ms",
+                "elapsedTime": "322.00ms",
+                "executionTime": "300.00ms",
+                "totalDrivers": 0,
+                "rawInputDataSize": "1.50kB",
+                "peakUserMemoryReservation": "0B",
+                "totalCpuTime": "0.00ns",
+                "fullyBlocked": false,
+                "blockedReasons": [],
+                "progressPercentage": 100.0
+              },
+              "errorType": "USER_ERROR",
+              "errorCode": { "code": 46, "name": "TABLE_NOT_FOUND", "type": "USER_ERROR" },
+              "queryType": "SELECT"
+            }]"#,
+        )
+        .unwrap();
+        let query = &queries[0];
+        assert_eq!(query.state, QueryState::Failed);
+        assert_eq!(query.session.user, "etl");
+        assert_eq!(query.session.schema, None);
+        assert_eq!(query.error_code.as_ref().unwrap().name, "TABLE_NOT_FOUND");
+        let stats = &query.query_stats;
+        assert_eq!(stats.queued_time, Some(Duration::from_micros(1500)));
+        assert_eq!(stats.elapsed_time, Some(Duration::from_millis(322)));
+        assert_eq!(stats.raw_input_data_size, Some(1536));
+        assert_eq!(stats.peak_user_memory_reservation, Some(0));
+        assert_eq!(stats.total_drivers, Some(0));
+        assert_eq!(stats.physical_input_data_size, None);
+    }
+
+    #[test]
+    fn deserialize_query_info() {
+        let info: QueryInfo = serde_json::from_str(
+            r#"{
+              "queryId": "20240102_030405_00013_abcde",
+              "session": { "user": "etl" },
+              "state": "RUNNING",
+              "query": "SELECT count(*) FROM hive.web.events",
+              "queryStats": { "elapsedTime": "2.00m", "peakTotalMemoryReservation": "1GB" },
+              "outputStage": {
+                "stageId": "20240102_030405_00013_abcde.0",
+                "state": "RUNNING",
+                "subStages": [{ "stageId": "20240102_030405_00013_abcde.1", "state": "FINISHED" }]
+              },
+              "finalQueryInfo": false
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(info.state, QueryState::Running);
+        assert_eq!(
+            info.query_stats.elapsed_time,
+            Some(Duration::from_secs(120))
+        );
+        assert_eq!(
+            info.query_stats.peak_total_memory_reservation,
+            Some(1 << 30)
+        );
+        assert_eq!(info.output_stage.unwrap().sub_stages[0].state, "FINISHED");
+        assert_eq!(info.extra["finalQueryInfo"], Value::Bool(false));
+    }
+}
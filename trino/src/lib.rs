@@ -1,19 +1,64 @@
+pub mod analyze;
+pub mod cluster;
+pub mod error;
+pub mod explain;
+pub mod guardrails;
+pub mod history;
+#[doc(hidden)]
+pub mod macros;
+pub mod metadata;
+pub mod params;
+mod prefetch;
 pub mod response;
+pub mod row;
+pub mod script;
+
+use std::collections::HashMap;
 
 use data_encoding::BASE64;
 use reqwest::{Client as ReqwestClient, Response};
 
+pub use analyze::AnalyzedPlan;
+use cluster::{Node, NodeHealth, NodeRow, ResourceGroup};
+pub use error::{Error, Result};
+pub use explain::{Explain, ExplainFormat, ExplainOptions, ExplainType};
+pub use guardrails::Guardrails;
+use history::{BasicQueryInfo, QueryInfo, RuntimeQuery, RuntimeQueryRow};
+use metadata::{Catalog, Column, Schema, Table, TableKind, TableName, TableProperty};
+use params::{quote_identifier, quote_string, Placeholders, STATEMENT_NAME};
+pub use params::{Params, ToTrinoLiteral};
+use prefetch::PageStream;
+pub use prefetch::PrefetchConfig;
 use response::*;
+use row::ColumnRef;
+pub use row::{FromTrino, TrinoRow};
+pub use script::ExecuteResult;
+use script::{OnError, ScriptReport, Session, StatementOutcome, StatementResult};
 use serde::de::DeserializeOwned;
 use tokio::time::Duration;
+#[cfg(feature = "derive")]
+pub use trino_derive::{query_as, TrinoRow};
+// the type parser lives in its own crate so `query_as!` can use it at compile time too
+pub use trino_types as types;
+use types::TrinoType;
 
 use sha2::{Digest, Sha256};
 
 use tracing::debug;
 use tracing::instrument;
 
+use serde_json::value::RawValue;
 use serde_json::Value;
 
+fn qualified(name: &TableName) -> String {
+    format!(
+        "{}.{}.{}",
+        quote_identifier(&name.catalog),
+        quote_identifier(&name.schema),
+        quote_identifier(&name.table)
+    )
+}
+
 // Helper function to hash a string
 fn hash_string(s: &str) -> String {
     let mut hasher = Sha256::new();
@@ -27,6 +72,7 @@ pub struct ClientBuilder {
     user: Option<String>,
     password: Option<String>,
     timeout: Option<Duration>,
+    prefetch: PrefetchConfig,
 }
 
 impl Default for ClientBuilder {
@@ -42,6 +88,7 @@ impl Default for ClientBuilder {
             user: std::env::var("TRINO_USER").ok(),
             password: std::env::var("TRINO_PASSWORD").ok(),
             timeout: None,
+            prefetch: PrefetchConfig::default(),
         }
     }
 }
@@ -54,6 +101,7 @@ impl ClientBuilder {
             user: None,
             password: None,
             timeout: None,
+            prefetch: PrefetchConfig::default(),
         }
     }
 
@@ -82,6 +130,11 @@ impl ClientBuilder {
         self
     }
 
+    pub fn prefetch(mut self, prefetch: PrefetchConfig) -> Self {
+        self.prefetch = prefetch;
+        self
+    }
+
     pub fn build(self) -> Client {
         let mut cb = ReqwestClient::builder();
 
@@ -110,6 +163,7 @@ impl ClientBuilder {
             port: self.port.expect("Port must be set."),
             user: self.user,
             http_client,
+            prefetch: self.prefetch,
         }
     }
 }
@@ -119,57 +173,566 @@ pub struct Client {
     pub port: u32,
     pub user: Option<String>,
     pub http_client: ReqwestClient,
+    pub prefetch: PrefetchConfig,
 }
 
 impl Client {
     // TODO:
     //  - Implement query cancellation i.e. DELETE to nextUri
-    //  - Implement own errors
-    //  - Implement paging
-    #[instrument(skip(self, query_str), fields(correlation_id = %hash_string(query_str)))]
-    pub async fn query<T>(&self, query_str: &str) -> Result<Vec<T>, reqwest::Error>
+    pub async fn query<T>(&self, query_str: &str) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.fetch(query_str, None).await
+    }
+
+    // `?` or `:name` placeholders in `query_str` are filled from `params`
+    pub async fn query_with<T>(&self, query_str: &str, params: &Params) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let placeholders = Placeholders::parse(query_str)?;
+        if placeholders.is_empty() && params.is_empty() {
+            return self.query(query_str).await;
+        }
+        let execute = params.execute(&placeholders)?;
+        self.query_prepared(&execute, &placeholders.sql).await
+    }
+
+    // Runs `query_str` with `prepared` available to it as `params::STATEMENT_NAME`
+    pub async fn query_prepared<T>(&self, query_str: &str, prepared: &str) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        self.fetch(query_str, Some(prepared)).await
+    }
+
+    // The Trino type of every placeholder, in the order they appear
+    pub async fn describe_input(&self, query_str: &str) -> Result<Vec<String>> {
+        let placeholders = Placeholders::parse(query_str)?;
+        let mut inputs: Vec<(i64, String)> = self
+            .query_prepared(
+                &format!("DESCRIBE INPUT {}", STATEMENT_NAME),
+                &placeholders.sql,
+            )
+            .await?;
+        inputs.sort_by_key(|(position, _)| *position);
+        Ok(inputs.into_iter().map(|(_, ty)| ty).collect())
+    }
+
+    // `EXPLAIN` in any of its types and formats, JSON plans come back parsed
+    pub async fn explain(&self, query_str: &str, options: ExplainOptions) -> Result<Explain> {
+        self.explain_with(query_str, None, options).await
+    }
+
+    // Explains `query_str`, which can `EXECUTE` `prepared` as `params::STATEMENT_NAME`
+    pub async fn explain_prepared(
+        &self,
+        query_str: &str,
+        prepared: &str,
+        options: ExplainOptions,
+    ) -> Result<Explain> {
+        self.explain_with(query_str, Some(prepared), options).await
+    }
+
+    // What `guardrails` find wrong with the distributed plan of `query_str`, without running it
+    pub async fn check_guardrails(
+        &self,
+        query_str: &str,
+        guardrails: &Guardrails,
+    ) -> Result<Vec<guardrails::Violation>> {
+        let options = ExplainOptions::new(ExplainType::Distributed, ExplainFormat::Json);
+        match self.explain(query_str, options).await? {
+            Explain::Distributed(fragments) => Ok(guardrails.check(&fragments)),
+            other => Err(Error::Decode(format!(
+                "expected a distributed JSON plan, got {:?}",
+                other
+            ))),
+        }
+    }
+
+    // Like `query`, but only runs once `guardrails` pass: warnings are logged, and any
+    // rejection fails with `Error::Guardrails` before the query starts
+    pub async fn query_guarded<T>(&self, query_str: &str, guardrails: &Guardrails) -> Result<Vec<T>>
+    where
+        T: DeserializeOwned,
+    {
+        let (rejected, warnings): (Vec<_>, Vec<_>) = self
+            .check_guardrails(query_str, guardrails)
+            .await?
+            .into_iter()
+            .partition(|v| v.severity == guardrails::Severity::Reject);
+        for warning in &warnings {
+            tracing::warn!("guardrail: {}", warning);
+        }
+        if !rejected.is_empty() {
+            return Err(Error::Guardrails(rejected));
+        }
+        self.query(query_str).await
+    }
+
+    // Runs the statement and returns its plan with what each operator actually did
+    pub async fn explain_analyze(&self, query_str: &str) -> Result<AnalyzedPlan> {
+        let rows: Vec<(String,)> = self
+            .fetch(&format!("EXPLAIN ANALYZE {}", query_str), None)
+            .await?;
+        let output: Vec<String> = rows.into_iter().map(|(text,)| text).collect();
+        AnalyzedPlan::parse(&output.join("\n"))
+    }
+
+    pub async fn catalogs(&self) -> Result<Vec<Catalog>> {
+        let rows: Vec<(String, String)> = self
+            .query(
+                "SELECT catalog_name, connector_name FROM system.metadata.catalogs \
+                 ORDER BY catalog_name",
+            )
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(name, connector)| Catalog { name, connector })
+            .collect())
+    }
+
+    pub async fn schemas(&self, catalog: &str) -> Result<Vec<Schema>> {
+        let rows: Vec<(String,)> = self
+            .query(&format!(
+                "SELECT schema_name FROM {}.information_schema.schemata ORDER BY schema_name",
+                quote_identifier(catalog)
+            ))
+            .await?;
+        Ok(rows
+            .into_iter()
+            .map(|(name,)| Schema {
+                catalog: catalog.to_string(),
+                name,
+            })
+            .collect())
+    }
+
+    // Tables and views; comments are left out for connectors that don't expose them
+    pub async fn tables(&self, catalog: &str, schema: &str) -> Result<Vec<Table>> {
+        let rows: Vec<(String, String)> = self
+            .query(&format!(
+                "SELECT table_name, table_type FROM {}.information_schema.tables \
+                 WHERE table_schema = {} ORDER BY table_name",
+                quote_identifier(catalog),
+                quote_string(schema)
+            ))
+            .await?;
+        let comments: HashMap<String, String> = match self
+            .query::<(String, String)>(&format!(
+                "SELECT table_name, comment FROM system.metadata.table_comments \
+                 WHERE catalog_name = {} AND schema_name = {} AND comment IS NOT NULL",
+                quote_string(catalog),
+                quote_string(schema)
+            ))
+            .await
+        {
+            Ok(rows) => rows.into_iter().collect(),
+            Err(e) => {
+                tracing::warn!(
+                    "couldn't read table comments of {}.{}: {}",
+                    catalog,
+                    schema,
+                    e
+                );
+                Default::default()
+            }
+        };
+        Ok(rows
+            .into_iter()
+            .map(|(name, table_type)| Table {
+                comment: comments.get(&name).cloned(),
+                name: TableName::new(catalog, schema, &name),
+                kind: TableKind::from(table_type.as_str()),
+            })
+            .collect())
+    }
+
+    // The columns of `catalog.schema.table`: types, comments and extras from `DESCRIBE`,
+    // nullability from `information_schema.columns`
+    pub async fn columns(&self, table: &str) -> Result<Vec<Column>> {
+        let name: TableName = table.parse()?;
+        let not_null: Vec<(String,)> = self
+            .query(&format!(
+                "SELECT column_name FROM {}.information_schema.columns \
+                 WHERE table_schema = {} AND table_name = {} AND is_nullable = 'NO'",
+                quote_identifier(&name.catalog),
+                quote_string(&name.schema),
+                quote_string(&name.table)
+            ))
+            .await?;
+        let rows: Vec<(String, String, String, String)> = self
+            .query(&format!("DESCRIBE {}", qualified(&name)))
+            .await?;
+        rows.into_iter()
+            .map(|(column, ty, extra, comment)| {
+                Ok(Column {
+                    nullable: !not_null.iter().any(|(c,)| *c == column),
+                    name: column,
+                    r#type: ty.parse()?,
+                    comment: metadata::non_empty(comment),
+                    extra: metadata::non_empty(extra),
+                })
+            })
+            .collect()
+    }
+
+    // Every property the table's connector supports, with the values the table sets
+    pub async fn table_properties(&self, table: &str) -> Result<Vec<TableProperty>> {
+        let name: TableName = table.parse()?;
+        let definitions: Vec<(String, String, String, String)> = self
+            .query(&format!(
+                "SELECT property_name, default_value, type, description \
+                 FROM system.metadata.table_properties WHERE catalog_name = {} \
+                 ORDER BY property_name",
+                quote_string(&name.catalog)
+            ))
+            .await?;
+        let create: Vec<(String,)> = self
+            .query(&format!("SHOW CREATE TABLE {}", qualified(&name)))
+            .await?;
+        let values = create
+            .first()
+            .map(|(sql,)| metadata::with_properties(sql))
+            .unwrap_or_default();
+        Ok(definitions
+            .into_iter()
+            .map(
+                |(property, default_value, r#type, description)| TableProperty {
+                    value: values
+                        .iter()
+                        .find(|(name, _)| *name == property)
+                        .map(|(_, value)| value.clone()),
+                    name: property,
+                    default_value,
+                    r#type,
+                    description,
+                },
+            )
+            .collect())
+    }
+
+    // Running and recently finished queries, as on the web UI; all of them when `states` is
+    // empty
+    pub async fn list_queries(&self, states: &[QueryState]) -> Result<Vec<BasicQueryInfo>> {
+        let queries: Vec<BasicQueryInfo> = self
+            .api(reqwest::Method::GET, "query")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(queries
+            .into_iter()
+            .filter(|q| states.is_empty() || states.contains(&q.state))
+            .collect())
+    }
+
+    pub async fn query_info(&self, query_id: &str) -> Result<QueryInfo> {
+        Ok(self
+            .api(
+                reqwest::Method::GET,
+                &format!("query/{}", urlencoding::encode(query_id)),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    // Kills any query, not just ones this client started; `message` shows up in the
+    // query's error
+    pub async fn kill_query(&self, query_id: &str, message: Option<&str>) -> Result<()> {
+        let query_id = urlencoding::encode(query_id);
+        let request = match message {
+            Some(message) => self
+                .api(reqwest::Method::PUT, &format!("query/{}/killed", query_id))
+                .body(message.to_string()),
+            None => self.api(reqwest::Method::DELETE, &format!("query/{}", query_id)),
+        };
+        request.send().await?.error_for_status()?;
+        Ok(())
+    }
+
+    // `system.runtime.queries`, the newest first
+    pub async fn runtime_queries(&self) -> Result<Vec<RuntimeQuery>> {
+        let rows: Vec<RuntimeQueryRow> = self.query(history::RUNTIME_QUERIES).await?;
+        Ok(rows.into_iter().map(RuntimeQuery::from).collect())
+    }
+
+    // The coordinator's `/v1/info`
+    pub async fn info(&self) -> Result<Info> {
+        Ok(self
+            .api(reqwest::Method::GET, "info")
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    // Every node of the cluster, the coordinator first, with the health the coordinator
+    // sees it in; health is left out when the failure detector isn't readable
+    pub async fn nodes(&self) -> Result<Vec<Node>> {
+        let rows: Vec<NodeRow> = self.query(cluster::NODES).await?;
+        let health = match self.node_health("node").await {
+            Ok(health) => health,
+            Err(e) => {
+                tracing::warn!("couldn't read node health: {}", e);
+                Vec::new()
+            }
+        };
+        Ok(cluster::nodes(rows, &health))
+    }
+
+    // Nodes the coordinator's failure detector currently considers failed
+    pub async fn failed_nodes(&self) -> Result<Vec<NodeHealth>> {
+        self.node_health("node/failed").await
+    }
+
+    // A resource group and its sub groups, by its path from the root group like
+    // `["global", "adhoc"]`, as found in `BasicQueryInfo::resource_group_id`
+    pub async fn resource_group(&self, id: &[&str]) -> Result<ResourceGroup> {
+        let path: Vec<String> = id
+            .iter()
+            .map(|part| urlencoding::encode(part).into_owned())
+            .collect();
+        Ok(self
+            .api(
+                reqwest::Method::GET,
+                &format!("resourceGroupState/{}", path.join("/")),
+            )
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn node_health(&self, path: &str) -> Result<Vec<NodeHealth>> {
+        Ok(self
+            .api(reqwest::Method::GET, path)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    // Runs an `INSERT`, `DELETE`, `MERGE`, `CREATE TABLE AS`, DDL... to the end and returns
+    // the rows it changed; queries that return rows fail with `Error::NotAnUpdate`
+    pub async fn execute(&self, sql: &str) -> Result<ExecuteResult> {
+        let outcome = self
+            .run_statement(sql, &mut Session::default(), true)
+            .await?;
+        Ok(ExecuteResult {
+            update_type: outcome.update_type,
+            update_count: outcome.update_count.unwrap_or(0),
+        })
+    }
+
+    // Runs every statement of `sql` in order, in one session, see `script::split_statements`
+    // for how it's split
+    pub async fn execute_script(&self, sql: &str, on_error: OnError) -> ScriptReport {
+        let mut session = Session::default();
+        let mut statements = Vec::new();
+        let mut pending = script::split_statements(sql).into_iter();
+        for statement in pending.by_ref() {
+            let outcome = self.run_statement(&statement, &mut session, false).await;
+            let failed = outcome.is_err();
+            statements.push(StatementResult {
+                sql: statement,
+                outcome,
+            });
+            if failed && on_error == OnError::Stop {
+                // the skipped statements were never going to commit it
+                if session.transaction_id.is_some() {
+                    if let Err(e) = self.run_statement("ROLLBACK", &mut session, false).await {
+                        tracing::warn!("couldn't roll back the script's transaction: {}", e);
+                    }
+                }
+                break;
+            }
+        }
+        ScriptReport {
+            statements,
+            skipped: pending.collect(),
+            session,
+        }
+    }
+
+    // Follows one statement to the end, dropping any rows, with `session` sent along and
+    // updated from the response headers. With `updates_only`, a statement that turns out to
+    // return rows is cancelled as soon as its columns show up, rather than read to the end.
+    async fn run_statement(
+        &self,
+        statement: &str,
+        session: &mut Session,
+        updates_only: bool,
+    ) -> Result<StatementOutcome> {
+        let conn_str = format!("{}:{}/v1/statement", &self.base_url, &self.port);
+        let mut rb = self.http_client.post(conn_str).body(statement.to_string());
+        if let Some(user) = &self.user {
+            rb = rb.header("X-Trino-User", user);
+        }
+        let mut response = session.headers(rb).send().await?.error_for_status()?;
+
+        let mut outcome = StatementOutcome::default();
+        loop {
+            session.apply(response.headers());
+            let raw_text = response.text().await?;
+            let mut results: QueryResults<&RawValue> = serde_json::from_str(&raw_text)?;
+            check(&mut results)?;
+            if updates_only && results.columns.is_some() && results.update_type.is_none() {
+                if let Some(next_uri) = &results.next_uri {
+                    self.cancel(next_uri).await;
+                }
+                return Err(Error::NotAnUpdate(statement.to_string()));
+            }
+            if let (Some(columns), true) = (results.columns, outcome.columns.is_empty()) {
+                outcome.columns = columns.into_iter().map(|c| c.name).collect();
+            }
+            outcome.rows += results.data.map_or(0, |rows| rows.len() as u64);
+            outcome.update_type = results.update_type.or(outcome.update_type);
+            outcome.update_count = results.update_count.or(outcome.update_count);
+            match results.next_uri {
+                Some(next_uri) => {
+                    response = self.next_request(&next_uri).await?.error_for_status()?
+                }
+                None => return Ok(outcome),
+            }
+        }
+    }
+
+    async fn explain_with(
+        &self,
+        query_str: &str,
+        prepared: Option<&str>,
+        options: ExplainOptions,
+    ) -> Result<Explain> {
+        let rows: Vec<(Value,)> = self.fetch(&options.statement(query_str), prepared).await?;
+        let output: Vec<String> = rows
+            .into_iter()
+            .map(|(value,)| match value {
+                Value::String(s) => s,
+                other => other.to_string(),
+            })
+            .collect();
+        Explain::parse(options, &output.join("\n"))
+    }
+
+    #[instrument(skip(self, query_str, prepared), fields(correlation_id = %hash_string(query_str)))]
+    async fn fetch<T>(&self, query_str: &str, prepared: Option<&str>) -> Result<Vec<T>>
     where
         T: DeserializeOwned,
     {
-        let mut response = self.initial_request(query_str).await?;
+        let response = self.initial_request(query_str, prepared).await?;
         let headers = response.headers().clone();
         debug!("trino response headers: {:#?}", headers);
         let raw_text = response.text().await?;
         debug!("raw_text: {}", raw_text);
 
-        let mut response_body: QueryResults = serde_json::from_str(&raw_text).unwrap();
+        let mut response_body: QueryResults<&RawValue> = serde_json::from_str(&raw_text)?;
         debug!("initial response_body: {:?}", response_body);
+        check(&mut response_body)?;
 
         let mut data = Vec::new();
-        while let Some(next_uri) = response_body.next_uri {
-            response = self.next_request(&next_uri).await?;
-            response_body = response.json().await?;
+        if let Some(rows) = response_body.data {
+            extend_rows(&mut data, &rows)?;
+        }
 
-            if let Some(rows) = response_body.data {
+        // pages are fetched ahead of decoding on a background task, bounded by `self.prefetch`
+        let mut pages = PageStream::spawn(
+            self.http_client.clone(),
+            response_body.next_uri,
+            self.prefetch,
+        );
+        while let Some(page) = pages.next().await {
+            let page = page?;
+            let mut results = page.results::<&RawValue>()?;
+            check(&mut results)?;
+            if let Some(rows) = results.data {
                 debug!("rows: {:?}", rows);
-                data.extend(rows.into_iter().map(|x| serde_json::from_value(x).unwrap()));
+                extend_rows(&mut data, &rows)?;
             }
         }
         Ok(data)
     }
 
-    #[instrument(skip(self, query_str), fields(correlation_id = %hash_string(query_str)))]
-    pub async fn query_once<T>(&self, query_str: &str) -> Result<T, reqwest::Error>
+    // Like `query`, but rows are mapped through `TrinoRow`, so the columns are checked
+    // against `T` before any row is decoded
+    pub async fn query_rows<T>(&self, query_str: &str) -> Result<Vec<T>>
+    where
+        T: TrinoRow,
+    {
+        self.query_bound(query_str, RowMapper::new(T::bind, T::from_row))
+            .await
+    }
+
+    #[instrument(skip(self, query_str, mapper), fields(correlation_id = %hash_string(query_str)))]
+    pub(crate) async fn query_bound<T>(
+        &self,
+        query_str: &str,
+        mut mapper: RowMapper<T>,
+    ) -> Result<Vec<T>> {
+        let response = self.initial_request(query_str, None).await?;
+        let raw_text = response.text().await?;
+        debug!("raw_text: {}", raw_text);
+
+        let mut response_body: QueryResults<&RawValue> = serde_json::from_str(&raw_text)?;
+        check(&mut response_body)?;
+        let mut data = Vec::new();
+        mapper.extend(&mut data, response_body.columns, response_body.data)?;
+
+        let mut pages = PageStream::spawn(
+            self.http_client.clone(),
+            response_body.next_uri,
+            self.prefetch,
+        );
+        while let Some(page) = pages.next().await {
+            let page = page?;
+            let mut results = page.results::<&RawValue>()?;
+            check(&mut results)?;
+            mapper.extend(&mut data, results.columns, results.data)?;
+        }
+        Ok(data)
+    }
+
+    pub async fn query_once<T>(&self, query_str: &str) -> Result<T>
     where
         T: DeserializeOwned,
     {
-        let mut response = self.initial_request(query_str).await?;
+        self.fetch_once(query_str, None).await
+    }
+
+    pub async fn query_once_prepared<T>(&self, query_str: &str, prepared: &str) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        self.fetch_once(query_str, Some(prepared)).await
+    }
+
+    #[instrument(skip(self, query_str, prepared), fields(correlation_id = %hash_string(query_str)))]
+    async fn fetch_once<T>(&self, query_str: &str, prepared: Option<&str>) -> Result<T>
+    where
+        T: DeserializeOwned,
+    {
+        let mut response = self.initial_request(query_str, prepared).await?;
         let headers = response.headers().clone();
         debug!("trino response headers: {:#?}", headers);
         let raw_text = response.text().await?;
         debug!("raw_text: {}", raw_text);
 
-        let mut response_body: QueryResults = serde_json::from_str(&raw_text).unwrap();
+        let mut response_body: QueryResults = serde_json::from_str(&raw_text)?;
         debug!("initial response_body: {:?}", response_body);
+        check(&mut response_body)?;
 
         while let Some(next_uri) = response_body.next_uri {
-            response = self.next_request(&next_uri).await?;
+            response = self.next_request(&next_uri).await?.error_for_status()?;
             response_body = response.json().await?;
+            check(&mut response_body)?;
 
             if let Some(mut data) = response_body.data {
                 if let Some(Value::Array(arr)) = data.pop() {
@@ -186,24 +749,133 @@ impl Client {
         panic!("Failed to get JSON formatted response from Trino");
     }
 
+    // A request to the coordinator's REST API under `/v1/`
+    fn api(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let url = format!("{}:{}/v1/{}", &self.base_url, &self.port, path);
+        let mut rb = self.http_client.request(method, url);
+        if let Some(user) = &self.user {
+            rb = rb.header("X-Trino-User", user);
+        }
+        rb
+    }
+
     // Make the initial request to Trino, hash the query string as a correlation_id
-    #[instrument(skip(self, query_str), fields(correlation_id = %hash_string(query_str)))]
-    async fn initial_request(&self, query_str: &str) -> Result<Response, reqwest::Error> {
+    #[instrument(skip(self, query_str, prepared), fields(correlation_id = %hash_string(query_str)))]
+    async fn initial_request(
+        &self,
+        query_str: &str,
+        prepared: Option<&str>,
+    ) -> reqwest::Result<Response> {
         let conn_str = format!("{}:{}/v1/statement", &self.base_url, &self.port);
         let mut rb = self.http_client.post(conn_str).body(query_str.to_string());
 
         if let Some(user) = &self.user {
             rb = rb.header("X-Trino-User", user);
         }
+        if let Some(prepared) = prepared {
+            rb = rb.header(
+                "X-Trino-Prepared-Statement",
+                format!("{}={}", STATEMENT_NAME, urlencoding::encode(prepared)),
+            );
+        }
 
         debug!("initial_request headers: {:#?}", rb);
 
-        rb.send().await
+        rb.send().await?.error_for_status()
+    }
+
+    // Cancels a running query, which is what a DELETE of its `nextUri` does
+    async fn cancel(&self, next_uri: &str) {
+        if let Err(e) = self.http_client.delete(next_uri).send().await {
+            tracing::warn!("couldn't cancel the query at {}: {}", next_uri, e);
+        }
     }
 
     #[tracing::instrument(skip(self))]
-    async fn next_request(&self, next_uri: &str) -> Result<Response, reqwest::Error> {
+    async fn next_request(&self, next_uri: &str) -> reqwest::Result<Response> {
         debug!("navigating to next_uri: {}", next_uri);
         self.http_client.get(next_uri).send().await
     }
 }
+
+// Trino reports a failed query in the body of a successful response, on whichever page
+// it fails
+fn check<D>(results: &mut QueryResults<D>) -> Result<()> {
+    match results.error.take() {
+        Some(error) => Err(Error::Query(error)),
+        None => Ok(()),
+    }
+}
+
+// Rows are still borrowed slices of the page body, so each one is decoded
+// straight into `T` without building an intermediate `Value` tree
+fn extend_rows<T>(data: &mut Vec<T>, rows: &[&RawValue]) -> Result<()>
+where
+    T: DeserializeOwned,
+{
+    data.reserve(rows.len());
+    for row in rows {
+        data.push(serde_json::from_str(row.get())?);
+    }
+    Ok(())
+}
+
+pub(crate) type BindFn = fn(&[ColumnRef<'_>]) -> std::result::Result<Vec<usize>, Vec<String>>;
+pub(crate) type DecodeFn<T> = fn(&[Value], &[TrinoType], &[usize]) -> Result<T>;
+
+// Columns show up on the first page that has them, rows can't be decoded before that
+pub(crate) struct RowMapper<T> {
+    bind: BindFn,
+    decode: DecodeFn<T>,
+    types: Vec<TrinoType>,
+    indices: Option<Vec<usize>>,
+}
+
+impl<T> RowMapper<T> {
+    pub(crate) fn new(bind: BindFn, decode: DecodeFn<T>) -> Self {
+        RowMapper {
+            bind,
+            decode,
+            types: Vec::new(),
+            indices: None,
+        }
+    }
+
+    fn extend(
+        &mut self,
+        data: &mut Vec<T>,
+        columns: Option<Vec<QueryResultColumn>>,
+        rows: Option<Vec<&RawValue>>,
+    ) -> Result<()> {
+        if let (None, Some(columns)) = (&self.indices, columns) {
+            self.types = columns
+                .iter()
+                .map(|c| c.trino_type())
+                .collect::<std::result::Result<_, _>>()?;
+            let refs: Vec<ColumnRef<'_>> = columns
+                .iter()
+                .zip(&self.types)
+                .map(|(c, ty)| ColumnRef {
+                    name: Some(c.name.as_str()),
+                    r#type: ty,
+                })
+                .collect();
+            self.indices = Some((self.bind)(&refs).map_err(Error::ColumnMismatch)?);
+        }
+
+        let rows = match rows {
+            Some(rows) => rows,
+            None => return Ok(()),
+        };
+        let indices = self
+            .indices
+            .as_ref()
+            .ok_or_else(|| Error::Decode("received rows before column metadata".to_string()))?;
+        data.reserve(rows.len());
+        for row in rows {
+            let values: Vec<Value> = serde_json::from_str(row.get())?;
+            data.push((self.decode)(&values, &self.types, indices)?);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,164 @@
+// Runtime support for `query_as!`. The macro resolves every output column to one of
+// the marker types below at compile time, and `Accepts` decides which Rust types may
+// hold it, so a type mismatch is a compile error at the field it belongs to.
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+use crate::error::{Error, Result};
+use crate::row::{column_position, ColumnRef, Value};
+use crate::{BindFn, Client, DecodeFn, RowMapper};
+
+pub struct Boolean;
+pub struct TinyInt;
+pub struct SmallInt;
+pub struct Integer;
+pub struct BigInt;
+pub struct Real;
+pub struct Double;
+pub struct Decimal;
+pub struct Text;
+pub struct Varbinary;
+pub struct Json;
+pub struct Date;
+pub struct Time;
+pub struct TimeTz;
+pub struct Timestamp;
+pub struct TimestampTz;
+pub struct Interval;
+pub struct Uuid;
+pub struct IpAddress;
+pub struct Row;
+pub struct Unknown;
+pub struct Array<T>(PhantomData<T>);
+pub struct Map<K, V>(PhantomData<(K, V)>);
+
+#[diagnostic::on_unimplemented(
+    message = "query_as!: a `{M}` column can't be decoded into `{Self}`",
+    label = "field type doesn't match the column"
+)]
+pub trait Accepts<M> {}
+
+pub fn accepts<M, T: Accepts<M>>(_field: &T) {}
+
+impl<M> Accepts<M> for Value {}
+impl<M, T: Accepts<M>> Accepts<M> for Option<T> {}
+
+impl Accepts<Boolean> for bool {}
+
+impl Accepts<TinyInt> for i8 {}
+impl Accepts<TinyInt> for i16 {}
+impl Accepts<SmallInt> for i16 {}
+impl Accepts<TinyInt> for i32 {}
+impl Accepts<SmallInt> for i32 {}
+impl Accepts<Integer> for i32 {}
+impl Accepts<TinyInt> for i64 {}
+impl Accepts<SmallInt> for i64 {}
+impl Accepts<Integer> for i64 {}
+impl Accepts<BigInt> for i64 {}
+
+impl Accepts<Real> for f32 {}
+impl Accepts<Real> for f64 {}
+impl Accepts<Double> for f64 {}
+
+macro_rules! accepts_as_string {
+    ($($marker:ty),*) => {
+        $(impl Accepts<$marker> for String {})*
+    };
+}
+
+accepts_as_string!(
+    Decimal,
+    Text,
+    Json,
+    Date,
+    Time,
+    TimeTz,
+    Timestamp,
+    TimestampTz,
+    Interval,
+    Uuid,
+    IpAddress,
+    Unknown
+);
+
+impl Accepts<Varbinary> for Vec<u8> {}
+impl<M, T: Accepts<M>> Accepts<Array<M>> for Vec<T> {}
+impl<KM, VM, K: Accepts<KM>, V: Accepts<VM>> Accepts<Map<KM, VM>> for HashMap<K, V> {}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::*;
+
+    impl Accepts<Date> for chrono::NaiveDate {}
+    impl Accepts<Time> for chrono::NaiveTime {}
+    impl Accepts<Timestamp> for chrono::NaiveDateTime {}
+    impl Accepts<TimestampTz> for chrono::DateTime<chrono::FixedOffset> {}
+    impl Accepts<TimestampTz> for chrono::DateTime<chrono::Utc> {}
+}
+
+#[cfg(feature = "bigdecimal")]
+mod bigdecimal_impls {
+    use super::*;
+
+    impl Accepts<Decimal> for bigdecimal::BigDecimal {}
+    impl Accepts<TinyInt> for bigdecimal::BigDecimal {}
+    impl Accepts<SmallInt> for bigdecimal::BigDecimal {}
+    impl Accepts<Integer> for bigdecimal::BigDecimal {}
+    impl Accepts<BigInt> for bigdecimal::BigDecimal {}
+}
+
+// Columns are looked up by the names captured at compile time, so a reordered
+// or renamed column on the server is reported instead of silently misread
+pub fn bind_columns(
+    columns: &[ColumnRef<'_>],
+    expected: &[&str],
+) -> std::result::Result<Vec<usize>, Vec<String>> {
+    let mut errors = Vec::new();
+    let binding = expected
+        .iter()
+        .map(|name| {
+            column_position(columns, name).unwrap_or_else(|| {
+                errors.push(format!(
+                    "column `{}` was checked at compile time but is no longer returned",
+                    name
+                ));
+                0
+            })
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(binding)
+    } else {
+        Err(errors)
+    }
+}
+
+pub struct CheckedQuery<T> {
+    sql: &'static str,
+    bind: BindFn,
+    decode: DecodeFn<T>,
+}
+
+impl<T> CheckedQuery<T> {
+    pub fn new(sql: &'static str, bind: BindFn, decode: DecodeFn<T>) -> Self {
+        CheckedQuery { sql, bind, decode }
+    }
+
+    pub fn sql(&self) -> &'static str {
+        self.sql
+    }
+
+    pub async fn fetch_all(&self, client: &Client) -> Result<Vec<T>> {
+        client
+            .query_bound(self.sql, RowMapper::new(self.bind, self.decode))
+            .await
+    }
+
+    pub async fn fetch_one(&self, client: &Client) -> Result<T> {
+        self.fetch_all(client)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| Error::Decode("query returned no rows".to_string()))
+    }
+}
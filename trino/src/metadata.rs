@@ -0,0 +1,194 @@
+// Typed views of the catalogs, schemas, tables and columns of the cluster, see
+// `Client::catalogs` and friends. Everything comes from `information_schema`,
+// `system.metadata` and `DESCRIBE`, which every connector supports.
+use std::fmt;
+use std::str::FromStr;
+
+use crate::types::TrinoType;
+use crate::Error;
+
+// A fully qualified `catalog.schema.table`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TableName {
+    pub catalog: String,
+    pub schema: String,
+    pub table: String,
+}
+
+impl TableName {
+    pub fn new(catalog: &str, schema: &str, table: &str) -> Self {
+        TableName {
+            catalog: catalog.to_string(),
+            schema: schema.to_string(),
+            table: table.to_string(),
+        }
+    }
+}
+
+impl FromStr for TableName {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split('.').collect::<Vec<_>>()[..] {
+            [catalog, schema, table]
+                if !catalog.is_empty() && !schema.is_empty() && !table.is_empty() =>
+            {
+                Ok(TableName::new(catalog, schema, table))
+            }
+            _ => Err(Error::Parameter(format!(
+                "expected a table as catalog.schema.table, got `{}`",
+                s
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for TableName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.catalog, self.schema, self.table)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    pub name: String,
+    // `hive`, `iceberg`, `postgresql`...
+    pub connector: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schema {
+    pub catalog: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TableKind {
+    Table,
+    View,
+    // any other `table_type` a connector reports, verbatim
+    Other(String),
+}
+
+impl From<&str> for TableKind {
+    fn from(table_type: &str) -> Self {
+        match table_type {
+            "BASE TABLE" => TableKind::Table,
+            "VIEW" => TableKind::View,
+            other => TableKind::Other(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub name: TableName,
+    pub kind: TableKind,
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Column {
+    pub name: String,
+    pub r#type: TrinoType,
+    pub nullable: bool,
+    pub comment: Option<String>,
+    // connector specific notes from `DESCRIBE`, like `partition key`
+    pub extra: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableProperty {
+    pub name: String,
+    // the SQL literal the table sets it to, `None` when it's left at the default
+    pub value: Option<String>,
+    pub default_value: String,
+    pub r#type: String,
+    pub description: String,
+}
+
+// The `name = value` pairs of the `WITH (...)` clause of a `SHOW CREATE TABLE`, values as
+// the SQL literals they are written as
+pub(crate) fn with_properties(create_table: &str) -> Vec<(String, String)> {
+    let start = match create_table.rfind("\nWITH (") {
+        Some(start) => start + "\nWITH (".len(),
+        None => return Vec::new(),
+    };
+    let body = create_table[start..].trim_end();
+    let body = body.strip_suffix(')').unwrap_or(body);
+
+    let mut properties = Vec::new();
+    let mut depth = 0;
+    let mut quoted = None;
+    let mut item = String::new();
+    for c in body.chars().chain(Some(',')) {
+        match (c, quoted) {
+            (q, Some(open)) if q == open => quoted = None,
+            (_, Some(_)) => {}
+            ('\'', None) | ('"', None) => quoted = Some(c),
+            ('(', None) | ('[', None) => depth += 1,
+            (')', None) | (']', None) => depth -= 1,
+            (',', None) if depth == 0 => {
+                if let Some((name, value)) = item.split_once('=') {
+                    properties.push((name.trim().to_string(), value.trim().to_string()));
+                }
+                item.clear();
+                continue;
+            }
+            _ => {}
+        }
+        item.push(c);
+    }
+    properties
+}
+
+// `DESCRIBE` returns empty strings for missing extras and comments
+pub(crate) fn non_empty(s: String) -> Option<String> {
+    if s.is_empty() {
+        None
+    } else {
+        Some(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_with_properties() {
+        let create_table = "CREATE TABLE hive.web.events (\n   \
+            id bigint,\n   \
+            \"day\" varchar COMMENT 'a, b'\n\
+            )\n\
+            COMMENT 'page views'\n\
+            WITH (\n   \
+            bucketed_by = ARRAY['id','day'],\n   \
+            bucket_count = 8,\n   \
+            format = 'ORC',\n   \
+            location = 's3://bucket/it''s, here'\n\
+            )";
+        assert_eq!(
+            with_properties(create_table),
+            vec![
+                ("bucketed_by".to_string(), "ARRAY['id','day']".to_string()),
+                ("bucket_count".to_string(), "8".to_string()),
+                ("format".to_string(), "'ORC'".to_string()),
+                (
+                    "location".to_string(),
+                    "'s3://bucket/it''s, here'".to_string()
+                ),
+            ]
+        );
+        assert!(with_properties("CREATE TABLE memory.default.t (\n   id bigint\n)").is_empty());
+    }
+
+    #[test]
+    fn parse_table_name() {
+        let name: TableName = "hive.web.events".parse().unwrap();
+        assert_eq!(name, TableName::new("hive", "web", "events"));
+        assert_eq!(name.to_string(), "hive.web.events");
+        assert!("web.events".parse::<TableName>().is_err());
+        assert!("hive..events".parse::<TableName>().is_err());
+    }
+}
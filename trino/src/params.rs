@@ -0,0 +1,506 @@
+// Query parameters. Trino only understands `?`, so `:name` placeholders are rewritten
+// before the statement is prepared, and every value is sent as a SQL literal in
+// `EXECUTE ... USING`, with the statement itself in the `X-Trino-Prepared-Statement` header.
+use crate::error::{Error, Result};
+
+pub const STATEMENT_NAME: &str = "trino_rs_statement";
+
+pub trait ToTrinoLiteral {
+    fn to_literal(&self) -> String;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Placeholders {
+    // the query with every placeholder turned into `?`
+    pub sql: String,
+    // one entry per `?` in `sql`, `None` for positional placeholders
+    pub names: Vec<Option<String>>,
+}
+
+impl Placeholders {
+    pub fn parse(sql: &str) -> Result<Self> {
+        let mut out = String::with_capacity(sql.len());
+        let mut names = Vec::new();
+        for (segment, range) in segments(sql) {
+            let text = &sql[range];
+            if segment != Segment::Code {
+                out.push_str(text);
+                continue;
+            }
+            let chars: Vec<char> = text.chars().collect();
+            let mut i = 0;
+            while i < chars.len() {
+                match chars[i] {
+                    '?' => {
+                        out.push('?');
+                        names.push(None);
+                        i += 1;
+                    }
+                    ':' if chars.get(i + 1).is_some_and(|c| is_ident_start(*c))
+                        && (i == 0 || chars[i - 1] != ':') =>
+                    {
+                        let end = chars[i + 1..]
+                            .iter()
+                            .position(|c| !is_ident_char(*c))
+                            .map_or(chars.len(), |p| i + 1 + p);
+                        out.push('?');
+                        names.push(Some(chars[i + 1..end].iter().collect()));
+                        i = end;
+                    }
+                    c => {
+                        out.push(c);
+                        i += 1;
+                    }
+                }
+            }
+        }
+
+        if names.iter().any(Option::is_some) && names.iter().any(Option::is_none) {
+            return Err(Error::Parameter(
+                "query mixes `?` and `:name` placeholders".to_string(),
+            ));
+        }
+        Ok(Placeholders { sql: out, names })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    // Named placeholders in the order they first appear, each listed once
+    pub fn unique_names(&self) -> Vec<&str> {
+        let mut unique: Vec<&str> = Vec::new();
+        for name in self.names.iter().flatten() {
+            if !unique.contains(&name.as_str()) {
+                unique.push(name);
+            }
+        }
+        unique
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Segment {
+    Code,
+    // a string literal or quoted identifier, quotes included
+    Quoted,
+    // from `--` up to, not including, the end of the line
+    LineComment,
+    BlockComment,
+}
+
+// Splits `sql` into byte ranges of code, quoted text and comments, so that what looks like
+// a placeholder or a comment inside a string isn't taken for one
+pub fn segments(sql: &str) -> Vec<(Segment, std::ops::Range<usize>)> {
+    let chars: Vec<char> = sql.chars().collect();
+    let offsets: Vec<usize> = sql
+        .char_indices()
+        .map(|(offset, _)| offset)
+        .chain(Some(sql.len()))
+        .collect();
+    let mut segments = Vec::new();
+    let mut code_start = 0;
+    let mut i = 0;
+    while i < chars.len() {
+        let (segment, end) = match chars[i] {
+            c @ ('\'' | '"') => (Segment::Quoted, quoted_end(&chars, i, c)),
+            '-' if chars.get(i + 1) == Some(&'-') => (
+                Segment::LineComment,
+                chars[i..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(chars.len(), |p| i + p),
+            ),
+            '/' if chars.get(i + 1) == Some(&'*') => (
+                Segment::BlockComment,
+                chars[i + 2..]
+                    .windows(2)
+                    .position(|w| w == ['*', '/'])
+                    .map_or(chars.len(), |p| i + 2 + p + 2),
+            ),
+            _ => {
+                i += 1;
+                continue;
+            }
+        };
+        if code_start < i {
+            segments.push((Segment::Code, offsets[code_start]..offsets[i]));
+        }
+        segments.push((segment, offsets[i]..offsets[end]));
+        code_start = end;
+        i = end;
+    }
+    if code_start < chars.len() {
+        segments.push((Segment::Code, offsets[code_start]..sql.len()));
+    }
+    segments
+}
+
+// Index just past the closing quote, where a doubled quote is an escaped one
+pub(crate) fn quoted_end(chars: &[char], start: usize, quote: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == quote {
+            if chars.get(i + 1) == Some(&quote) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_ascii_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct Params {
+    positional: Vec<String>,
+    named: Vec<(String, String)>,
+}
+
+impl Params {
+    pub fn new() -> Self {
+        Params::default()
+    }
+
+    // Value for the next `?`
+    pub fn push<T: ToTrinoLiteral>(mut self, value: T) -> Self {
+        self.positional.push(value.to_literal());
+        self
+    }
+
+    // Value for every `:name`
+    pub fn bind<T: ToTrinoLiteral>(mut self, name: &str, value: T) -> Self {
+        let literal = value.to_literal();
+        match self.named.iter_mut().find(|(n, _)| n == name) {
+            Some((_, existing)) => *existing = literal,
+            None => self.named.push((name.to_string(), literal)),
+        }
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.positional.is_empty() && self.named.is_empty()
+    }
+
+    // The `EXECUTE` statement that runs the prepared query with these values
+    pub fn execute(&self, placeholders: &Placeholders) -> Result<String> {
+        let positional = placeholders.names.iter().filter(|n| n.is_none()).count();
+        if positional != self.positional.len() {
+            return Err(Error::Parameter(format!(
+                "query has {} `?` placeholders but {} values were pushed",
+                positional,
+                self.positional.len()
+            )));
+        }
+        if let Some((name, _)) = self
+            .named
+            .iter()
+            .find(|(name, _)| !placeholders.names.contains(&Some(name.clone())))
+        {
+            return Err(Error::Parameter(format!(
+                "`:{}` was bound but the query doesn't use it",
+                name
+            )));
+        }
+
+        let mut next = self.positional.iter();
+        let literals = placeholders
+            .names
+            .iter()
+            .map(|name| match name {
+                None => Ok(next.next().expect("counted above").as_str()),
+                Some(name) => self
+                    .named
+                    .iter()
+                    .find(|(n, _)| n == name)
+                    .map(|(_, literal)| literal.as_str())
+                    .ok_or_else(|| Error::Parameter(format!("no value bound for `:{}`", name))),
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(format!(
+            "EXECUTE {} USING {}",
+            STATEMENT_NAME,
+            literals.join(", ")
+        ))
+    }
+}
+
+pub fn quote_string(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+pub fn quote_identifier(s: &str) -> String {
+    format!("\"{}\"", s.replace('"', "\"\""))
+}
+
+impl<T: ToTrinoLiteral + ?Sized> ToTrinoLiteral for &T {
+    fn to_literal(&self) -> String {
+        (**self).to_literal()
+    }
+}
+
+impl<T: ToTrinoLiteral> ToTrinoLiteral for Option<T> {
+    fn to_literal(&self) -> String {
+        match self {
+            Some(value) => value.to_literal(),
+            None => "NULL".to_string(),
+        }
+    }
+}
+
+impl ToTrinoLiteral for bool {
+    fn to_literal(&self) -> String {
+        if *self { "TRUE" } else { "FALSE" }.to_string()
+    }
+}
+
+macro_rules! integer_literal {
+    ($($ty:ty => $cast:literal),*) => {
+        $(impl ToTrinoLiteral for $ty {
+            fn to_literal(&self) -> String {
+                format!(concat!($cast, " '{}'"), self)
+            }
+        })*
+    };
+}
+
+integer_literal!(i8 => "TINYINT", i16 => "SMALLINT", i32 => "INTEGER", i64 => "BIGINT");
+
+// A bare `1.5` is a DECIMAL in Trino, so floats always carry their type
+macro_rules! float_literal {
+    ($($ty:ty => $name:literal),*) => {
+        $(impl ToTrinoLiteral for $ty {
+            fn to_literal(&self) -> String {
+                if self.is_nan() {
+                    format!("CAST(nan() AS {})", $name)
+                } else if self.is_infinite() {
+                    let sign = if *self > 0.0 { "" } else { "-" };
+                    format!("CAST({}infinity() AS {})", sign, $name)
+                } else {
+                    format!(concat!($name, " '{:?}'"), self)
+                }
+            }
+        })*
+    };
+}
+
+float_literal!(f32 => "REAL", f64 => "DOUBLE");
+
+impl ToTrinoLiteral for str {
+    fn to_literal(&self) -> String {
+        quote_string(self)
+    }
+}
+
+impl ToTrinoLiteral for String {
+    fn to_literal(&self) -> String {
+        quote_string(self)
+    }
+}
+
+impl ToTrinoLiteral for [u8] {
+    fn to_literal(&self) -> String {
+        let hex: String = self.iter().map(|b| format!("{:02X}", b)).collect();
+        format!("X'{}'", hex)
+    }
+}
+
+impl<T: ToTrinoLiteral> ToTrinoLiteral for [T] {
+    fn to_literal(&self) -> String {
+        let elements: Vec<String> = self.iter().map(ToTrinoLiteral::to_literal).collect();
+        format!("ARRAY[{}]", elements.join(", "))
+    }
+}
+
+impl<T> ToTrinoLiteral for Vec<T>
+where
+    [T]: ToTrinoLiteral,
+{
+    fn to_literal(&self) -> String {
+        self.as_slice().to_literal()
+    }
+}
+
+// An empty map is `MAP()`, whose `unknown` key and value types cast to anything
+impl<K: ToTrinoLiteral, V: ToTrinoLiteral, S> ToTrinoLiteral
+    for std::collections::HashMap<K, V, S>
+{
+    fn to_literal(&self) -> String {
+        if self.is_empty() {
+            return "MAP()".to_string();
+        }
+        let (keys, values): (Vec<String>, Vec<String>) = self
+            .iter()
+            .map(|(k, v)| (k.to_literal(), v.to_literal()))
+            .unzip();
+        format!(
+            "MAP(ARRAY[{}], ARRAY[{}])",
+            keys.join(", "),
+            values.join(", ")
+        )
+    }
+}
+
+impl ToTrinoLiteral for std::net::IpAddr {
+    fn to_literal(&self) -> String {
+        format!("IPADDRESS '{}'", self)
+    }
+}
+
+impl ToTrinoLiteral for serde_json::Value {
+    fn to_literal(&self) -> String {
+        format!("JSON {}", quote_string(&self.to_string()))
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use super::*;
+
+    impl ToTrinoLiteral for chrono::NaiveDate {
+        fn to_literal(&self) -> String {
+            format!("DATE '{}'", self.format("%Y-%m-%d"))
+        }
+    }
+
+    impl ToTrinoLiteral for chrono::NaiveTime {
+        fn to_literal(&self) -> String {
+            format!("TIME '{}'", self.format("%H:%M:%S%.f"))
+        }
+    }
+
+    impl ToTrinoLiteral for chrono::NaiveDateTime {
+        fn to_literal(&self) -> String {
+            format!("TIMESTAMP '{}'", self.format("%Y-%m-%d %H:%M:%S%.f"))
+        }
+    }
+
+    impl ToTrinoLiteral for chrono::DateTime<chrono::Utc> {
+        fn to_literal(&self) -> String {
+            format!("TIMESTAMP '{} UTC'", self.format("%Y-%m-%d %H:%M:%S%.f"))
+        }
+    }
+
+    impl ToTrinoLiteral for chrono::DateTime<chrono::FixedOffset> {
+        fn to_literal(&self) -> String {
+            format!("TIMESTAMP '{}'", self.format("%Y-%m-%d %H:%M:%S%.f %:z"))
+        }
+    }
+
+    impl ToTrinoLiteral for chrono::Duration {
+        fn to_literal(&self) -> String {
+            let sign = if *self < chrono::Duration::zero() {
+                "-"
+            } else {
+                ""
+            };
+            let abs = self.abs();
+            let millis = abs.num_milliseconds();
+            format!(
+                "INTERVAL '{}{} {:02}:{:02}:{:02}.{:03}' DAY TO SECOND",
+                sign,
+                abs.num_days(),
+                millis / 3_600_000 % 24,
+                millis / 60_000 % 60,
+                millis / 1_000 % 60,
+                millis % 1_000
+            )
+        }
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl ToTrinoLiteral for bigdecimal::BigDecimal {
+    fn to_literal(&self) -> String {
+        format!("DECIMAL '{}'", self)
+    }
+}
+
+#[cfg(feature = "uuid")]
+impl ToTrinoLiteral for uuid::Uuid {
+    fn to_literal(&self) -> String {
+        format!("UUID '{}'", self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_placeholders() {
+        let p = Placeholders::parse(
+            "SELECT ':not_me', \"a:b\" -- :nor_me ?\nFROM t /* ? */ WHERE d >= :start AND t = :tenant AND d < :start",
+        )
+        .unwrap();
+        assert_eq!(
+            p.sql,
+            "SELECT ':not_me', \"a:b\" -- :nor_me ?\nFROM t /* ? */ WHERE d >= ? AND t = ? AND d < ?"
+        );
+        assert_eq!(p.unique_names(), vec!["start", "tenant"]);
+        assert_eq!(p.names.len(), 3);
+
+        let p = Placeholders::parse("SELECT * FROM t WHERE a = ? AND b = 'it''s ?'").unwrap();
+        assert_eq!(p.names, vec![None]);
+
+        assert!(Placeholders::parse("SELECT ? + :a").is_err());
+    }
+
+    #[test]
+    fn execute_statement() {
+        let p = Placeholders::parse("SELECT * FROM t WHERE a = :a AND b = :b OR a = :a").unwrap();
+        let params = Params::new().bind("b", "o'brien").bind("a", 3i64);
+        assert_eq!(
+            params.execute(&p).unwrap(),
+            "EXECUTE trino_rs_statement USING BIGINT '3', 'o''brien', BIGINT '3'"
+        );
+        assert!(Params::new().bind("a", 1i32).execute(&p).is_err());
+        assert!(params.clone().bind("c", 1i32).execute(&p).is_err());
+
+        let p = Placeholders::parse("SELECT ?, ?").unwrap();
+        assert_eq!(
+            Params::new()
+                .push(Some(true))
+                .push(None::<i32>)
+                .execute(&p)
+                .unwrap(),
+            "EXECUTE trino_rs_statement USING TRUE, NULL"
+        );
+        assert!(Params::new().push(1i32).execute(&p).is_err());
+    }
+
+    #[test]
+    fn literals() {
+        assert_eq!(1.5f64.to_literal(), "DOUBLE '1.5'");
+        assert_eq!(f32::NEG_INFINITY.to_literal(), "CAST(-infinity() AS REAL)");
+        assert_eq!(b"\x01\xff"[..].to_literal(), "X'01FF'");
+        assert_eq!(vec!["a", "b"].to_literal(), "ARRAY['a', 'b']");
+        let map: std::collections::HashMap<&str, Option<i32>> =
+            vec![("k", None)].into_iter().collect();
+        assert_eq!(map.to_literal(), "MAP(ARRAY['k'], ARRAY[NULL])");
+        assert_eq!(
+            serde_json::json!({"k": "it's"}).to_literal(),
+            "JSON '{\"k\":\"it''s\"}'"
+        );
+    }
+
+    #[cfg(feature = "chrono")]
+    #[test]
+    fn chrono_literals() {
+        let interval = -(chrono::Duration::days(2) + chrono::Duration::milliseconds(3_723_004));
+        assert_eq!(
+            interval.to_literal(),
+            "INTERVAL '-2 01:02:03.004' DAY TO SECOND"
+        );
+    }
+}
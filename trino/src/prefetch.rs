@@ -0,0 +1,168 @@
+use std::convert::TryFrom;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use reqwest::Client as ReqwestClient;
+use serde::Deserialize;
+use tokio::sync::{mpsc, OwnedSemaphorePermit, Semaphore};
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+use crate::error::Result;
+use crate::response::QueryResults;
+
+/// Limits how far the page fetcher may run ahead of row decoding.
+///
+/// A page counts against `max_pages` from the moment its download starts
+/// until the caller has finished decoding it, so `max_pages: 1` gives the
+/// old strictly sequential behaviour. It counts against `max_bytes` from the
+/// moment its size is known: before the body is read when the response has a
+/// `Content-Length`, only once it's downloaded otherwise (compressed or chunked
+/// responses), so `max_bytes` can then be exceeded by the one page in flight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefetchConfig {
+    pub max_pages: usize,
+    pub max_bytes: usize,
+}
+
+impl Default for PrefetchConfig {
+    fn default() -> Self {
+        PrefetchConfig {
+            max_pages: 4,
+            max_bytes: 64 * 1024 * 1024,
+        }
+    }
+}
+
+pub(crate) struct Page {
+    body: Bytes,
+    _pages: OwnedSemaphorePermit,
+    _bytes: OwnedSemaphorePermit,
+}
+
+pub(crate) struct PageStream {
+    rx: mpsc::UnboundedReceiver<Result<Page>>,
+    task: JoinHandle<()>,
+}
+
+impl Page {
+    // Full decoding happens on the consumer side so it overlaps the next download
+    pub(crate) fn results<'a, D>(&'a self) -> Result<QueryResults<D>>
+    where
+        D: Deserialize<'a>,
+    {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+// Only the continuation is needed to keep the fetcher moving, everything else is skipped
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Continuation {
+    next_uri: Option<String>,
+}
+
+impl PageStream {
+    // Follow `next_uri` on a background task, handing parsed pages back in order
+    pub(crate) fn spawn(
+        http_client: ReqwestClient,
+        next_uri: Option<String>,
+        config: PrefetchConfig,
+    ) -> Self {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let pages = Arc::new(Semaphore::new(config.max_pages.max(1)));
+        let budget = Budget::new(config.max_bytes);
+
+        let task = tokio::spawn(async move {
+            let mut next_uri = next_uri;
+            while let Some(uri) = next_uri.take() {
+                let page_permit = match pages.clone().acquire_owned().await {
+                    Ok(permit) => permit,
+                    Err(_) => return,
+                };
+
+                let (body, continuation, bytes_permit) =
+                    match fetch(&http_client, &uri, &budget).await {
+                        Ok(page) => page,
+                        Err(e) => {
+                            let _ = tx.send(Err(e));
+                            return;
+                        }
+                    };
+
+                next_uri = continuation.next_uri;
+                let page = Page {
+                    body,
+                    _pages: page_permit,
+                    _bytes: bytes_permit,
+                };
+                if tx.send(Ok(page)).is_err() {
+                    debug!("page consumer went away, stopping prefetch");
+                    return;
+                }
+            }
+        });
+
+        PageStream { rx, task }
+    }
+
+    pub(crate) async fn next(&mut self) -> Option<Result<Page>> {
+        self.rx.recv().await
+    }
+}
+
+// A caller that stops early, on an error say, shouldn't leave the fetcher downloading
+impl Drop for PageStream {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// The page body and its share of the byte budget, which is taken before the body is read
+// when the response says how big it is
+#[tracing::instrument(skip(http_client, budget))]
+async fn fetch(
+    http_client: &ReqwestClient,
+    uri: &str,
+    budget: &Budget,
+) -> Result<(Bytes, Continuation, OwnedSemaphorePermit)> {
+    debug!("prefetching next_uri: {}", uri);
+    let response = http_client.get(uri).send().await?.error_for_status()?;
+    let reserved = match response.content_length() {
+        Some(len) => Some(budget.reserve(len as usize).await),
+        None => None,
+    };
+    let body = response.bytes().await?;
+    let permit = match reserved {
+        Some(permit) => permit,
+        None => budget.reserve(body.len()).await,
+    };
+    let continuation = serde_json::from_slice(&body)?;
+    Ok((body, continuation, permit))
+}
+
+struct Budget {
+    bytes: Arc<Semaphore>,
+    max: u32,
+}
+
+impl Budget {
+    fn new(max_bytes: usize) -> Self {
+        let max = u32::try_from(max_bytes.max(1)).unwrap_or(u32::MAX);
+        Budget {
+            bytes: Arc::new(Semaphore::new(max as usize)),
+            max,
+        }
+    }
+
+    // A single page larger than the whole budget still has to get through, so it takes
+    // every permit rather than waiting forever
+    async fn reserve(&self, len: usize) -> OwnedSemaphorePermit {
+        let wanted = u32::try_from(len).unwrap_or(u32::MAX).min(self.max);
+        self.bytes
+            .clone()
+            .acquire_many_owned(wanted)
+            .await
+            .expect("the byte budget is never closed")
+    }
+}
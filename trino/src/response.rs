@@ -1,6 +1,10 @@
+use std::fmt;
+
 use serde::Deserialize;
 use serde_json::Value;
 
+use crate::types::{ParseTypeError, TrinoType};
+
 #[derive(Debug, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Info {
@@ -16,17 +20,54 @@ pub struct NodeVersion {
     pub version: String,
 }
 
+// Rows default to `Value`, but can be left undecoded as `&RawValue` / `Box<RawValue>`
+// so typed callers go straight from the page bytes to `T`
 #[derive(Debug, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
-pub struct QueryResults {
+pub struct QueryResults<D = Value> {
     pub id: String,
     pub info_uri: String,
     pub partial_cancel_uri: Option<String>,
     pub next_uri: Option<String>,
     pub columns: Option<Vec<QueryResultColumn>>,
-    pub data: Option<Vec<Value>>,
+    pub data: Option<Vec<D>>,
     pub stats: QueryStats,
     pub warnings: Vec<String>,
+    // `INSERT`, `CREATE TABLE`... and the rows it changed, for statements other than queries
+    pub update_type: Option<String>,
+    pub update_count: Option<u64>,
+    pub error: Option<QueryError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QueryError {
+    pub message: String,
+    pub error_code: i64,
+    pub error_name: String,
+    pub error_type: String,
+    pub error_location: Option<ErrorLocation>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorLocation {
+    pub line_number: u32,
+    pub column_number: u32,
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.error_name, self.message)?;
+        if let Some(location) = &self.error_location {
+            write!(
+                f,
+                " (line {}, column {})",
+                location.line_number, location.column_number
+            )?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, PartialEq, Deserialize)]
@@ -51,6 +92,12 @@ pub struct QueryResultColumn {
     pub type_signature: ColumnTypeSignature,
 }
 
+impl QueryResultColumn {
+    pub fn trino_type(&self) -> Result<TrinoType, ParseTypeError> {
+        self.type_name.parse()
+    }
+}
+
 #[derive(Debug, PartialEq, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct QueryStats {
@@ -94,22 +141,27 @@ pub struct QueryStage {
     pub sub_stages: Vec<QueryStage>,
 }
 
-#[derive(Debug, PartialEq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum QueryState {
     Aborted,
+    Dispatching,
     Failed,
     Finished,
     Finishing,
     Flushing,
+    Planning,
     Queued,
     Running,
     Scheduled,
+    Starting,
+    WaitingForResources,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use serde_json::value::RawValue;
 
     #[test]
     fn deserialize_initial_response() {
@@ -172,10 +224,49 @@ mod tests {
                 progress_percentage: None,
             },
             warnings: vec![],
+            update_type: None,
+            update_count: None,
+            error: None,
         };
         assert_eq!(res, exp);
     }
 
+    #[test]
+    fn deserialize_raw_rows() {
+        let body = r#"
+            {
+              "id": "19991231_000000_00000_00000",
+              "infoUri": "http://localhost:8080/ui/query.html?19991231_000000_00000_00000",
+              "data": [[1, "ALGERIA"], [2, "ARGENTINA"]],
+              "stats": {
+                "state": "FINISHED",
+                "queued": false,
+                "scheduled": true,
+                "nodes": 1,
+                "totalSplits": 1,
+                "queuedSplits": 0,
+                "runningSplits": 0,
+                "completedSplits": 1,
+                "cpuTimeMillis": 1,
+                "wallTimeMillis": 1,
+                "queuedTimeMillis": 0,
+                "elapsedTimeMillis": 2,
+                "processedRows": 2,
+                "processedBytes": 32,
+                "physicalInputBytes": 0,
+                "peakMemoryBytes": 0,
+                "spilledBytes": 0
+              },
+              "warnings": []
+            }"#;
+        let res: QueryResults<&RawValue> = serde_json::from_str(body).unwrap();
+        let rows: Vec<&str> = res.data.unwrap().iter().map(|r| r.get()).collect();
+        assert_eq!(rows, vec![r#"[1, "ALGERIA"]"#, r#"[2, "ARGENTINA"]"#]);
+
+        let (id, name): (u32, String) = serde_json::from_str(rows[1]).unwrap();
+        assert_eq!((id, name.as_str()), (2, "ARGENTINA"));
+    }
+
     #[test]
     fn deserialize_info_response() {
         let res: Info = serde_json::from_str(
@@ -0,0 +1,514 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use data_encoding::BASE64;
+pub use serde_json::Value;
+
+use crate::error::{Error, Result};
+use crate::types::TrinoType;
+
+// A column as seen by `TrinoRow::bind`: top level query columns and the fields
+// of a nested `row(...)` are matched the same way
+#[derive(Debug, Clone, Copy)]
+pub struct ColumnRef<'a> {
+    pub name: Option<&'a str>,
+    pub r#type: &'a TrinoType,
+}
+
+// Conversion from a single JSON encoded Trino value
+pub trait FromTrino: Sized {
+    fn accepts(ty: &TrinoType) -> bool;
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self>;
+
+    // Only `Option` overrides this, everything else rejects NULL
+    fn from_null(ty: &TrinoType) -> Result<Self> {
+        Err(Error::Decode(format!("unexpected NULL for {}", ty)))
+    }
+}
+
+pub trait TrinoRow: Sized {
+    // Resolves each field to a column index, reporting every problem at once
+    fn bind(columns: &[ColumnRef<'_>]) -> std::result::Result<Vec<usize>, Vec<String>>;
+
+    fn from_row(row: &[Value], types: &[TrinoType], binding: &[usize]) -> Result<Self>;
+}
+
+// Trino folds unquoted identifiers to lower case, so names match case-insensitively, for
+// `#[derive(TrinoRow)]` and `query_as!` alike
+#[doc(hidden)]
+pub fn column_position(columns: &[ColumnRef<'_>], name: &str) -> Option<usize> {
+    columns.iter().position(|c| {
+        c.name
+            .map(|n| n.eq_ignore_ascii_case(name))
+            .unwrap_or(false)
+    })
+}
+
+// Used by the derive to look a field up by name or by position
+#[doc(hidden)]
+pub fn find_column<T: FromTrino>(
+    columns: &[ColumnRef<'_>],
+    field: &str,
+    name: Option<&str>,
+    position: Option<usize>,
+    rust_type: &str,
+    errors: &mut Vec<String>,
+) -> usize {
+    let found = match (name, position) {
+        (_, Some(position)) if position < columns.len() => Some(position),
+        (_, Some(position)) => {
+            errors.push(format!(
+                "field `{}` wants column {}, but only {} columns were returned",
+                field,
+                position,
+                columns.len()
+            ));
+            return 0;
+        }
+        (Some(name), None) => column_position(columns, name),
+        (None, None) => None,
+    };
+
+    match found {
+        Some(i) if T::accepts(columns[i].r#type) => i,
+        Some(i) => {
+            errors.push(format!(
+                "column `{}` has type {}, which can't be decoded into field `{}: {}`",
+                columns[i].name.unwrap_or("?"),
+                columns[i].r#type,
+                field,
+                rust_type
+            ));
+            i
+        }
+        None => {
+            let available: Vec<&str> = columns.iter().filter_map(|c| c.name).collect();
+            errors.push(format!(
+                "no column named `{}` for field `{}`, available columns are [{}]",
+                name.unwrap_or(field),
+                field,
+                available.join(", ")
+            ));
+            0
+        }
+    }
+}
+
+#[doc(hidden)]
+pub fn decode_field<T: FromTrino>(
+    row: &[Value],
+    types: &[TrinoType],
+    index: usize,
+    field: &str,
+) -> Result<T> {
+    let ty = &types[index];
+    let decoded = match row.get(index) {
+        None | Some(Value::Null) => T::from_null(ty),
+        Some(value) => T::from_trino(value, ty),
+    };
+    decoded.map_err(|e| Error::Decode(format!("field `{}`: {}", field, e)))
+}
+
+// Nested rows arrive as positional arrays, a derived struct implements `FromTrino`
+// by binding itself against the row's field list
+#[doc(hidden)]
+pub fn row_accepts<T: TrinoRow>(ty: &TrinoType) -> bool {
+    match ty {
+        TrinoType::Row(fields) => T::bind(&row_columns(fields)).is_ok(),
+        _ => false,
+    }
+}
+
+#[doc(hidden)]
+pub fn row_from_trino<T: TrinoRow>(value: &Value, ty: &TrinoType) -> Result<T> {
+    let fields = match ty {
+        TrinoType::Row(fields) => fields,
+        _ => return Err(mismatch(value, ty)),
+    };
+    let binding = T::bind(&row_columns(fields)).map_err(Error::ColumnMismatch)?;
+    let types: Vec<TrinoType> = fields.iter().map(|f| f.r#type.clone()).collect();
+    match value {
+        Value::Array(values) => T::from_row(values, &types, &binding),
+        _ => Err(mismatch(value, ty)),
+    }
+}
+
+fn row_columns(fields: &[crate::types::RowField]) -> Vec<ColumnRef<'_>> {
+    fields
+        .iter()
+        .map(|f| ColumnRef {
+            name: f.name.as_deref(),
+            r#type: &f.r#type,
+        })
+        .collect()
+}
+
+fn mismatch(value: &Value, ty: &TrinoType) -> Error {
+    Error::Decode(format!("can't decode {} as {}", value, ty))
+}
+
+impl<T: FromTrino> FromTrino for Option<T> {
+    fn accepts(ty: &TrinoType) -> bool {
+        T::accepts(ty)
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        T::from_trino(value, ty).map(Some)
+    }
+
+    fn from_null(_ty: &TrinoType) -> Result<Self> {
+        Ok(None)
+    }
+}
+
+impl FromTrino for Value {
+    fn accepts(_ty: &TrinoType) -> bool {
+        true
+    }
+
+    fn from_trino(value: &Value, _ty: &TrinoType) -> Result<Self> {
+        Ok(value.clone())
+    }
+
+    fn from_null(_ty: &TrinoType) -> Result<Self> {
+        Ok(Value::Null)
+    }
+}
+
+impl FromTrino for bool {
+    fn accepts(ty: &TrinoType) -> bool {
+        matches!(ty, TrinoType::Boolean)
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        value.as_bool().ok_or_else(|| mismatch(value, ty))
+    }
+}
+
+macro_rules! impl_from_trino_int {
+    ($t:ty, $($accepts:pat_param)|+) => {
+        impl FromTrino for $t {
+            fn accepts(ty: &TrinoType) -> bool {
+                matches!(ty, $($accepts)|+)
+            }
+
+            fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+                value
+                    .as_i64()
+                    .and_then(|v| <$t as std::convert::TryFrom<i64>>::try_from(v).ok())
+                    .ok_or_else(|| mismatch(value, ty))
+            }
+        }
+    };
+}
+
+impl_from_trino_int!(i8, TrinoType::TinyInt);
+impl_from_trino_int!(i16, TrinoType::TinyInt | TrinoType::SmallInt);
+impl_from_trino_int!(
+    i32,
+    TrinoType::TinyInt | TrinoType::SmallInt | TrinoType::Integer
+);
+impl_from_trino_int!(
+    i64,
+    TrinoType::TinyInt | TrinoType::SmallInt | TrinoType::Integer | TrinoType::BigInt
+);
+
+// Trino sends non-finite floating point values as strings
+fn float(value: &Value, ty: &TrinoType) -> Result<f64> {
+    match value {
+        Value::Number(n) => n.as_f64().ok_or_else(|| mismatch(value, ty)),
+        Value::String(s) => match s.as_str() {
+            "NaN" => Ok(f64::NAN),
+            "Infinity" => Ok(f64::INFINITY),
+            "-Infinity" => Ok(f64::NEG_INFINITY),
+            _ => Err(mismatch(value, ty)),
+        },
+        _ => Err(mismatch(value, ty)),
+    }
+}
+
+impl FromTrino for f32 {
+    fn accepts(ty: &TrinoType) -> bool {
+        matches!(ty, TrinoType::Real)
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        float(value, ty).map(|v| v as f32)
+    }
+}
+
+impl FromTrino for f64 {
+    fn accepts(ty: &TrinoType) -> bool {
+        matches!(ty, TrinoType::Real | TrinoType::Double)
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        float(value, ty)
+    }
+}
+
+// Everything Trino renders as a JSON string can be read back as text
+impl FromTrino for String {
+    fn accepts(ty: &TrinoType) -> bool {
+        !matches!(
+            ty,
+            TrinoType::Boolean
+                | TrinoType::TinyInt
+                | TrinoType::SmallInt
+                | TrinoType::Integer
+                | TrinoType::BigInt
+                | TrinoType::Real
+                | TrinoType::Double
+                | TrinoType::Array(_)
+                | TrinoType::Map(_, _)
+                | TrinoType::Row(_)
+        )
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        value
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| mismatch(value, ty))
+    }
+}
+
+impl FromTrino for Vec<u8> {
+    fn accepts(ty: &TrinoType) -> bool {
+        matches!(ty, TrinoType::Varbinary)
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        let encoded = value.as_str().ok_or_else(|| mismatch(value, ty))?;
+        BASE64
+            .decode(encoded.as_bytes())
+            .map_err(|e| Error::Decode(e.to_string()))
+    }
+}
+
+impl<T: FromTrino> FromTrino for Vec<T> {
+    fn accepts(ty: &TrinoType) -> bool {
+        match ty {
+            TrinoType::Array(element) => T::accepts(element),
+            _ => false,
+        }
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        let (values, element) = match (value, ty) {
+            (Value::Array(values), TrinoType::Array(element)) => (values, element),
+            _ => return Err(mismatch(value, ty)),
+        };
+        values
+            .iter()
+            .map(|v| match v {
+                Value::Null => T::from_null(element),
+                v => T::from_trino(v, element),
+            })
+            .collect()
+    }
+}
+
+impl<K, V> FromTrino for HashMap<K, V>
+where
+    K: FromTrino + Eq + Hash,
+    V: FromTrino,
+{
+    fn accepts(ty: &TrinoType) -> bool {
+        match ty {
+            TrinoType::Map(key, value) => K::accepts(key) && V::accepts(value),
+            _ => false,
+        }
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        let (entries, key_type, value_type) = match (value, ty) {
+            (Value::Object(entries), TrinoType::Map(k, v)) => (entries, k, v),
+            _ => return Err(mismatch(value, ty)),
+        };
+        entries
+            .iter()
+            .map(|(k, v)| {
+                // JSON object keys are always strings, numeric keys need re-parsing
+                let key = match key_type.as_ref() {
+                    TrinoType::Varchar(_) | TrinoType::Char(_) => Value::String(k.clone()),
+                    _ => serde_json::from_str(k).unwrap_or_else(|_| Value::String(k.clone())),
+                };
+                let value = match v {
+                    Value::Null => V::from_null(value_type)?,
+                    v => V::from_trino(v, value_type)?,
+                };
+                Ok((K::from_trino(&key, key_type)?, value))
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "chrono")]
+mod chrono_impls {
+    use chrono::{
+        DateTime, FixedOffset, NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Utc,
+    };
+    use serde_json::Value;
+
+    use super::{mismatch, FromTrino};
+    use crate::error::{Error, Result};
+    use crate::types::TrinoType;
+
+    fn text<'a>(value: &'a Value, ty: &TrinoType) -> Result<&'a str> {
+        value.as_str().ok_or_else(|| mismatch(value, ty))
+    }
+
+    fn decode_err(e: chrono::ParseError) -> Error {
+        Error::Decode(e.to_string())
+    }
+
+    impl FromTrino for NaiveDate {
+        fn accepts(ty: &TrinoType) -> bool {
+            matches!(ty, TrinoType::Date)
+        }
+
+        fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+            NaiveDate::parse_from_str(text(value, ty)?, "%Y-%m-%d").map_err(decode_err)
+        }
+    }
+
+    impl FromTrino for NaiveTime {
+        fn accepts(ty: &TrinoType) -> bool {
+            matches!(
+                ty,
+                TrinoType::Time {
+                    with_time_zone: false,
+                    ..
+                }
+            )
+        }
+
+        fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+            NaiveTime::parse_from_str(text(value, ty)?, "%H:%M:%S%.f").map_err(decode_err)
+        }
+    }
+
+    impl FromTrino for NaiveDateTime {
+        fn accepts(ty: &TrinoType) -> bool {
+            matches!(
+                ty,
+                TrinoType::Timestamp {
+                    with_time_zone: false,
+                    ..
+                }
+            )
+        }
+
+        fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+            NaiveDateTime::parse_from_str(text(value, ty)?, "%Y-%m-%d %H:%M:%S%.f")
+                .map_err(decode_err)
+        }
+    }
+
+    // Trino renders zones as `UTC`, a fixed `+HH:MM` offset or a region like
+    // `America/New_York`, whose offset at that local time is kept
+    impl FromTrino for DateTime<FixedOffset> {
+        fn accepts(ty: &TrinoType) -> bool {
+            matches!(
+                ty,
+                TrinoType::Timestamp {
+                    with_time_zone: true,
+                    ..
+                }
+            )
+        }
+
+        fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+            let s = text(value, ty)?;
+            let invalid = || Error::Decode(format!("invalid timestamp with time zone `{}`", s));
+            let (local, zone) = s.rsplit_once(' ').ok_or_else(invalid)?;
+            let local =
+                NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M:%S%.f").map_err(decode_err)?;
+            let offset = match zone {
+                "UTC" => Utc.fix(),
+                _ => match zone.parse::<FixedOffset>() {
+                    Ok(offset) => offset,
+                    Err(_) => {
+                        let tz: chrono_tz::Tz = zone.parse().map_err(|_| invalid())?;
+                        // ambiguous local times during DST changes take the earlier instant
+                        let dt = tz
+                            .from_local_datetime(&local)
+                            .earliest()
+                            .ok_or_else(invalid)?;
+                        return Ok(dt.with_timezone(&dt.offset().fix()));
+                    }
+                },
+            };
+            offset
+                .from_local_datetime(&local)
+                .single()
+                .ok_or_else(invalid)
+        }
+    }
+
+    impl FromTrino for DateTime<Utc> {
+        fn accepts(ty: &TrinoType) -> bool {
+            <DateTime<FixedOffset> as FromTrino>::accepts(ty)
+        }
+
+        fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+            <DateTime<FixedOffset> as FromTrino>::from_trino(value, ty)
+                .map(|dt| dt.with_timezone(&Utc))
+        }
+    }
+}
+
+#[cfg(feature = "bigdecimal")]
+impl FromTrino for bigdecimal::BigDecimal {
+    fn accepts(ty: &TrinoType) -> bool {
+        matches!(
+            ty,
+            TrinoType::Decimal { .. }
+                | TrinoType::TinyInt
+                | TrinoType::SmallInt
+                | TrinoType::Integer
+                | TrinoType::BigInt
+        )
+    }
+
+    fn from_trino(value: &Value, ty: &TrinoType) -> Result<Self> {
+        let text = match value {
+            Value::String(s) => s.clone(),
+            Value::Number(n) => n.to_string(),
+            _ => return Err(mismatch(value, ty)),
+        };
+        text.parse().map_err(|e| Error::Decode(format!("{}", e)))
+    }
+}
+
+#[cfg(all(test, feature = "chrono"))]
+mod tests {
+    use chrono::{DateTime, FixedOffset, TimeZone, Utc};
+    use serde_json::json;
+
+    use super::FromTrino;
+    use crate::types::TrinoType;
+
+    #[test]
+    fn timestamp_with_time_zone() {
+        let ty: TrinoType = "timestamp(3) with time zone".parse().unwrap();
+        let decode = |s: &str| DateTime::<Utc>::from_trino(&json!(s), &ty).unwrap();
+        let expected = Utc.with_ymd_and_hms(2024, 1, 1, 5, 0, 0).unwrap();
+
+        assert_eq!(decode("2024-01-01 05:00:00.000 UTC"), expected);
+        assert_eq!(decode("2024-01-01 00:00:00.000 -05:00"), expected);
+        assert_eq!(decode("2024-01-01 00:00:00.000 America/New_York"), expected);
+
+        let summer = DateTime::<FixedOffset>::from_trino(
+            &json!("2024-07-01 00:00:00.000 Europe/Paris"),
+            &ty,
+        )
+        .unwrap();
+        assert_eq!(summer.offset().local_minus_utc(), 2 * 3600);
+        assert!(
+            DateTime::<Utc>::from_trino(&json!("2024-01-01 00:00:00.000 Mars/Base"), &ty).is_err()
+        );
+    }
+}
@@ -0,0 +1,362 @@
+// SQL scripts of many statements, see `Client::execute_script`. Statements run one after
+// the other in one session: what `USE`, `SET SESSION`, `PREPARE` and `START TRANSACTION`
+// change is carried from each statement to the next through the protocol headers.
+use std::collections::BTreeMap;
+
+use reqwest::header::HeaderMap;
+use reqwest::RequestBuilder;
+
+use crate::error::{Error, Result};
+use crate::params::quoted_end;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OnError {
+    // the rest of the script is skipped, and a transaction the script started is rolled back
+    #[default]
+    Stop,
+    Continue,
+}
+
+// What a statement that ran reports back
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct StatementOutcome {
+    // `INSERT`, `CREATE TABLE`..., `None` for queries
+    pub update_type: Option<String>,
+    pub update_count: Option<u64>,
+    // the columns and number of rows a query returned, the rows themselves are dropped
+    pub columns: Vec<String>,
+    pub rows: u64,
+}
+
+// What `Client::execute` returns, like JDBC's `executeUpdate`: statements that don't
+// report a count, DDL mostly, count as 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecuteResult {
+    pub update_type: Option<String>,
+    pub update_count: u64,
+}
+
+#[derive(Debug)]
+pub struct StatementResult {
+    pub sql: String,
+    pub outcome: Result<StatementOutcome>,
+}
+
+#[derive(Debug)]
+pub struct ScriptReport {
+    // in script order, up to and including a failed one with `OnError::Stop`
+    pub statements: Vec<StatementResult>,
+    // statements not run after a failure
+    pub skipped: Vec<String>,
+    // the session as the script left it
+    pub session: Session,
+}
+
+impl ScriptReport {
+    pub fn is_ok(&self) -> bool {
+        self.statements.iter().all(|s| s.outcome.is_ok())
+    }
+
+    pub fn errors(&self) -> impl Iterator<Item = (&str, &Error)> {
+        self.statements
+            .iter()
+            .filter_map(|s| s.outcome.as_ref().err().map(|e| (s.sql.as_str(), e)))
+    }
+
+    // The outcome of every statement, or the first error
+    pub fn into_result(self) -> Result<Vec<StatementOutcome>> {
+        self.statements.into_iter().map(|s| s.outcome).collect()
+    }
+}
+
+// The session state the client has to send with every statement
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Session {
+    pub catalog: Option<String>,
+    pub schema: Option<String>,
+    pub path: Option<String>,
+    pub properties: BTreeMap<String, String>,
+    pub prepared_statements: BTreeMap<String, String>,
+    pub transaction_id: Option<String>,
+}
+
+impl Session {
+    pub(crate) fn apply(&mut self, headers: &HeaderMap) {
+        let values = |name: &str| {
+            headers
+                .get_all(name)
+                .iter()
+                .filter_map(|value| value.to_str().ok())
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+        let pair = |value: &str| {
+            let (name, value) = value.split_once('=')?;
+            let value = urlencoding::decode(value).ok()?.into_owned();
+            Some((name.trim().to_string(), value))
+        };
+
+        if let Some(catalog) = values("X-Trino-Set-Catalog").pop() {
+            self.catalog = Some(catalog);
+        }
+        if let Some(schema) = values("X-Trino-Set-Schema").pop() {
+            self.schema = Some(schema);
+        }
+        if let Some(path) = values("X-Trino-Set-Path").pop() {
+            self.path = Some(path);
+        }
+        self.properties
+            .extend(values("X-Trino-Set-Session").iter().filter_map(|v| pair(v)));
+        for name in values("X-Trino-Clear-Session") {
+            self.properties.remove(&name);
+        }
+        self.prepared_statements.extend(
+            values("X-Trino-Added-Prepare")
+                .iter()
+                .filter_map(|v| pair(v)),
+        );
+        for name in values("X-Trino-Deallocated-Prepare") {
+            self.prepared_statements.remove(&name);
+        }
+        if let Some(id) = values("X-Trino-Started-Transaction-Id").pop() {
+            self.transaction_id = Some(id);
+        }
+        if !values("X-Trino-Clear-Transaction-Id").is_empty() {
+            self.transaction_id = None;
+        }
+    }
+
+    pub(crate) fn headers(&self, mut rb: RequestBuilder) -> RequestBuilder {
+        let pairs = |map: &BTreeMap<String, String>| {
+            map.iter()
+                .map(|(name, value)| format!("{}={}", name, urlencoding::encode(value)))
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        if let Some(catalog) = &self.catalog {
+            rb = rb.header("X-Trino-Catalog", catalog);
+        }
+        if let Some(schema) = &self.schema {
+            rb = rb.header("X-Trino-Schema", schema);
+        }
+        if let Some(path) = &self.path {
+            rb = rb.header("X-Trino-Path", path);
+        }
+        if !self.properties.is_empty() {
+            rb = rb.header("X-Trino-Session", pairs(&self.properties));
+        }
+        if !self.prepared_statements.is_empty() {
+            rb = rb.header(
+                "X-Trino-Prepared-Statement",
+                pairs(&self.prepared_statements),
+            );
+        }
+        if let Some(id) = &self.transaction_id {
+            rb = rb.header("X-Trino-Transaction-Id", id);
+        }
+        rb
+    }
+}
+
+// Splits a script on the semicolons that end statements: not the ones in string literals,
+// quoted identifiers, comments, or `BEGIN ... END` bodies of routines. A `BEGIN` only
+// opens a body after a `FUNCTION ... RETURNS ...` header (`CREATE FUNCTION` or an inline
+// `WITH FUNCTION`) or inside another body, elsewhere it's just a name. Statements that are
+// empty or only comments are left out.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let chars: Vec<char> = sql.chars().collect();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut has_code = false;
+    // open `BEGIN` and `CASE` blocks, which close with `END`
+    let mut depth = 0usize;
+    let mut after_end = false;
+    // how far into a `FUNCTION ... RETURNS` routine header the statement is
+    let (mut function, mut returns) = (false, false);
+    let statement = |from: usize, to: usize| {
+        let statement: String = chars[from..to].iter().collect();
+        statement.trim().to_string()
+    };
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' => {
+                i = quoted_end(&chars, i, c);
+                has_code = true;
+                after_end = false;
+            }
+            '-' if chars.get(i + 1) == Some(&'-') => {
+                i = chars[i..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(chars.len(), |p| i + p);
+            }
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i = chars[i + 2..]
+                    .windows(2)
+                    .position(|w| w == ['*', '/'])
+                    .map_or(chars.len(), |p| i + 2 + p + 2);
+            }
+            ';' if depth == 0 => {
+                if has_code {
+                    statements.push(statement(start, i));
+                }
+                has_code = false;
+                function = false;
+                returns = false;
+                i += 1;
+                start = i;
+            }
+            c if c.is_ascii_alphabetic() || c == '_' => {
+                let end = word_end(&chars, i);
+                let word = word_at(&chars, i, end);
+                match word.as_str() {
+                    "FUNCTION" => function = true,
+                    "RETURNS" => returns = function,
+                    "BEGIN" if depth > 0 || returns => depth += 1,
+                    // `END CASE` closes the block its `END` already did
+                    "CASE" if !after_end => depth += 1,
+                    // `END IF`, `END LOOP`, `END WHILE` and `END REPEAT` close blocks that
+                    // aren't counted, `IF` being a function too
+                    "END" => {
+                        let next = chars[end..]
+                            .iter()
+                            .position(|c| !c.is_whitespace())
+                            .map_or(chars.len(), |p| end + p);
+                        let next = word_at(&chars, next, word_end(&chars, next));
+                        if !matches!(next.as_str(), "IF" | "LOOP" | "WHILE" | "REPEAT") {
+                            depth = depth.saturating_sub(1);
+                        }
+                    }
+                    _ => {}
+                }
+                after_end = word == "END";
+                has_code = true;
+                i = end;
+            }
+            c => {
+                has_code |= !c.is_whitespace();
+                i += 1;
+            }
+        }
+    }
+    if has_code {
+        statements.push(statement(start, chars.len()));
+    }
+    statements
+}
+
+fn word_end(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|c| !(c.is_ascii_alphanumeric() || *c == '_'))
+        .map_or(chars.len(), |p| start + p)
+}
+
+fn word_at(chars: &[char], start: usize, end: usize) -> String {
+    chars[start..end]
+        .iter()
+        .collect::<String>()
+        .to_ascii_uppercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn split() {
+        let script = "
+            -- create things; carefully
+            CREATE SCHEMA IF NOT EXISTS hive.web;
+            USE hive.web;;
+            /* a comment; */
+            INSERT INTO events VALUES ('a;b', 1), ('it''s; here', 2);
+            SELECT \"odd;name\", CASE WHEN x THEN 1 ELSE 2 END FROM t;
+            CREATE FUNCTION meaning()
+                RETURNS bigint
+                BEGIN
+                    DECLARE x bigint DEFAULT 42;
+                    IF x > 0 THEN
+                        RETURN CASE x WHEN 42 THEN x END;
+                    END IF;
+                    CASE WHEN true THEN RETURN 0; END CASE;
+                    RETURN x;
+                END;
+            SELECT begin FROM t;
+            -- trailing comment
+            SELECT 1
+        ";
+        let statements = split_statements(script);
+        assert_eq!(statements.len(), 7, "{:#?}", statements);
+        assert!(statements[0].starts_with("-- create things; carefully"));
+        assert!(statements[0].ends_with("CREATE SCHEMA IF NOT EXISTS hive.web"));
+        assert_eq!(statements[1], "USE hive.web");
+        assert!(statements[2].ends_with("('a;b', 1), ('it''s; here', 2)"));
+        assert!(statements[3].ends_with("ELSE 2 END FROM t"));
+        assert!(statements[4].starts_with("CREATE FUNCTION meaning()"));
+        assert!(statements[4].ends_with("RETURN x;\n                END"));
+        assert_eq!(statements[5], "SELECT begin FROM t");
+        assert_eq!(statements[6], "-- trailing comment\n            SELECT 1");
+
+        assert!(split_statements(" ;\n-- nothing\n; /* at all */").is_empty());
+    }
+
+    #[test]
+    fn session_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("X-Trino-Set-Catalog", HeaderValue::from_static("hive"));
+        headers.insert("X-Trino-Set-Schema", HeaderValue::from_static("web"));
+        headers.append(
+            "X-Trino-Set-Session",
+            HeaderValue::from_static("query_max_run_time=1%20h"),
+        );
+        headers.append(
+            "X-Trino-Set-Session",
+            HeaderValue::from_static("join_distribution_type=BROADCAST"),
+        );
+        headers.insert(
+            "X-Trino-Added-Prepare",
+            HeaderValue::from_static("q=SELECT%20%3F"),
+        );
+        headers.insert(
+            "X-Trino-Started-Transaction-Id",
+            HeaderValue::from_static("abc"),
+        );
+
+        let mut session = Session::default();
+        session.apply(&headers);
+        assert_eq!(session.catalog.as_deref(), Some("hive"));
+        assert_eq!(session.properties["query_max_run_time"], "1 h");
+        assert_eq!(session.prepared_statements["q"], "SELECT ?");
+        assert_eq!(session.transaction_id.as_deref(), Some("abc"));
+
+        let request = session
+            .headers(reqwest::Client::new().get("http://localhost"))
+            .build()
+            .unwrap();
+        assert_eq!(
+            request.headers()["X-Trino-Session"],
+            "join_distribution_type=BROADCAST,query_max_run_time=1%20h"
+        );
+        assert_eq!(request.headers()["X-Trino-Schema"], "web");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            "X-Trino-Clear-Session",
+            HeaderValue::from_static("query_max_run_time"),
+        );
+        headers.insert("X-Trino-Deallocated-Prepare", HeaderValue::from_static("q"));
+        headers.insert(
+            "X-Trino-Clear-Transaction-Id",
+            HeaderValue::from_static("true"),
+        );
+        session.apply(&headers);
+        assert_eq!(session.properties.len(), 1);
+        assert!(session.prepared_statements.is_empty());
+        assert_eq!(session.transaction_id, None);
+    }
+}
@@ -7,6 +7,7 @@ use serde_json::Value;
 async fn test_query_typed() {
     common::initialize().await;
 
+    #[allow(dead_code)]
     #[derive(Debug, Deserialize)]
     struct Nation {
         nationkey: u32,
@@ -15,7 +16,7 @@ async fn test_query_typed() {
         comment: String,
     }
 
-    let mut cb = trino::ClientBuilder::default()
+    let cb = trino::ClientBuilder::default()
         .base_url("http://localhost")
         .port(8080)
         .user("user");
@@ -32,7 +33,7 @@ async fn test_query_typed() {
 async fn test_query_untyped() {
     common::initialize().await;
 
-    let mut cb = trino::ClientBuilder::default()
+    let cb = trino::ClientBuilder::default()
         .base_url("http://localhost")
         .port(8080)
         .user("user");
@@ -0,0 +1,141 @@
+// A minimal stand-in for the statement protocol, serving a fixed number of pages
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+
+pub struct MockTrino {
+    pub base_url: String,
+    pub port: u32,
+}
+
+impl MockTrino {
+    pub fn client(&self, prefetch: trino::PrefetchConfig) -> trino::Client {
+        trino::ClientBuilder::new()
+            .base_url(&self.base_url)
+            .port(self.port)
+            .user("user")
+            .prefetch(prefetch)
+            .build()
+    }
+}
+
+fn stats() -> Value {
+    json!({
+        "state": "RUNNING",
+        "queued": false,
+        "scheduled": true,
+        "nodes": 1,
+        "totalSplits": 0,
+        "queuedSplits": 0,
+        "runningSplits": 0,
+        "completedSplits": 0,
+        "cpuTimeMillis": 0,
+        "wallTimeMillis": 0,
+        "queuedTimeMillis": 0,
+        "elapsedTimeMillis": 0,
+        "processedRows": 0,
+        "processedBytes": 0,
+        "physicalInputBytes": 0,
+        "peakMemoryBytes": 0,
+        "spilledBytes": 0
+    })
+}
+
+fn columns() -> Value {
+    let column = |name: &str, ty: &str| {
+        json!({
+            "name": name,
+            "type": ty,
+            "typeSignature": { "rawType": ty, "arguments": [] }
+        })
+    };
+    json!([
+        column("id", "bigint"),
+        column("name", "varchar"),
+        column("score", "double"),
+        column("comment", "varchar")
+    ])
+}
+
+fn page(addr: SocketAddr, page: usize, pages: usize, rows_per_page: usize) -> String {
+    let next_uri = if page < pages {
+        Value::String(format!(
+            "http://{}/v1/statement/executing/mock/{}",
+            addr,
+            page + 1
+        ))
+    } else {
+        Value::Null
+    };
+    let data: Vec<Value> = if page == 0 {
+        vec![]
+    } else {
+        (0..rows_per_page)
+            .map(|i| {
+                let id = (page - 1) * rows_per_page + i;
+                json!([
+                    id,
+                    format!("name-{}", id),
+                    id as f64 * 0.5,
+                    "a fairly ordinary comment"
+                ])
+            })
+            .collect()
+    };
+    json!({
+        "id": "mock",
+        "infoUri": format!("http://{}/ui/query.html?mock", addr),
+        "nextUri": next_uri,
+        "columns": if page == 0 { Value::Null } else { columns() },
+        "data": if page == 0 { Value::Null } else { Value::Array(data) },
+        "stats": stats(),
+        "warnings": []
+    })
+    .to_string()
+}
+
+// Pages are rendered up front so the server only pays for `latency`
+pub async fn start(pages: usize, rows_per_page: usize, latency: Duration) -> MockTrino {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let bodies: Arc<Vec<String>> = Arc::new(
+        (0..=pages)
+            .map(|p| page(addr, p, pages, rows_per_page))
+            .collect(),
+    );
+
+    let make_svc = make_service_fn(move |_| {
+        let bodies = bodies.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let bodies = bodies.clone();
+                async move {
+                    let page: usize = req
+                        .uri()
+                        .path()
+                        .rsplit('/')
+                        .next()
+                        .and_then(|p| p.parse().ok())
+                        .unwrap_or(0);
+                    if page > 0 {
+                        tokio::time::sleep(latency).await;
+                    }
+                    Ok::<_, Infallible>(Response::new(Body::from(bodies[page].clone())))
+                }
+            }))
+        }
+    });
+
+    let server = Server::from_tcp(listener).unwrap().serve(make_svc);
+    tokio::spawn(server);
+
+    MockTrino {
+        base_url: format!("http://{}", addr.ip()),
+        port: addr.port() as u32,
+    }
+}
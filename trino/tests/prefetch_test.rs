@@ -0,0 +1,46 @@
+mod mock;
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use trino::PrefetchConfig;
+
+#[allow(dead_code)]
+#[derive(Debug, Deserialize)]
+struct Row {
+    id: u64,
+    name: String,
+    score: f64,
+    comment: String,
+}
+
+#[tokio::test]
+async fn test_prefetch_preserves_page_order() {
+    let server = mock::start(8, 50, Duration::from_millis(5)).await;
+
+    for max_pages in [1, 4] {
+        let client = server.client(PrefetchConfig {
+            max_pages,
+            ..PrefetchConfig::default()
+        });
+        let res: Vec<Row> = client.query("SELECT * FROM mock").await.unwrap();
+
+        assert_eq!(res.len(), 400);
+        for (i, row) in res.iter().enumerate() {
+            assert_eq!(row.id, i as u64);
+            assert_eq!(row.name, format!("name-{}", i));
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_prefetch_page_larger_than_byte_budget() {
+    let server = mock::start(3, 100, Duration::from_millis(1)).await;
+    let client = server.client(PrefetchConfig {
+        max_pages: 4,
+        max_bytes: 16,
+    });
+
+    let res: Vec<Row> = client.query("SELECT * FROM mock").await.unwrap();
+    assert_eq!(res.len(), 300);
+}
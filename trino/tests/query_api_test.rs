@@ -0,0 +1,53 @@
+use std::convert::Infallible;
+use std::sync::Mutex;
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server, StatusCode};
+
+static PATHS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// Records the raw path of every request; there are no queries to look up
+async fn respond(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    PATHS.lock().unwrap().push(req.uri().path().to_string());
+    let status = if req.method() == hyper::Method::GET {
+        StatusCode::NOT_FOUND
+    } else {
+        StatusCode::OK
+    };
+    Ok(Response::builder()
+        .status(status)
+        .body(Body::empty())
+        .unwrap())
+}
+
+fn start() -> trino::Client {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(respond)) });
+    tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_svc));
+    trino::ClientBuilder::new()
+        .base_url(&format!("http://{}", addr.ip()))
+        .port(addr.port() as u32)
+        .user("user")
+        .build()
+}
+
+#[tokio::test]
+async fn test_query_id_is_encoded() {
+    let client = start();
+    let id = "../resourceGroupState/global";
+
+    assert!(client.query_info(id).await.is_err());
+    client.kill_query(id, None).await.unwrap();
+    client.kill_query(id, Some("too slow")).await.unwrap();
+
+    let encoded = "/v1/query/..%2FresourceGroupState%2Fglobal";
+    assert_eq!(
+        *PATHS.lock().unwrap(),
+        vec![
+            encoded.to_string(),
+            encoded.to_string(),
+            format!("{}/killed", encoded),
+        ]
+    );
+}
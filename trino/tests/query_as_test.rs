@@ -0,0 +1,30 @@
+mod mock;
+
+use std::time::Duration;
+
+use trino::PrefetchConfig;
+
+#[derive(Debug)]
+struct MockRow {
+    id: i64,
+    name: String,
+    score: Option<f64>,
+    comment: String,
+}
+
+// Checked against the committed `.trino/` cache entry for this query
+#[tokio::test]
+async fn test_query_as_offline() {
+    let server = mock::start(2, 10, Duration::from_millis(1)).await;
+    let client = server.client(PrefetchConfig::default());
+
+    let query = trino::query_as!(MockRow, "SELECT * FROM mock");
+    let rows = query.fetch_all(&client).await.unwrap();
+
+    assert_eq!(query.sql(), "SELECT * FROM mock");
+    assert_eq!(rows.len(), 20);
+    assert_eq!(rows[3].id, 3);
+    assert_eq!(rows[3].name, "name-3");
+    assert_eq!(rows[3].score, Some(1.5));
+    assert_eq!(rows[3].comment, "a fairly ordinary comment");
+}
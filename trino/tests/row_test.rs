@@ -0,0 +1,125 @@
+use std::collections::HashMap;
+
+use trino::row::{ColumnRef, FromTrino, TrinoRow, Value};
+use trino::types::TrinoType;
+
+#[derive(Debug, PartialEq, trino::TrinoRow)]
+struct Point {
+    x: f64,
+    y: f64,
+}
+
+#[derive(Debug, PartialEq, trino::TrinoRow)]
+struct Nation {
+    nationkey: i64,
+    #[trino(rename = "name")]
+    nation_name: String,
+    comment: Option<String>,
+    tags: Vec<String>,
+    attributes: HashMap<String, i32>,
+    location: Option<Point>,
+    r#type: Vec<u8>,
+}
+
+#[derive(Debug, PartialEq, trino::TrinoRow)]
+struct Pair(i32, String);
+
+fn columns(spec: &[(&str, &str)]) -> (Vec<String>, Vec<TrinoType>) {
+    (
+        spec.iter().map(|(n, _)| n.to_string()).collect(),
+        spec.iter().map(|(_, t)| t.parse().unwrap()).collect(),
+    )
+}
+
+fn refs<'a>(names: &'a [String], types: &'a [TrinoType]) -> Vec<ColumnRef<'a>> {
+    names
+        .iter()
+        .zip(types)
+        .map(|(n, t)| ColumnRef {
+            name: Some(n),
+            r#type: t,
+        })
+        .collect()
+}
+
+#[test]
+fn test_trino_row_by_name() {
+    let (names, types) = columns(&[
+        ("type", "varbinary"),
+        ("location", "row(y double, x double)"),
+        ("nationkey", "bigint"),
+        ("name", "varchar(25)"),
+        ("comment", "varchar"),
+        ("tags", "array(varchar)"),
+        ("attributes", "map(varchar, integer)"),
+    ]);
+    let binding = Nation::bind(&refs(&names, &types)).unwrap();
+
+    let row: Vec<Value> = serde_json::from_str(
+        r#"["REVBREJFRUY=", [2.0, 1.0], 7, "GERMANY", null, ["a", "b"], {"k": 3}]"#,
+    )
+    .unwrap();
+    let nation = Nation::from_row(&row, &types, &binding).unwrap();
+
+    assert_eq!(
+        nation,
+        Nation {
+            nationkey: 7,
+            nation_name: "GERMANY".to_string(),
+            comment: None,
+            tags: vec!["a".to_string(), "b".to_string()],
+            attributes: vec![("k".to_string(), 3)].into_iter().collect(),
+            location: Some(Point { x: 1.0, y: 2.0 }),
+            r#type: b"DEADBEEF".to_vec(),
+        }
+    );
+}
+
+#[test]
+fn test_trino_row_by_position() {
+    let (names, types) = columns(&[("a", "integer"), ("b", "varchar")]);
+    let binding = Pair::bind(&refs(&names, &types)).unwrap();
+    let row: Vec<Value> = serde_json::from_str(r#"[1, "one"]"#).unwrap();
+
+    assert_eq!(
+        Pair::from_row(&row, &types, &binding).unwrap(),
+        Pair(1, "one".to_string())
+    );
+    assert!(<Option<Point> as FromTrino>::accepts(
+        &"row(x double, y real)".parse().unwrap()
+    ));
+}
+
+#[test]
+fn test_trino_row_mismatch() {
+    let (names, types) = columns(&[("nationkey", "varchar"), ("name", "varchar")]);
+    let errors = Nation::bind(&refs(&names, &types)).unwrap_err();
+
+    assert_eq!(errors.len(), 6);
+    assert_eq!(
+        errors[0],
+        "column `nationkey` has type varchar, which can't be decoded into field `nationkey: i64`"
+    );
+    assert_eq!(
+        errors[1],
+        "no column named `comment` for field `comment`, available columns are [nationkey, name]"
+    );
+
+    let (names, types) = columns(&[("a", "integer"), ("b", "varchar")]);
+    let row: Vec<Value> = serde_json::from_str(r#"[null, "one"]"#).unwrap();
+    let binding = Pair::bind(&refs(&names, &types)).unwrap();
+    assert!(Pair::from_row(&row, &types, &binding).is_err());
+}
+
+// `query_as!` binds columns the same way the derive does
+#[test]
+fn test_bind_columns_ignores_case() {
+    let (names, types) = columns(&[("NationKey", "bigint"), ("name", "varchar")]);
+    let refs = refs(&names, &types);
+
+    assert_eq!(
+        trino::macros::bind_columns(&refs, &["name", "nationkey"]),
+        Ok(vec![1, 0])
+    );
+    assert!(trino::macros::bind_columns(&refs, &["comment"]).is_err());
+}
@@ -0,0 +1,193 @@
+use std::convert::Infallible;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Request, Response, Server};
+use serde_json::{json, Value};
+use trino::script::OnError;
+
+static CANCELLED: AtomicUsize = AtomicUsize::new(0);
+
+// Answers every statement in one page: `USE` sets the schema, `INSERT` only works once it's
+// set, and `FAIL` fails. `FETCH` returns columns and a next page, which fails like any
+// request without a statement. DELETEs, which cancel queries, are counted.
+async fn respond(req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() == hyper::Method::DELETE {
+        CANCELLED.fetch_add(1, Ordering::SeqCst);
+        return Ok(Response::new(Body::empty()));
+    }
+    let schema = req
+        .headers()
+        .get("X-Trino-Schema")
+        .map(|v| v.to_str().unwrap().to_string());
+    let host = req.headers()["host"].to_str().unwrap().to_string();
+    let sql = String::from_utf8(
+        hyper::body::to_bytes(req.into_body())
+            .await
+            .unwrap()
+            .to_vec(),
+    )
+    .unwrap();
+
+    let mut body = json!({
+        "id": "mock",
+        "infoUri": "http://localhost/ui/query.html?mock",
+        "stats": {
+            "state": "FINISHED",
+            "queued": false,
+            "scheduled": true,
+            "nodes": 1,
+            "totalSplits": 0,
+            "queuedSplits": 0,
+            "runningSplits": 0,
+            "completedSplits": 0,
+            "cpuTimeMillis": 0,
+            "wallTimeMillis": 0,
+            "queuedTimeMillis": 0,
+            "elapsedTimeMillis": 0,
+            "processedRows": 0,
+            "processedBytes": 0,
+            "physicalInputBytes": 0,
+            "peakMemoryBytes": 0,
+            "spilledBytes": 0
+        },
+        "warnings": []
+    });
+    let mut response = Response::builder();
+    if sql.starts_with("USE") {
+        response = response.header("X-Trino-Set-Schema", "web");
+        body["updateType"] = json!("USE");
+    } else if sql.starts_with("INSERT") && schema.as_deref() == Some("web") {
+        body["updateType"] = json!("INSERT");
+        body["updateCount"] = json!(3);
+    } else if sql.starts_with("START TRANSACTION") {
+        response = response.header("X-Trino-Started-Transaction-Id", "tx");
+        body["updateType"] = json!("START TRANSACTION");
+    } else if sql == "ROLLBACK" {
+        response = response.header("X-Trino-Clear-Transaction-Id", "true");
+        body["updateType"] = json!("ROLLBACK");
+    } else if sql.starts_with("CREATE") {
+        body["updateType"] = json!("CREATE TABLE");
+    } else if sql.starts_with("SELECT") {
+        body["columns"] = json!([{
+            "name": "_col0",
+            "type": "integer",
+            "typeSignature": { "rawType": "integer", "arguments": [] }
+        }]);
+        body["data"] = json!([[1], [2]]);
+    } else if sql.starts_with("FETCH") {
+        body["columns"] = json!([{
+            "name": "_col0",
+            "type": "integer",
+            "typeSignature": { "rawType": "integer", "arguments": [] }
+        }]);
+        body["nextUri"] = json!(format!("http://{}/v1/statement/executing/mock/1", host));
+    } else {
+        body["error"] = json!({
+            "message": format!("can't run {}", sql),
+            "errorCode": 1,
+            "errorName": "GENERIC_USER_ERROR",
+            "errorType": "USER_ERROR",
+            "errorLocation": { "lineNumber": 1, "columnNumber": 1 }
+        });
+    }
+    Ok(response.body(Body::from(Value::to_string(&body))).unwrap())
+}
+
+fn start() -> trino::Client {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let make_svc = make_service_fn(|_| async { Ok::<_, Infallible>(service_fn(respond)) });
+    tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_svc));
+    trino::ClientBuilder::new()
+        .base_url(&format!("http://{}", addr.ip()))
+        .port(addr.port() as u32)
+        .user("user")
+        .build()
+}
+
+#[tokio::test]
+async fn test_execute_script() {
+    let client = start();
+    let script = "USE hive.web; INSERT INTO t VALUES (1), (2), (3); SELECT 1; FAIL; SELECT 2";
+
+    let report = client.execute_script(script, OnError::Stop).await;
+    assert!(!report.is_ok());
+    assert_eq!(report.statements.len(), 4);
+    assert_eq!(report.skipped, vec!["SELECT 2"]);
+    assert_eq!(report.session.schema.as_deref(), Some("web"));
+
+    let insert = report.statements[1].outcome.as_ref().unwrap();
+    assert_eq!(insert.update_type.as_deref(), Some("INSERT"));
+    assert_eq!(insert.update_count, Some(3));
+    let select = report.statements[2].outcome.as_ref().unwrap();
+    assert_eq!((select.columns.len(), select.rows), (1, 2));
+
+    let errors: Vec<_> = report.errors().collect();
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].0, "FAIL");
+    assert_eq!(
+        errors[0].1.to_string(),
+        "query failed: GENERIC_USER_ERROR: can't run FAIL (line 1, column 1)"
+    );
+
+    let report = client.execute_script(script, OnError::Continue).await;
+    assert_eq!(report.statements.len(), 5);
+    assert!(report.skipped.is_empty());
+    assert!(report.into_result().is_err());
+
+    let report = client
+        .execute_script("START TRANSACTION; FAIL; COMMIT", OnError::Stop)
+        .await;
+    assert_eq!(report.skipped, vec!["COMMIT"]);
+    assert_eq!(report.session.transaction_id, None);
+}
+
+#[tokio::test]
+async fn test_execute() {
+    let client = start();
+
+    // without the schema from a `USE`, the mock fails inserts
+    assert!(matches!(
+        client.execute("INSERT INTO t VALUES (1)").await,
+        Err(trino::Error::Query(_))
+    ));
+    let created = client.execute("CREATE TABLE t (id int)").await.unwrap();
+    assert_eq!(created.update_type.as_deref(), Some("CREATE TABLE"));
+    assert_eq!(created.update_count, 0);
+    assert!(matches!(
+        client.execute("SELECT 1").await,
+        Err(trino::Error::NotAnUpdate(_))
+    ));
+
+    // a query is cancelled on its first page with columns instead of being read through
+    assert!(matches!(
+        client.execute("FETCH").await,
+        Err(trino::Error::NotAnUpdate(_))
+    ));
+    assert_eq!(CANCELLED.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_query_error() {
+    let client = start();
+
+    let error = client.query::<(i32,)>("FAIL").await.unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "query failed: GENERIC_USER_ERROR: can't run FAIL (line 1, column 1)"
+    );
+    // the query fails on a later page
+    assert!(matches!(
+        client.query::<(i32,)>("FETCH").await,
+        Err(trino::Error::Query(_))
+    ));
+    assert!(matches!(
+        client.query_once::<Value>("FETCH").await,
+        Err(trino::Error::Query(_))
+    ));
+    assert_eq!(
+        client.query::<(i32,)>("SELECT 1").await.unwrap(),
+        vec![(1,), (2,)]
+    );
+}
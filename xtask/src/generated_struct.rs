@@ -1,30 +1,31 @@
 use serde::Deserialize;
-use trino_codegen::utils::array;
-use trino_codegen::utils::binary;
-use trino_codegen::utils::dates;
 #[derive(Debug, Deserialize)]
 pub struct Generated_structQueryResult {
-    pub expr: Option<i32>,
-    pub expr_0: Option<i16>,
-    pub expr_1: Option<i8>,
-    pub expr_2: Option<bigdecimal::BigDecimal>,
-    pub expr_3: Option<f32>,
-    pub expr_4: Option<f64>,
-    pub expr_5: Option<bool>,
-    #[serde(deserialize_with = "dates::from_str_to_naive_date")]
-    pub expr_6: Option<chrono::NaiveDate>,
-    #[serde(deserialize_with = "dates::from_str_to_naive_datetime")]
-    pub expr_7: Option<chrono::NaiveDateTime>,
-    pub expr_8: Option<String>,
-    #[serde(deserialize_with = "dates::from_str_to_naive_time")]
-    pub expr_9: Option<chrono::NaiveTime>,
-    pub expr_10: Option<String>,
-    pub expr_11: Option<String>,
-    #[serde(deserialize_with = "binary::from_base64")]
-    pub expr_12: Option<Vec<u8>>,
-    pub expr_13: Option<serde_json::Value>,
-    #[serde(deserialize_with = "array::from_homogeneous_array")]
-    pub expr_14: Option<Vec<array::Element>>,
-    pub map: Option<std::collections::HashMap<String, serde_json::Value>>,
-    pub expr_15: Option<uuid::Uuid>,
+    pub integer_type: Option<i32>,
+    pub smallint_type: Option<i16>,
+    pub tinyint_type: Option<i8>,
+    pub decimal_type: Option<bigdecimal::BigDecimal>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub real_type: Option<f32>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub double_type: Option<f64>,
+    pub boolean_type: Option<bool>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub date_type: Option<chrono::NaiveDate>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub timestamp_type: Option<chrono::NaiveDateTime>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub timestamptz_type: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub time_type: Option<chrono::NaiveTime>,
+    pub char_type: Option<String>,
+    pub varchar_type: Option<String>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub varbinary_type: Option<Vec<u8>>,
+    pub json_type: Option<serde_json::Value>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub array_int_type: Option<Vec<Option<i32>>>,
+    #[serde(deserialize_with = "trino_codegen::utils::de::deserialize")]
+    pub map_varchar_int_type: Option<std::collections::HashMap<String, Option<i32>>>,
+    pub uuid_type: Option<uuid::Uuid>,
 }
@@ -1 +1,2 @@
+#[allow(non_camel_case_types)]
 pub mod generated_struct;
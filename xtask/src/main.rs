@@ -1,10 +1,11 @@
 // xtask/src/lib.rs
+use std::env;
 use std::fs::File;
 use std::io::Write;
 use std::path::Path;
 use std::process::Command;
-use std::{env, fs, path::PathBuf, process::Stdio};
 
+use trino_codegen::config::Config;
 use trino_codegen::explain::ExplainRoot;
 use trino_codegen::utils::generate_struct;
 
@@ -13,25 +14,19 @@ pub fn test_generated_struct() -> Result<(), Box<dyn std::error::Error>> {
 
     let root: ExplainRoot =
         serde_json::from_str(include_str!("../../trino-codegen/data/explain2.json")).unwrap();
-    let data = root.outputs;
 
     let path = Path::new("src/generated_struct.rs");
     let mut file = File::create(path)?;
 
-    let imports = vec![
-        "use serde::Deserialize;\n",
-        "use trino_codegen::utils::dates;\n",
-        "use trino_codegen::utils::binary;\n",
-        "use trino_codegen::utils::array;\n",
-    ];
+    let imports = vec!["use serde::Deserialize;\n"];
 
     for import in &imports {
         file.write_all(import.as_bytes()).unwrap();
     }
 
-    let generated_struct = generate_struct(&data, path);
+    let generated_struct = generate_struct(&root, path, &[], &Config::default());
 
-    let _ = file.write(generated_struct.to_string().as_bytes());
+    file.write_all(generated_struct.to_string().as_bytes())?;
 
     // Compile the code
     let status = Command::new("cargo").arg("build").status()?;
@@ -46,7 +41,7 @@ pub fn test_generated_struct() -> Result<(), Box<dyn std::error::Error>> {
 
     // Run the test
     let status = Command::new("cargo")
-        .args(&["test", "--test", "test_generated_struct"])
+        .args(["test", "--test", "test_generated_struct"])
         .status()?;
     if !status.success() {
         return Err("Test failed".into());
@@ -27,5 +27,5 @@ fn test_generated_struct() {
             "123e4567-e89b-12d3-a456-426614174000"
         ]
         "#;
-    let res: Generated_structQueryResult = serde_json::from_str(json_string).unwrap();
+    let _res: Generated_structQueryResult = serde_json::from_str(json_string).unwrap();
 }